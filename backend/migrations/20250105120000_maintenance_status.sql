@@ -0,0 +1,18 @@
+-- Lifecycle status for maintenance bookings
+ALTER TABLE maintenance ADD COLUMN status TEXT NOT NULL DEFAULT 'SCHEDULED';
+
+-- Every status change a booking goes through
+CREATE TABLE maintenance_status_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    maintenance_id INTEGER NOT NULL REFERENCES maintenance(id) ON DELETE CASCADE,
+    from_status TEXT,
+    to_status TEXT NOT NULL,
+    note TEXT,
+    changed_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_maintenance_status_history_maintenance_id
+    ON maintenance_status_history (maintenance_id);
+
+INSERT INTO maintenance_status_history (maintenance_id, from_status, to_status)
+SELECT id, NULL, status FROM maintenance;
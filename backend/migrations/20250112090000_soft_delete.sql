@@ -0,0 +1,4 @@
+-- Soft deletion: rows are flagged instead of removed so history is kept
+ALTER TABLE cars ADD COLUMN deleted_at TEXT;
+ALTER TABLE garages ADD COLUMN deleted_at TEXT;
+ALTER TABLE maintenance ADD COLUMN deleted_at TEXT;
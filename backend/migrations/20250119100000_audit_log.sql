@@ -0,0 +1,14 @@
+-- Record of every create/update/delete across cars, garages and maintenance
+CREATE TABLE audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    actor TEXT NOT NULL,
+    resource TEXT NOT NULL,
+    resource_id TEXT NOT NULL,
+    action TEXT NOT NULL,
+    before_json TEXT,
+    after_json TEXT,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_audit_log_resource_created_at ON audit_log (resource, created_at);
+CREATE INDEX idx_audit_log_resource_id ON audit_log (resource, resource_id);
@@ -0,0 +1,19 @@
+-- Recurring maintenance attached to a car
+CREATE TABLE maintenance_plans (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    car_id INTEGER NOT NULL REFERENCES cars(id),
+    garage_id INTEGER NOT NULL REFERENCES garages(id), -- preferred garage
+    service_type TEXT NOT NULL,
+    interval_days INTEGER,
+    interval_months INTEGER,
+    interval_km INTEGER,
+    start_date TEXT NOT NULL,
+    next_due_date TEXT NOT NULL, -- nominal date of the next occurrence to book
+    active INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_maintenance_plans_car_id ON maintenance_plans (car_id);
+
+-- Bookings created by the scheduler point back at their plan
+ALTER TABLE maintenance ADD COLUMN plan_id INTEGER REFERENCES maintenance_plans(id);
@@ -0,0 +1,11 @@
+-- Odometer readings taken for a car, optionally while it was being serviced
+CREATE TABLE odometer_readings (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    car_id INTEGER NOT NULL REFERENCES cars(id),
+    reading_km INTEGER NOT NULL,
+    recorded_on TEXT NOT NULL,
+    maintenance_id INTEGER REFERENCES maintenance(id),
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_odometer_readings_car_id_recorded_on ON odometer_readings (car_id, recorded_on);
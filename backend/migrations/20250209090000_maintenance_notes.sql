@@ -0,0 +1,2 @@
+-- Free-form notes from the garage about a booking
+ALTER TABLE maintenance ADD COLUMN notes TEXT;
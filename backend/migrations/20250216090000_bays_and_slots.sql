@@ -0,0 +1,28 @@
+-- Opening hours and booking granularity per garage
+ALTER TABLE garages ADD COLUMN opening_time TEXT NOT NULL DEFAULT '08:00';
+ALTER TABLE garages ADD COLUMN closing_time TEXT NOT NULL DEFAULT '18:00';
+ALTER TABLE garages ADD COLUMN slot_minutes INTEGER NOT NULL DEFAULT 60;
+
+-- Workshop bays; a garage without bays keeps the per-day capacity model
+CREATE TABLE bays (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    garage_id INTEGER NOT NULL REFERENCES garages(id),
+    name TEXT NOT NULL,
+    active INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_bays_garage_id ON bays (garage_id);
+
+-- Catalogue of services and how long they keep a bay busy
+CREATE TABLE service_types (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    duration_minutes INTEGER NOT NULL
+);
+
+ALTER TABLE maintenance ADD COLUMN bay_id INTEGER REFERENCES bays(id);
+ALTER TABLE maintenance ADD COLUMN start_time TEXT; -- HH:MM
+ALTER TABLE maintenance ADD COLUMN duration_minutes INTEGER;
+
+CREATE INDEX idx_maintenance_garage_id_scheduled_date ON maintenance (garage_id, scheduled_date);
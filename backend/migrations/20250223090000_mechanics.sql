@@ -0,0 +1,14 @@
+-- Staff working in a garage
+CREATE TABLE mechanics (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    garage_id INTEGER NOT NULL REFERENCES garages(id),
+    name TEXT NOT NULL,
+    active INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_mechanics_garage_id ON mechanics (garage_id);
+
+ALTER TABLE maintenance ADD COLUMN mechanic_id INTEGER REFERENCES mechanics(id);
+
+CREATE INDEX idx_maintenance_mechanic_id_scheduled_date ON maintenance (mechanic_id, scheduled_date);
@@ -0,0 +1,27 @@
+-- Priced work on a booking; amounts are in cents
+CREATE TABLE maintenance_line_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    maintenance_id INTEGER NOT NULL REFERENCES maintenance(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL, -- LABOUR, PART or FEE
+    description TEXT NOT NULL,
+    quantity REAL NOT NULL,
+    unit_price_cents INTEGER NOT NULL,
+    total_cents INTEGER NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_maintenance_line_items_maintenance_id ON maintenance_line_items (maintenance_id);
+
+-- Total the customer was quoted, kept even if line items change afterwards
+ALTER TABLE maintenance ADD COLUMN quoted_total_cents INTEGER;
+
+-- Issued when a booking is completed; totals are frozen at that point
+CREATE TABLE invoices (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    maintenance_id INTEGER NOT NULL UNIQUE REFERENCES maintenance(id),
+    subtotal_cents INTEGER NOT NULL,
+    tax_rate_bps INTEGER NOT NULL,
+    tax_cents INTEGER NOT NULL,
+    total_cents INTEGER NOT NULL,
+    issued_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,37 @@
+-- Catalogue of parts garages keep in stock
+CREATE TABLE parts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    sku TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    name TEXT NOT NULL
+);
+
+-- Stock of a part in a garage
+CREATE TABLE garage_parts (
+    garage_id INTEGER NOT NULL REFERENCES garages(id),
+    part_id INTEGER NOT NULL REFERENCES parts(id),
+    on_hand INTEGER NOT NULL DEFAULT 0,
+    reorder_level INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (garage_id, part_id)
+);
+
+-- Parts a service uses up
+CREATE TABLE service_type_parts (
+    service_type_id INTEGER NOT NULL REFERENCES service_types(id) ON DELETE CASCADE,
+    part_id INTEGER NOT NULL REFERENCES parts(id),
+    quantity INTEGER NOT NULL,
+    PRIMARY KEY (service_type_id, part_id)
+);
+
+-- Parts held for a booking until it is completed (consumed_at set) or
+-- cancelled (row removed)
+CREATE TABLE maintenance_part_reservations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    maintenance_id INTEGER NOT NULL REFERENCES maintenance(id) ON DELETE CASCADE,
+    garage_id INTEGER NOT NULL REFERENCES garages(id),
+    part_id INTEGER NOT NULL REFERENCES parts(id),
+    quantity INTEGER NOT NULL,
+    consumed_at TEXT
+);
+
+CREATE INDEX idx_maintenance_part_reservations_maintenance_id ON maintenance_part_reservations (maintenance_id);
+CREATE INDEX idx_maintenance_part_reservations_garage_part ON maintenance_part_reservations (garage_id, part_id);
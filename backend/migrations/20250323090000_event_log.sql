@@ -0,0 +1,12 @@
+-- Every published event in commit order, kept for a while so live
+-- subscribers can resume from the last event they saw
+CREATE TABLE event_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_id TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    garage_id INTEGER,
+    payload TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_event_log_created_at ON event_log (created_at);
@@ -0,0 +1,23 @@
+-- Who to tell about a car's bookings; a car without contact details gets
+-- no notifications
+ALTER TABLE cars ADD COLUMN owner_name TEXT;
+ALTER TABLE cars ADD COLUMN owner_email TEXT;
+ALTER TABLE cars ADD COLUMN owner_phone TEXT;
+
+-- Every notification sent or attempted, per channel
+CREATE TABLE notifications (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    maintenance_id INTEGER NOT NULL REFERENCES maintenance(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('CONFIRMATION', 'RESCHEDULE', 'REMINDER')),
+    channel TEXT NOT NULL,
+    recipient TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    body TEXT NOT NULL,
+    -- The booking date the message was about
+    scheduled_date TEXT NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('SENT', 'FAILED')),
+    error TEXT,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_notifications_maintenance ON notifications (maintenance_id, created_at);
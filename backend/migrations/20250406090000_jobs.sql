@@ -0,0 +1,35 @@
+-- Durable background work. Workers claim due PENDING jobs, retrying
+-- failures with backoff until `max_attempts` is reached.
+CREATE TABLE jobs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL,
+    payload TEXT NOT NULL DEFAULT '{}',
+    status TEXT NOT NULL DEFAULT 'PENDING' CHECK (status IN ('PENDING', 'RUNNING', 'SUCCEEDED', 'FAILED')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL DEFAULT 5,
+    run_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    -- At most one pending job per key, so repeated triggers collapse
+    unique_key TEXT,
+    -- Schedule that enqueued the job, if any
+    schedule TEXT,
+    result TEXT,
+    last_error TEXT,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    started_at TEXT,
+    finished_at TEXT
+);
+
+CREATE INDEX idx_jobs_due ON jobs (status, run_at);
+CREATE INDEX idx_jobs_kind ON jobs (kind, created_at);
+CREATE UNIQUE INDEX idx_jobs_unique_pending ON jobs (unique_key) WHERE status = 'PENDING';
+
+-- Recurring jobs with a five-field cron expression in server local time
+CREATE TABLE job_schedules (
+    name TEXT PRIMARY KEY NOT NULL,
+    kind TEXT NOT NULL,
+    payload TEXT NOT NULL DEFAULT '{}',
+    cron TEXT NOT NULL,
+    active INTEGER NOT NULL DEFAULT 1,
+    next_run_at TEXT NOT NULL,
+    last_run_at TEXT
+);
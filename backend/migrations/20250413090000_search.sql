@@ -0,0 +1,73 @@
+-- Full-text indexes for GET /search. The trigram tokenizer matches any
+-- part of a word, so partial plates and addresses are found too. The
+-- indexes read their text from the base tables and the triggers below keep
+-- them in step.
+CREATE VIRTUAL TABLE cars_fts USING fts5(
+    make, model, license_plate,
+    content = 'cars', content_rowid = 'id', tokenize = 'trigram'
+);
+
+CREATE VIRTUAL TABLE garages_fts USING fts5(
+    name, location, city,
+    content = 'garages', content_rowid = 'id', tokenize = 'trigram'
+);
+
+CREATE VIRTUAL TABLE maintenance_fts USING fts5(
+    service_type, notes,
+    content = 'maintenance', content_rowid = 'id', tokenize = 'trigram'
+);
+
+INSERT INTO cars_fts (cars_fts) VALUES ('rebuild');
+INSERT INTO garages_fts (garages_fts) VALUES ('rebuild');
+INSERT INTO maintenance_fts (maintenance_fts) VALUES ('rebuild');
+
+CREATE TRIGGER cars_fts_insert AFTER INSERT ON cars BEGIN
+    INSERT INTO cars_fts (rowid, make, model, license_plate)
+    VALUES (new.id, new.make, new.model, new.license_plate);
+END;
+
+CREATE TRIGGER cars_fts_delete AFTER DELETE ON cars BEGIN
+    INSERT INTO cars_fts (cars_fts, rowid, make, model, license_plate)
+    VALUES ('delete', old.id, old.make, old.model, old.license_plate);
+END;
+
+CREATE TRIGGER cars_fts_update AFTER UPDATE OF make, model, license_plate ON cars BEGIN
+    INSERT INTO cars_fts (cars_fts, rowid, make, model, license_plate)
+    VALUES ('delete', old.id, old.make, old.model, old.license_plate);
+    INSERT INTO cars_fts (rowid, make, model, license_plate)
+    VALUES (new.id, new.make, new.model, new.license_plate);
+END;
+
+CREATE TRIGGER garages_fts_insert AFTER INSERT ON garages BEGIN
+    INSERT INTO garages_fts (rowid, name, location, city)
+    VALUES (new.id, new.name, new.location, new.city);
+END;
+
+CREATE TRIGGER garages_fts_delete AFTER DELETE ON garages BEGIN
+    INSERT INTO garages_fts (garages_fts, rowid, name, location, city)
+    VALUES ('delete', old.id, old.name, old.location, old.city);
+END;
+
+CREATE TRIGGER garages_fts_update AFTER UPDATE OF name, location, city ON garages BEGIN
+    INSERT INTO garages_fts (garages_fts, rowid, name, location, city)
+    VALUES ('delete', old.id, old.name, old.location, old.city);
+    INSERT INTO garages_fts (rowid, name, location, city)
+    VALUES (new.id, new.name, new.location, new.city);
+END;
+
+CREATE TRIGGER maintenance_fts_insert AFTER INSERT ON maintenance BEGIN
+    INSERT INTO maintenance_fts (rowid, service_type, notes)
+    VALUES (new.id, new.service_type, new.notes);
+END;
+
+CREATE TRIGGER maintenance_fts_delete AFTER DELETE ON maintenance BEGIN
+    INSERT INTO maintenance_fts (maintenance_fts, rowid, service_type, notes)
+    VALUES ('delete', old.id, old.service_type, old.notes);
+END;
+
+CREATE TRIGGER maintenance_fts_update AFTER UPDATE OF service_type, notes ON maintenance BEGIN
+    INSERT INTO maintenance_fts (maintenance_fts, rowid, service_type, notes)
+    VALUES ('delete', old.id, old.service_type, old.notes);
+    INSERT INTO maintenance_fts (rowid, service_type, notes)
+    VALUES (new.id, new.service_type, new.notes);
+END;
@@ -0,0 +1,17 @@
+-- Plates are compared by their letters and digits in upper case, so
+-- "AB-123-CD" and "ab 123 cd" are the same car. Existing cars whose plates
+-- only differed in case or separators keep their rows: all but the oldest
+-- get their id appended to the key until someone merges them.
+ALTER TABLE cars ADD COLUMN plate_key TEXT;
+
+UPDATE cars
+SET plate_key = UPPER(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(license_plate, ' ', ''), '-', ''), '.', ''), '_', ''), '/', ''));
+
+UPDATE cars
+SET plate_key = plate_key || '#' || id
+WHERE EXISTS (
+    SELECT 1 FROM cars AS older
+    WHERE older.plate_key = cars.plate_key AND older.id < cars.id
+);
+
+CREATE UNIQUE INDEX idx_cars_plate_key ON cars (plate_key);
@@ -0,0 +1,5 @@
+-- Vehicle identification number, stored upper case without separators.
+-- Optional, but no two cars may share one.
+ALTER TABLE cars ADD COLUMN vin TEXT;
+
+CREATE UNIQUE INDEX idx_cars_vin ON cars (vin);
@@ -0,0 +1,32 @@
+-- Plates are stored in their national style, e.g. "AB-123-CD", which the
+-- trigram index does not match when searched for as "AB123CD" or "ab123".
+-- The car index also holds the plate key, letters and digits only, so
+-- partial plates are found however they are typed.
+DROP TRIGGER cars_fts_insert;
+DROP TRIGGER cars_fts_delete;
+DROP TRIGGER cars_fts_update;
+DROP TABLE cars_fts;
+
+CREATE VIRTUAL TABLE cars_fts USING fts5(
+    make, model, license_plate, plate_key,
+    content = 'cars', content_rowid = 'id', tokenize = 'trigram'
+);
+
+INSERT INTO cars_fts (cars_fts) VALUES ('rebuild');
+
+CREATE TRIGGER cars_fts_insert AFTER INSERT ON cars BEGIN
+    INSERT INTO cars_fts (rowid, make, model, license_plate, plate_key)
+    VALUES (new.id, new.make, new.model, new.license_plate, new.plate_key);
+END;
+
+CREATE TRIGGER cars_fts_delete AFTER DELETE ON cars BEGIN
+    INSERT INTO cars_fts (cars_fts, rowid, make, model, license_plate, plate_key)
+    VALUES ('delete', old.id, old.make, old.model, old.license_plate, old.plate_key);
+END;
+
+CREATE TRIGGER cars_fts_update AFTER UPDATE OF make, model, license_plate, plate_key ON cars BEGIN
+    INSERT INTO cars_fts (cars_fts, rowid, make, model, license_plate, plate_key)
+    VALUES ('delete', old.id, old.make, old.model, old.license_plate, old.plate_key);
+    INSERT INTO cars_fts (rowid, make, model, license_plate, plate_key)
+    VALUES (new.id, new.make, new.model, new.license_plate, new.plate_key);
+END;
@@ -1,5 +1,18 @@
+use crate::events::EventBus;
+use crate::plates::PlateCountry;
 use sqlx::SqlitePool;
+use std::sync::Arc;
 
 pub struct AppState {
     pub pool: SqlitePool,
+    /// Tax applied to invoices, in basis points.
+    pub tax_rate_bps: i64,
+    /// Longest date range, in days, a per-day report may cover.
+    pub report_max_days: i64,
+    /// Longest range, in months, a per-month report may cover.
+    pub report_max_months: i64,
+    /// Committed booking and capacity events for live subscribers.
+    pub events: Arc<EventBus>,
+    /// Plate layout used for cars that do not name their plate's country.
+    pub plate_country: Option<PlateCountry>,
 }
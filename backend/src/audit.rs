@@ -0,0 +1,239 @@
+use crate::events;
+use actix_web::HttpRequest;
+use serde_json::Value;
+use sqlx::SqliteConnection;
+
+/// Header clients use to identify who performed a change. There is no
+/// authentication yet, so the value is taken as given.
+pub const ACTOR_HEADER: &str = "X-Actor";
+
+const ANONYMOUS_ACTOR: &str = "anonymous";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditResource {
+    Car,
+    Garage,
+    Maintenance,
+    Mechanic,
+}
+
+impl AuditResource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditResource::Car => "car",
+            AuditResource::Garage => "garage",
+            AuditResource::Maintenance => "maintenance",
+            AuditResource::Mechanic => "mechanic",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "car" | "cars" => Some(AuditResource::Car),
+            "garage" | "garages" => Some(AuditResource::Garage),
+            "maintenance" => Some(AuditResource::Maintenance),
+            "mechanic" | "mechanics" => Some(AuditResource::Mechanic),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    Restore,
+    StatusChange,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+            AuditAction::Restore => "restore",
+            AuditAction::StatusChange => "status_change",
+        }
+    }
+}
+
+pub fn actor(req: &HttpRequest) -> String {
+    req.headers()
+        .get(ACTOR_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .unwrap_or(ANONYMOUS_ACTOR)
+        .to_string()
+}
+
+/// Current state of a row as JSON, in the same shape the API exposes it.
+pub async fn snapshot(
+    conn: &mut SqliteConnection,
+    resource: AuditResource,
+    id: &str,
+) -> Result<Option<Value>, sqlx::Error> {
+    let json = match resource {
+        AuditResource::Car => {
+            sqlx::query_scalar!(
+                r#"
+                SELECT json_object(
+                    'id', id,
+                    'make', make,
+                    'model', model,
+                    'productionYear', production_year,
+                    'licensePlate', license_plate,
+                    'vin', vin,
+                    'ownerName', owner_name,
+                    'ownerEmail', owner_email,
+                    'ownerPhone', owner_phone,
+                    'garageIds', json((
+                        SELECT json_group_array(garage_id)
+                        FROM car_garages
+                        WHERE car_garages.car_id = cars.id
+                    )),
+                    'deletedAt', deleted_at
+                ) AS "snapshot!: String"
+                FROM cars
+                WHERE id = ?
+                "#,
+                id
+            )
+            .fetch_optional(&mut *conn)
+            .await?
+        }
+        AuditResource::Garage => {
+            sqlx::query_scalar!(
+                r#"
+                SELECT json_object(
+                    'id', id,
+                    'name', name,
+                    'location', location,
+                    'city', city,
+                    'capacity', capacity,
+                    'openingTime', opening_time,
+                    'closingTime', closing_time,
+                    'slotMinutes', slot_minutes,
+                    'deletedAt', deleted_at
+                ) AS "snapshot!: String"
+                FROM garages
+                WHERE id = ?
+                "#,
+                id
+            )
+            .fetch_optional(&mut *conn)
+            .await?
+        }
+        AuditResource::Maintenance => {
+            sqlx::query_scalar!(
+                r#"
+                SELECT json_object(
+                    'id', id,
+                    'carId', car_id,
+                    'garageId', garage_id,
+                    'serviceType', service_type,
+                    'scheduledDate', scheduled_date,
+                    'status', status,
+                    'bayId', bay_id,
+                    'startTime', start_time,
+                    'durationMinutes', duration_minutes,
+                    'mechanicId', mechanic_id,
+                    'notes', notes,
+                    'quotedTotalCents', quoted_total_cents,
+                    'lineItems', json((
+                        SELECT json_group_array(json_object(
+                            'id', id,
+                            'kind', kind,
+                            'description', description,
+                            'quantity', quantity,
+                            'unitPriceCents', unit_price_cents,
+                            'totalCents', total_cents
+                        ))
+                        FROM maintenance_line_items
+                        WHERE maintenance_line_items.maintenance_id = maintenance.id
+                    )),
+                    'deletedAt', deleted_at
+                ) AS "snapshot!: String"
+                FROM maintenance
+                WHERE id = ?
+                "#,
+                id
+            )
+            .fetch_optional(&mut *conn)
+            .await?
+        }
+        AuditResource::Mechanic => {
+            sqlx::query_scalar!(
+                r#"
+                SELECT json_object(
+                    'id', id,
+                    'garageId', garage_id,
+                    'name', name,
+                    'active', json(CASE WHEN active THEN 'true' ELSE 'false' END)
+                ) AS "snapshot!: String"
+                FROM mechanics
+                WHERE id = ?
+                "#,
+                id
+            )
+            .fetch_optional(&mut *conn)
+            .await?
+        }
+    };
+
+    Ok(json.and_then(|json| serde_json::from_str(&json).ok()))
+}
+
+pub async fn record(
+    conn: &mut SqliteConnection,
+    actor: &str,
+    resource: AuditResource,
+    id: &str,
+    action: AuditAction,
+    before: Option<&Value>,
+    after: Option<&Value>,
+) -> Result<(), sqlx::Error> {
+    let resource = resource.as_str();
+    let action = action.as_str();
+    let before = before.map(Value::to_string);
+    let after = after.map(Value::to_string);
+
+    sqlx::query!(
+        r#"
+        INSERT INTO audit_log (actor, resource, resource_id, action, before_json, after_json)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        actor,
+        resource,
+        id,
+        action,
+        before,
+        after
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Snapshots the row after a change and writes the audit entry in one go.
+/// Changes webhooks subscribe to are queued in the same transaction.
+pub async fn record_change(
+    conn: &mut SqliteConnection,
+    actor: &str,
+    resource: AuditResource,
+    id: &str,
+    action: AuditAction,
+    before: Option<Value>,
+) -> Result<(), sqlx::Error> {
+    let after = snapshot(&mut *conn, resource, id).await?;
+    record(&mut *conn, actor, resource, id, action, before.as_ref(), after.as_ref()).await?;
+
+    if let Some(event) = events::from_change(resource, action, before.as_ref(), after.as_ref()) {
+        events::publish(conn, &event).await?;
+    }
+
+    Ok(())
+}
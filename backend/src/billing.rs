@@ -0,0 +1,189 @@
+use crate::models::billing::{InvoiceDTO, LineItemDTO, LineItemKind};
+use sqlx::SqliteConnection;
+
+/// Tax rates are kept in basis points (1/100 of a percent) so invoice
+/// amounts stay in integer cents.
+pub fn parse_tax_rate(percent: &str) -> Option<i64> {
+    let percent = percent.trim().parse::<f64>().ok()?;
+    (0.0..=100.0)
+        .contains(&percent)
+        .then(|| (percent * 100.0).round() as i64)
+}
+
+pub fn line_total(quantity: f64, unit_price_cents: i64) -> i64 {
+    (quantity * unit_price_cents as f64).round() as i64
+}
+
+/// Tax on a subtotal, rounded half up to the cent.
+pub fn tax_cents(subtotal_cents: i64, tax_rate_bps: i64) -> i64 {
+    (subtotal_cents * tax_rate_bps + 5_000).div_euclid(10_000)
+}
+
+pub fn invoice_number(invoice_id: i64) -> String {
+    format!("INV-{:06}", invoice_id)
+}
+
+pub async fn line_items(
+    conn: &mut SqliteConnection,
+    maintenance_id: i64,
+) -> Result<Vec<LineItemDTO>, sqlx::Error> {
+    sqlx::query_as!(
+        LineItemDTO,
+        r#"
+        SELECT
+            id AS "id!",
+            maintenance_id,
+            kind AS "kind: LineItemKind",
+            description,
+            quantity,
+            unit_price_cents,
+            total_cents
+        FROM maintenance_line_items
+        WHERE maintenance_id = ?
+        ORDER BY id
+        "#,
+        maintenance_id
+    )
+    .fetch_all(&mut *conn)
+    .await
+}
+
+pub async fn subtotal(conn: &mut SqliteConnection, maintenance_id: i64) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT CAST(COALESCE(SUM(total_cents), 0) AS INTEGER) AS "subtotal!: i64"
+        FROM maintenance_line_items
+        WHERE maintenance_id = ?
+        "#,
+        maintenance_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+}
+
+/// Freezes the booking's current line items into an invoice at the given
+/// tax rate.
+pub async fn issue_invoice(
+    conn: &mut SqliteConnection,
+    maintenance_id: i64,
+    tax_rate_bps: i64,
+) -> Result<i64, sqlx::Error> {
+    let subtotal_cents = subtotal(&mut *conn, maintenance_id).await?;
+    let tax = tax_cents(subtotal_cents, tax_rate_bps);
+    let total = subtotal_cents + tax;
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO invoices (maintenance_id, subtotal_cents, tax_rate_bps, tax_cents, total_cents)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+        maintenance_id,
+        subtotal_cents,
+        tax_rate_bps,
+        tax,
+        total
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn invoice(
+    conn: &mut SqliteConnection,
+    maintenance_id: i64,
+) -> Result<Option<InvoiceDTO>, sqlx::Error> {
+    let Some(invoice) = sqlx::query!(
+        r#"
+        SELECT
+            invoices.id AS "id!",
+            invoices.subtotal_cents,
+            invoices.tax_rate_bps,
+            invoices.tax_cents,
+            invoices.total_cents,
+            invoices.issued_at,
+            maintenance.car_id AS "car_id!",
+            maintenance.garage_id AS "garage_id!"
+        FROM invoices
+        JOIN maintenance ON maintenance.id = invoices.maintenance_id
+        WHERE invoices.maintenance_id = ?
+        "#,
+        maintenance_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    Ok(Some(InvoiceDTO {
+        invoice_number: invoice_number(invoice.id),
+        maintenance_id,
+        car_id: invoice.car_id,
+        garage_id: invoice.garage_id,
+        issued_at: invoice.issued_at,
+        lines: line_items(&mut *conn, maintenance_id).await?,
+        subtotal_cents: invoice.subtotal_cents,
+        tax_rate_percent: invoice.tax_rate_bps as f64 / 100.0,
+        tax_cents: invoice.tax_cents,
+        total_cents: invoice.total_cents,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tax_rates_into_basis_points() {
+        assert_eq!(parse_tax_rate("21"), Some(2100));
+        assert_eq!(parse_tax_rate(" 20.5 "), Some(2050));
+        assert_eq!(parse_tax_rate("0.125"), Some(13));
+        assert_eq!(parse_tax_rate("0"), Some(0));
+        assert_eq!(parse_tax_rate("100"), Some(10_000));
+    }
+
+    #[test]
+    fn rejects_invalid_tax_rates() {
+        for rate in ["", "abc", "21%", "-1", "100.01", "NaN", "inf"] {
+            assert_eq!(parse_tax_rate(rate), None, "'{}' was accepted", rate);
+        }
+    }
+
+    #[test]
+    fn rounds_tax_half_up_to_the_cent() {
+        // 21% of 50 cents is 10.5 cents.
+        assert_eq!(tax_cents(50, 2100), 11);
+        assert_eq!(tax_cents(49, 2100), 10);
+        assert_eq!(tax_cents(10_000, 2100), 2100);
+        // 12.5% of 4 cents is exactly half a cent.
+        assert_eq!(tax_cents(4, 1250), 1);
+        assert_eq!(tax_cents(3, 1250), 0);
+    }
+
+    #[test]
+    fn charges_no_tax_at_a_zero_rate_or_subtotal() {
+        assert_eq!(tax_cents(12_345, 0), 0);
+        assert_eq!(tax_cents(0, 2100), 0);
+    }
+
+    #[test]
+    fn rounds_negative_tax_towards_positive_infinity() {
+        assert_eq!(tax_cents(-50, 2100), -10);
+        assert_eq!(tax_cents(-49, 2100), -10);
+    }
+
+    #[test]
+    fn rounds_line_totals_to_the_cent() {
+        assert_eq!(line_total(2.0, 1_500), 3_000);
+        assert_eq!(line_total(1.5, 1_999), 2_999);
+        assert_eq!(line_total(0.333, 300), 100);
+        assert_eq!(line_total(0.25, 1), 0);
+    }
+
+    #[test]
+    fn numbers_invoices_with_six_digits() {
+        assert_eq!(invoice_number(42), "INV-000042");
+        assert_eq!(invoice_number(1_234_567), "INV-1234567");
+    }
+}
@@ -0,0 +1,264 @@
+use crate::models::maintenance::MaintenanceStatus;
+use crate::{scheduler, slots};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use sqlx::SqliteConnection;
+
+/// Feeds leave out bookings older than this so subscriptions stay small.
+pub const CALENDAR_LOOKBACK_DAYS: i64 = 180;
+
+const PRODID: &str = "-//Garage Management//Bookings//EN";
+const UID_DOMAIN: &str = "garage-management";
+const ICS_DATE: &str = "%Y%m%d";
+const ICS_DATE_TIME: &str = "%Y%m%dT%H%M%S";
+const AUDIT_TIMESTAMP: &str = "%Y-%m-%d %H:%M:%S";
+const DEFAULT_EVENT_MINUTES: i64 = 60;
+
+pub struct CalendarEvent {
+    pub maintenance_id: i64,
+    pub scheduled_date: String,
+    pub start_time: Option<String>,
+    pub duration_minutes: Option<i64>,
+    pub service_type: String,
+    pub status: MaintenanceStatus,
+    pub notes: Option<String>,
+    pub car_name: String,
+    pub license_plate: String,
+    pub garage_name: String,
+    pub garage_location: String,
+    /// Number of edits and status changes, so clients know which copy of a
+    /// booking is newer.
+    pub sequence: i64,
+    pub last_modified: Option<String>,
+}
+
+/// Live bookings of one garage or one car from `CALENDAR_LOOKBACK_DAYS` ago on.
+pub async fn events(
+    conn: &mut SqliteConnection,
+    garage_id: Option<i64>,
+    car_id: Option<i64>,
+) -> Result<Vec<CalendarEvent>, sqlx::Error> {
+    let since = (Utc::now().date_naive() - Duration::days(CALENDAR_LOOKBACK_DAYS))
+        .format(scheduler::DATE_FORMAT)
+        .to_string();
+    let garage_id = garage_id.map(|id| id.to_string());
+    let car_id = car_id.map(|id| id.to_string());
+
+    sqlx::query_as!(
+        CalendarEvent,
+        r#"
+        SELECT
+            maintenance.id AS "maintenance_id!",
+            date(maintenance.scheduled_date) AS "scheduled_date!: String",
+            maintenance.start_time,
+            maintenance.duration_minutes,
+            maintenance.service_type,
+            maintenance.status AS "status: MaintenanceStatus",
+            maintenance.notes,
+            cars.make || ' ' || cars.model AS "car_name!: String",
+            cars.license_plate,
+            garages.name AS garage_name,
+            garages.location AS garage_location,
+            (
+                SELECT COUNT(*) FROM audit_log
+                WHERE audit_log.resource = 'maintenance'
+                  AND audit_log.resource_id = CAST(maintenance.id AS TEXT)
+                  AND audit_log.action IN ('update', 'status_change')
+            ) AS "sequence!: i64",
+            (
+                SELECT MAX(audit_log.created_at) FROM audit_log
+                WHERE audit_log.resource = 'maintenance'
+                  AND audit_log.resource_id = CAST(maintenance.id AS TEXT)
+            ) AS "last_modified: String"
+        FROM maintenance
+        JOIN cars ON maintenance.car_id = cars.id
+        JOIN garages ON maintenance.garage_id = garages.id
+        WHERE maintenance.deleted_at IS NULL
+          AND (?1 IS NULL OR maintenance.garage_id = ?1)
+          AND (?2 IS NULL OR maintenance.car_id = ?2)
+          AND date(maintenance.scheduled_date) >= ?3
+        ORDER BY date(maintenance.scheduled_date), maintenance.start_time, maintenance.id
+        "#,
+        garage_id,
+        car_id,
+        since
+    )
+    .fetch_all(&mut *conn)
+    .await
+}
+
+/// UIDs depend only on the booking id, so an edited booking replaces the
+/// event calendar clients already have.
+pub fn event_uid(maintenance_id: i64) -> String {
+    format!("maintenance-{}@{}", maintenance_id, UID_DOMAIN)
+}
+
+fn ics_status(status: MaintenanceStatus) -> &'static str {
+    match status {
+        MaintenanceStatus::Scheduled | MaintenanceStatus::Quoted => "TENTATIVE",
+        MaintenanceStatus::Confirmed | MaintenanceStatus::InProgress | MaintenanceStatus::Completed => "CONFIRMED",
+        MaintenanceStatus::Cancelled | MaintenanceStatus::NoShow => "CANCELLED",
+    }
+}
+
+/// Escapes a TEXT value (RFC 5545 section 3.3.11).
+fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Appends a content line, folded so no physical line exceeds 75 octets
+/// (RFC 5545 section 3.1).
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn push_event(out: &mut String, event: &CalendarEvent, stamp: &str) {
+    let Ok(date) = NaiveDate::parse_from_str(&event.scheduled_date, scheduler::DATE_FORMAT) else {
+        return;
+    };
+
+    push_line(out, "BEGIN:VEVENT");
+    push_line(out, &format!("UID:{}", event_uid(event.maintenance_id)));
+    push_line(out, &format!("DTSTAMP:{}", stamp));
+    push_line(out, &format!("SEQUENCE:{}", event.sequence));
+    if let Some(modified) = event
+        .last_modified
+        .as_deref()
+        .and_then(|modified| NaiveDateTime::parse_from_str(modified, AUDIT_TIMESTAMP).ok())
+    {
+        push_line(out, &format!("LAST-MODIFIED:{}Z", modified.format(ICS_DATE_TIME)));
+    }
+
+    // Bookings without a slot take the whole day; slotted ones are floating
+    // local times since garages have no time zone on record.
+    let start = event
+        .start_time
+        .as_deref()
+        .and_then(|time| NaiveTime::parse_from_str(time, slots::TIME_FORMAT).ok());
+    match start {
+        Some(time) => {
+            let start = date.and_time(time);
+            let end = start + Duration::minutes(event.duration_minutes.unwrap_or(DEFAULT_EVENT_MINUTES));
+            push_line(out, &format!("DTSTART:{}", start.format(ICS_DATE_TIME)));
+            push_line(out, &format!("DTEND:{}", end.format(ICS_DATE_TIME)));
+        }
+        None => {
+            push_line(out, &format!("DTSTART;VALUE=DATE:{}", date.format(ICS_DATE)));
+            push_line(out, &format!("DTEND;VALUE=DATE:{}", (date + Duration::days(1)).format(ICS_DATE)));
+        }
+    }
+
+    push_line(
+        out,
+        &format!(
+            "SUMMARY:{}",
+            escape_text(&format!("{}: {} ({})", event.service_type, event.car_name, event.license_plate))
+        ),
+    );
+    push_line(
+        out,
+        &format!("LOCATION:{}", escape_text(&format!("{}, {}", event.garage_name, event.garage_location))),
+    );
+
+    let mut description = format!(
+        "Car: {} ({})\nStatus: {}",
+        event.car_name,
+        event.license_plate,
+        event.status.as_str()
+    );
+    if let Some(notes) = event.notes.as_deref().filter(|notes| !notes.trim().is_empty()) {
+        description.push_str("\nNotes: ");
+        description.push_str(notes);
+    }
+    push_line(out, &format!("DESCRIPTION:{}", escape_text(&description)));
+    push_line(out, &format!("STATUS:{}", ics_status(event.status)));
+    push_line(out, "END:VEVENT");
+}
+
+/// Renders a VCALENDAR with one VEVENT per booking.
+pub fn render(name: &str, events: &[CalendarEvent]) -> String {
+    let stamp = format!("{}Z", Utc::now().format(ICS_DATE_TIME));
+    let mut out = String::new();
+
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, &format!("PRODID:{}", PRODID));
+    push_line(&mut out, "CALSCALE:GREGORIAN");
+    push_line(&mut out, "METHOD:PUBLISH");
+    push_line(&mut out, &format!("X-WR-CALNAME:{}", escape_text(name)));
+    for event in events {
+        push_event(&mut out, event, &stamp);
+    }
+    push_line(&mut out, "END:VCALENDAR");
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_text_special_characters() {
+        assert_eq!(escape_text("Oil; filter, wipers"), "Oil\\; filter\\, wipers");
+        assert_eq!(escape_text("C:\\garage"), "C:\\\\garage");
+        assert_eq!(escape_text("line one\r\nline two\nend"), "line one\\nline two\\nend");
+        assert_eq!(escape_text("Plain text: ok"), "Plain text: ok");
+    }
+
+    #[test]
+    fn leaves_short_lines_unfolded() {
+        let mut out = String::new();
+        push_line(&mut out, &"a".repeat(75));
+        assert_eq!(out, format!("{}\r\n", "a".repeat(75)));
+    }
+
+    #[test]
+    fn folds_lines_longer_than_75_octets() {
+        let mut out = String::new();
+        push_line(&mut out, &"a".repeat(200));
+        let lines: Vec<&str> = out.trim_end_matches("\r\n").split("\r\n").collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].len(), 75);
+        assert!(lines[1..].iter().all(|line| line.starts_with(' ') && line.len() <= 75));
+        let unfolded: String = lines.concat().replace(' ', "");
+        assert_eq!(unfolded, "a".repeat(200));
+    }
+
+    #[test]
+    fn never_splits_multi_byte_characters() {
+        let line = format!("SUMMARY:{}", "č".repeat(60));
+        let mut out = String::new();
+        push_line(&mut out, &line);
+        let lines: Vec<&str> = out.trim_end_matches("\r\n").split("\r\n").collect();
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| line.len() <= 75));
+        // "SUMMARY:" takes 8 octets, leaving room for 33 two-octet characters.
+        assert_eq!(lines[0].len(), 74);
+        let unfolded: String = lines
+            .iter()
+            .enumerate()
+            .map(|(index, folded)| if index == 0 { folded } else { &folded[1..] })
+            .collect();
+        assert_eq!(unfolded, line);
+    }
+}
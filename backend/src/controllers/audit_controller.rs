@@ -0,0 +1,131 @@
+use crate::app_state::AppState;
+use crate::audit::AuditResource;
+use crate::models::audit::{AuditLogEntryDTO, AuditQueryParams};
+use actix_web::{web, HttpResponse, Responder};
+use chrono::{NaiveDate, NaiveDateTime};
+use log::{error, info};
+use serde_json::json;
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+/// Accepts either a date (`2025-01-31`, meaning midnight) or a full
+/// timestamp and returns it in the format `audit_log.created_at` uses.
+fn parse_timestamp(value: &str) -> Option<String> {
+    let value = value.trim();
+    let parsed = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S"))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+
+    Some(parsed.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+pub async fn get_audit_log(
+    query: web::Query<AuditQueryParams>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    info!("Fetching audit log: {:?}", query);
+
+    let resource = match query.resource.as_deref() {
+        Some(value) => match AuditResource::parse(value) {
+            Some(resource) => Some(resource.as_str()),
+            None => {
+                return HttpResponse::BadRequest().json(json!({
+                    "error": "Invalid resource parameter",
+                    "details": "Expected one of car, garage, maintenance, mechanic"
+                }));
+            }
+        },
+        None => None,
+    };
+
+    let from = match query.from.as_deref().map(parse_timestamp) {
+        Some(Some(from)) => Some(from),
+        Some(None) => {
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Invalid from parameter",
+                "details": "Expected YYYY-MM-DD or YYYY-MM-DD HH:MM:SS"
+            }));
+        }
+        None => None,
+    };
+
+    let to = match query.to.as_deref().map(parse_timestamp) {
+        Some(Some(to)) => Some(to),
+        Some(None) => {
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Invalid to parameter",
+                "details": "Expected YYYY-MM-DD or YYYY-MM-DD HH:MM:SS"
+            }));
+        }
+        None => None,
+    };
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    // `from` is inclusive and `to` exclusive so consecutive ranges never overlap.
+    match sqlx::query!(
+        r#"
+        SELECT
+            id AS "id!",
+            actor,
+            resource,
+            resource_id,
+            action,
+            before_json,
+            after_json,
+            created_at
+        FROM audit_log
+        WHERE (?1 IS NULL OR resource = ?1)
+          AND (?2 IS NULL OR resource_id = ?2)
+          AND (?3 IS NULL OR actor = ?3)
+          AND (?4 IS NULL OR created_at >= ?4)
+          AND (?5 IS NULL OR created_at < ?5)
+        ORDER BY created_at DESC, id DESC
+        LIMIT ?6
+        "#,
+        resource,
+        query.resource_id,
+        query.actor,
+        from,
+        to,
+        limit
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(records) => {
+            let entries: Vec<AuditLogEntryDTO> = records
+                .into_iter()
+                .map(|record| AuditLogEntryDTO {
+                    id: record.id,
+                    actor: record.actor,
+                    resource: record.resource,
+                    resource_id: record.resource_id,
+                    action: record.action,
+                    before: record
+                        .before_json
+                        .and_then(|json| serde_json::from_str(&json).ok()),
+                    after: record
+                        .after_json
+                        .and_then(|json| serde_json::from_str(&json).ok()),
+                    created_at: record.created_at,
+                })
+                .collect();
+
+            HttpResponse::Ok().json(entries)
+        }
+        Err(err) => {
+            error!("Failed to fetch audit log: {:?}", err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch audit log",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
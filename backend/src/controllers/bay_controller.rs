@@ -0,0 +1,232 @@
+use crate::app_state::AppState;
+use crate::models::garage::{BayDTO, CreateBayRequest, FreeSlotDTO, FreeSlotsQueryParams};
+use crate::{events, scheduler, slots};
+use actix_web::{web, HttpResponse, Responder};
+use log::{error, info};
+use serde_json::json;
+
+fn bay_failed(message: &str, err: sqlx::Error) -> HttpResponse {
+    error!("{}: {:?}", message, err);
+    HttpResponse::InternalServerError().json(json!({
+        "error": message,
+        "details": err.to_string()
+    }))
+}
+
+pub async fn get_garage_bays(
+    garage_id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let garage_id = garage_id.into_inner();
+
+    match sqlx::query_as!(
+        BayDTO,
+        r#"
+        SELECT id AS "id!", garage_id, name, active AS "active: bool"
+        FROM bays
+        WHERE garage_id = ?
+        ORDER BY id
+        "#,
+        garage_id
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(bays) => HttpResponse::Ok().json(bays),
+        Err(err) => {
+            error!("Failed to fetch bays of garage {}: {:?}", garage_id, err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch bays",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
+
+pub async fn create_bay(
+    garage_id: web::Path<i64>,
+    bay_req: web::Json<CreateBayRequest>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let garage_id = garage_id.into_inner();
+    info!("Received request to create bay for garage {}: {:?}", garage_id, bay_req);
+
+    match sqlx::query_scalar!(
+        "SELECT id FROM garages WHERE id = ? AND deleted_at IS NULL",
+        garage_id
+    )
+    .fetch_optional(&data.pool)
+    .await
+    {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(json!({
+                "error": "Garage not found"
+            }));
+        }
+        Err(err) => {
+            error!("Failed to fetch garage {}: {:?}", garage_id, err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to create bay",
+                "details": err.to_string()
+            }));
+        }
+    }
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return bay_failed("Failed to create bay", err),
+    };
+
+    let bay_id = match sqlx::query!(
+        "INSERT INTO bays (garage_id, name) VALUES (?, ?)",
+        garage_id,
+        bay_req.name
+    )
+    .execute(&mut *transaction)
+    .await
+    {
+        Ok(result) => result.last_insert_rowid(),
+        Err(err) => return bay_failed("Failed to create bay", err),
+    };
+
+    let event = events::capacity_changed(garage_id, json!({ "garageId": garage_id, "bayId": bay_id, "active": true }));
+    if let Err(err) = events::publish(&mut transaction, &event).await {
+        return bay_failed("Failed to create bay", err);
+    }
+
+    if let Err(err) = transaction.commit().await {
+        return bay_failed("Failed to create bay", err);
+    }
+
+    data.events.relay(&data.pool).await;
+
+    HttpResponse::Created().json(BayDTO {
+        id: bay_id,
+        garage_id,
+        name: bay_req.name.clone(),
+        active: true,
+    })
+}
+
+/// Bays are deactivated so past bookings keep pointing at them; they no
+/// longer take new bookings.
+pub async fn delete_bay(
+    id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let bay_id = id.into_inner();
+    info!("Received request to deactivate bay {}", bay_id);
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return bay_failed("Failed to delete bay", err),
+    };
+
+    let garage_id = match sqlx::query_scalar!(
+        "UPDATE bays SET active = 0 WHERE id = ? AND active = 1 RETURNING garage_id",
+        bay_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    {
+        Ok(Some(garage_id)) => garage_id,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(json!({
+                "error": "Bay not found"
+            }));
+        }
+        Err(err) => return bay_failed("Failed to delete bay", err),
+    };
+
+    let event = events::capacity_changed(garage_id, json!({ "garageId": garage_id, "bayId": bay_id, "active": false }));
+    if let Err(err) = events::publish(&mut transaction, &event).await {
+        return bay_failed("Failed to delete bay", err);
+    }
+
+    if let Err(err) = transaction.commit().await {
+        return bay_failed("Failed to delete bay", err);
+    }
+
+    data.events.relay(&data.pool).await;
+
+    HttpResponse::Ok().json(json!({
+        "id": bay_id,
+        "deleted": true,
+    }))
+}
+
+pub async fn get_free_slots(
+    garage_id: web::Path<i64>,
+    query: web::Query<FreeSlotsQueryParams>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let garage_id = garage_id.into_inner();
+
+    let Some(date) = scheduler::parse_date(&query.date) else {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid date",
+            "details": "date must be formatted as YYYY-MM-DD"
+        }));
+    };
+
+    let mut conn = match data.pool.acquire().await {
+        Ok(conn) => conn,
+        Err(err) => {
+            error!("Failed to acquire connection: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch free slots",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let hours = match slots::garage_hours(&mut conn, garage_id).await {
+        Ok(Some(hours)) => hours,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(json!({
+                "error": "Garage not found"
+            }));
+        }
+        Err(err) => {
+            error!("Failed to fetch garage {}: {:?}", garage_id, err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch free slots",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let duration = match query.service_type.as_deref() {
+        Some(service_type) => match slots::duration_for(&mut conn, service_type, hours.slot_minutes).await {
+            Ok(duration) => duration,
+            Err(err) => {
+                error!("Failed to fetch service type duration: {:?}", err);
+                return HttpResponse::InternalServerError().json(json!({
+                    "error": "Failed to fetch free slots",
+                    "details": err.to_string()
+                }));
+            }
+        },
+        None => hours.slot_minutes,
+    };
+
+    match slots::free_slots(&mut conn, &hours, date, duration, None).await {
+        Ok(free) => HttpResponse::Ok().json(
+            free.into_iter()
+                .map(|(start, bay_ids)| FreeSlotDTO {
+                    start_time: slots::format_time(start),
+                    end_time: slots::format_time(start + duration),
+                    bay_ids,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Err(err) => {
+            error!("Failed to compute free slots for garage {}: {:?}", garage_id, err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch free slots",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
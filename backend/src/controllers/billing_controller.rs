@@ -0,0 +1,341 @@
+use crate::app_state::AppState;
+use crate::audit::{self, AuditAction, AuditResource};
+use crate::billing;
+use crate::models::billing::{CreateLineItemDTO, LineItemDTO};
+use crate::models::maintenance::MaintenanceStatus;
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use log::{error, info, warn};
+use serde_json::json;
+use sqlx::SqliteConnection;
+
+pub async fn get_line_items(
+    id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let maintenance_id = id.into_inner();
+
+    let mut conn = match data.pool.acquire().await {
+        Ok(conn) => conn,
+        Err(err) => {
+            error!("Failed to acquire connection: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch line items",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    if let Err(response) = maintenance_status(&mut conn, maintenance_id).await.map(|_| ()) {
+        return response;
+    }
+
+    match billing::line_items(&mut conn, maintenance_id).await {
+        Ok(items) => HttpResponse::Ok().json(items),
+        Err(err) => {
+            error!("Failed to fetch line items of maintenance {}: {:?}", maintenance_id, err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch line items",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
+
+pub async fn add_line_item(
+    req: HttpRequest,
+    id: web::Path<i64>,
+    item_req: web::Json<CreateLineItemDTO>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let maintenance_id = id.into_inner();
+    info!("Received request to add line item to maintenance {}: {:?}", maintenance_id, item_req);
+
+    if !(item_req.quantity.is_finite() && item_req.quantity > 0.0) || item_req.unit_price_cents < 0 {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid line item",
+            "details": "quantity must be positive and unitPriceCents must not be negative"
+        }));
+    }
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("Failed to start transaction: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to start transaction",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    if let Err(response) = ensure_editable(&mut transaction, maintenance_id).await {
+        return response;
+    }
+
+    let resource_id = maintenance_id.to_string();
+    let before = match audit::snapshot(&mut transaction, AuditResource::Maintenance, &resource_id).await {
+        Ok(before) => before,
+        Err(err) => {
+            error!("Failed to read maintenance before update: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to add line item",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let total_cents = billing::line_total(item_req.quantity, item_req.unit_price_cents);
+
+    let item_id = match sqlx::query!(
+        r#"
+        INSERT INTO maintenance_line_items (maintenance_id, kind, description, quantity, unit_price_cents, total_cents)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        maintenance_id,
+        item_req.kind,
+        item_req.description,
+        item_req.quantity,
+        item_req.unit_price_cents,
+        total_cents
+    )
+    .execute(&mut *transaction)
+    .await
+    {
+        Ok(result) => result.last_insert_rowid(),
+        Err(err) => {
+            error!("Failed to add line item: {:?}", err);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to add line item",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    if let Err(err) = audit::record_change(
+        &mut transaction,
+        &audit::actor(&req),
+        AuditResource::Maintenance,
+        &resource_id,
+        AuditAction::Update,
+        before,
+    )
+    .await
+    {
+        error!("Failed to write audit log: {:?}", err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to add line item",
+            "details": err.to_string()
+        }));
+    }
+
+    if let Err(err) = transaction.commit().await {
+        error!("Failed to commit transaction: {:?}", err);
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to add line item",
+            "details": err.to_string()
+        }));
+    }
+
+    data.events.relay(&data.pool).await;
+
+    HttpResponse::Created().json(LineItemDTO {
+        id: item_id,
+        maintenance_id,
+        kind: item_req.kind,
+        description: item_req.description.clone(),
+        quantity: item_req.quantity,
+        unit_price_cents: item_req.unit_price_cents,
+        total_cents,
+    })
+}
+
+pub async fn delete_line_item(
+    req: HttpRequest,
+    path: web::Path<(i64, i64)>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let (maintenance_id, item_id) = path.into_inner();
+    info!("Received request to delete line item {} of maintenance {}", item_id, maintenance_id);
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("Failed to start transaction: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to start transaction",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    if let Err(response) = ensure_editable(&mut transaction, maintenance_id).await {
+        return response;
+    }
+
+    let resource_id = maintenance_id.to_string();
+    let before = match audit::snapshot(&mut transaction, AuditResource::Maintenance, &resource_id).await {
+        Ok(before) => before,
+        Err(err) => {
+            error!("Failed to read maintenance before update: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to delete line item",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    match sqlx::query!(
+        "DELETE FROM maintenance_line_items WHERE id = ? AND maintenance_id = ?",
+        item_id,
+        maintenance_id
+    )
+    .execute(&mut *transaction)
+    .await
+    {
+        Ok(result) if result.rows_affected() == 0 => {
+            let _ = transaction.rollback().await;
+            return HttpResponse::NotFound().json(json!({
+                "error": "Line item not found"
+            }));
+        }
+        Ok(_) => {}
+        Err(err) => {
+            error!("Failed to delete line item: {:?}", err);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to delete line item",
+                "details": err.to_string()
+            }));
+        }
+    }
+
+    if let Err(err) = audit::record_change(
+        &mut transaction,
+        &audit::actor(&req),
+        AuditResource::Maintenance,
+        &resource_id,
+        AuditAction::Update,
+        before,
+    )
+    .await
+    {
+        error!("Failed to write audit log: {:?}", err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to delete line item",
+            "details": err.to_string()
+        }));
+    }
+
+    if let Err(err) = transaction.commit().await {
+        error!("Failed to commit transaction: {:?}", err);
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to delete line item",
+            "details": err.to_string()
+        }));
+    }
+
+    data.events.relay(&data.pool).await;
+
+    HttpResponse::Ok().json(json!({
+        "id": item_id,
+        "deleted": true,
+    }))
+}
+
+pub async fn get_invoice(
+    id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let maintenance_id = id.into_inner();
+
+    let mut conn = match data.pool.acquire().await {
+        Ok(conn) => conn,
+        Err(err) => {
+            error!("Failed to acquire connection: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch invoice",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    match billing::invoice(&mut conn, maintenance_id).await {
+        Ok(Some(invoice)) => HttpResponse::Ok().json(invoice),
+        Ok(None) => HttpResponse::NotFound().json(json!({
+            "error": "Invoice not found",
+            "details": format!("Maintenance {} has not been invoiced; invoices are issued on completion", maintenance_id)
+        })),
+        Err(err) => {
+            error!("Failed to fetch invoice of maintenance {}: {:?}", maintenance_id, err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch invoice",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
+
+/// Status of an existing, not deleted booking.
+async fn maintenance_status(
+    conn: &mut SqliteConnection,
+    maintenance_id: i64,
+) -> Result<MaintenanceStatus, HttpResponse> {
+    match sqlx::query_scalar!(
+        r#"SELECT status AS "status: MaintenanceStatus" FROM maintenance WHERE id = ? AND deleted_at IS NULL"#,
+        maintenance_id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    {
+        Ok(Some(status)) => Ok(status),
+        Ok(None) => {
+            warn!("Maintenance with ID {} not found", maintenance_id);
+            Err(HttpResponse::NotFound().json(json!({
+                "error": "Maintenance not found"
+            })))
+        }
+        Err(err) => {
+            error!("Failed to fetch maintenance {}: {:?}", maintenance_id, err);
+            Err(HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch maintenance",
+                "details": err.to_string()
+            })))
+        }
+    }
+}
+
+/// Line items are frozen once a booking is quoted, so the invoice matches the
+/// quote, and once it is completed, cancelled or a no-show.
+async fn ensure_editable(conn: &mut SqliteConnection, maintenance_id: i64) -> Result<(), HttpResponse> {
+    let status = maintenance_status(conn, maintenance_id).await?;
+    if status.is_final() {
+        return Err(HttpResponse::Conflict().json(json!({
+            "error": "Line items are locked",
+            "details": format!("Cannot change line items of a {} maintenance", status)
+        })));
+    }
+
+    match sqlx::query_scalar!("SELECT quoted_total_cents FROM maintenance WHERE id = ?", maintenance_id)
+        .fetch_one(&mut *conn)
+        .await
+    {
+        Ok(None) => Ok(()),
+        Ok(Some(quoted_total_cents)) => Err(HttpResponse::Conflict().json(json!({
+            "error": "Line items are locked",
+            "details": format!(
+                "Maintenance {} was quoted at {} cents; its line items can no longer change",
+                maintenance_id, quoted_total_cents
+            )
+        }))),
+        Err(err) => {
+            error!("Failed to fetch maintenance {}: {:?}", maintenance_id, err);
+            Err(HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch maintenance",
+                "details": err.to_string()
+            })))
+        }
+    }
+}
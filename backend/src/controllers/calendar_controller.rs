@@ -0,0 +1,98 @@
+use crate::app_state::AppState;
+use crate::calendar;
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{web, HttpResponse, Responder};
+use log::error;
+use serde_json::json;
+
+fn calendar_response(file_name: String, body: String) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Inline,
+            parameters: vec![DispositionParam::Filename(file_name)],
+        })
+        .body(body)
+}
+
+fn calendar_failed(err: sqlx::Error) -> HttpResponse {
+    error!("Failed to build calendar feed: {:?}", err);
+    HttpResponse::InternalServerError().json(json!({
+        "error": "Failed to build calendar feed",
+        "details": err.to_string()
+    }))
+}
+
+pub async fn get_garage_calendar(
+    data: web::Data<AppState>,
+    garage_id: web::Path<i64>,
+) -> impl Responder {
+    let garage_id = garage_id.into_inner();
+
+    let mut conn = match data.pool.acquire().await {
+        Ok(conn) => conn,
+        Err(err) => return calendar_failed(err),
+    };
+
+    let name = match sqlx::query_scalar!(
+        "SELECT name FROM garages WHERE id = ? AND deleted_at IS NULL",
+        garage_id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    {
+        Ok(Some(name)) => name,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(json!({
+                "error": "Garage not found",
+                "details": format!("No garage found with id {}", garage_id)
+            }));
+        }
+        Err(err) => return calendar_failed(err),
+    };
+
+    match calendar::events(&mut conn, Some(garage_id), None).await {
+        Ok(events) => calendar_response(
+            format!("garage-{}.ics", garage_id),
+            calendar::render(&format!("{} bookings", name), &events),
+        ),
+        Err(err) => calendar_failed(err),
+    }
+}
+
+pub async fn get_car_calendar(
+    data: web::Data<AppState>,
+    car_id: web::Path<i64>,
+) -> impl Responder {
+    let car_id = car_id.into_inner();
+
+    let mut conn = match data.pool.acquire().await {
+        Ok(conn) => conn,
+        Err(err) => return calendar_failed(err),
+    };
+
+    let car = match sqlx::query!(
+        r#"SELECT make || ' ' || model AS "name!: String", license_plate FROM cars WHERE id = ? AND deleted_at IS NULL"#,
+        car_id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    {
+        Ok(Some(car)) => car,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(json!({
+                "error": "Car not found",
+                "details": format!("No car found with id {}", car_id)
+            }));
+        }
+        Err(err) => return calendar_failed(err),
+    };
+
+    match calendar::events(&mut conn, None, Some(car_id)).await {
+        Ok(events) => calendar_response(
+            format!("car-{}.ics", car_id),
+            calendar::render(&format!("{} ({}) maintenance", car.name, car.license_plate), &events),
+        ),
+        Err(err) => calendar_failed(err),
+    }
+}
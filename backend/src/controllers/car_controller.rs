@@ -1,26 +1,206 @@
 use crate::app_state::AppState;
-use crate::models::car::{Car, CreateCarRequest};
-use actix_web::{web, HttpResponse, Responder};
+use crate::audit::{self, AuditAction, AuditResource};
+use crate::export::{self, ExportFormat};
+use crate::notifications::{self, Recipient};
+use crate::models::car::{Car, CarExportRow, CreateCarRequest};
+use crate::models::common::IncludeDeletedQuery;
+use crate::plates::{self, PlateCountry};
+use crate::vin;
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use futures_util::TryStreamExt;
+use sqlx::SqliteConnection;
 use serde_json::json;
 use log::{error, info};
 
+/// Trims the owner's contact details, treating blanks as not given.
+fn owner_contact(car_req: &CreateCarRequest) -> Result<Recipient, HttpResponse> {
+    let clean = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    let (name, email, phone) = (clean(&car_req.owner_name), clean(&car_req.owner_email), clean(&car_req.owner_phone));
+
+    match notifications::contact_error(email.as_deref(), phone.as_deref()) {
+        Some(details) => Err(HttpResponse::BadRequest().json(json!({
+            "error": "Invalid owner contact",
+            "details": details
+        }))),
+        None => Ok(Recipient { name, email, phone }),
+    }
+}
+
+/// The plate as stored, in its country's style, and the key it is
+/// compared by.
+fn license_plate(car_req: &CreateCarRequest, default_country: Option<PlateCountry>) -> Result<(String, String), HttpResponse> {
+    let invalid = |details: String| {
+        HttpResponse::BadRequest().json(json!({
+            "error": "Invalid license plate",
+            "details": details
+        }))
+    };
+
+    let country = match car_req.plate_country.as_deref().map(str::trim).filter(|code| !code.is_empty()) {
+        Some(code) => Some(code.parse::<PlateCountry>().map_err(invalid)?),
+        None => None,
+    };
+    let plate = plates::normalize(&car_req.license_plate, country, default_country).map_err(invalid)?;
+    let key = plates::plate_key(&plate);
+    Ok((plate, key))
+}
+
+/// The car's make and production year, filled in from its VIN where left
+/// out, and the normalized VIN. A blank `vin` means the car has none.
+fn vehicle_identity(car_req: &CreateCarRequest, vin: Option<&str>) -> Result<(String, i64, Option<String>), HttpResponse> {
+    let invalid = |error: &str, details: String| {
+        HttpResponse::BadRequest().json(json!({
+            "error": error,
+            "details": details
+        }))
+    };
+
+    let decoded = match vin.map(str::trim).filter(|vin| !vin.is_empty()) {
+        Some(raw) => Some(vin::decode(&vin::normalize(raw).map_err(|details| invalid("Invalid VIN", details))?)),
+        None => None,
+    };
+
+    let make = match car_req.make.trim() {
+        "" => decoded
+            .as_ref()
+            .and_then(|decoded| decoded.make)
+            .map(str::to_string)
+            .ok_or_else(|| invalid("Invalid car", "make is required unless the VIN identifies it".to_string()))?,
+        make => make.to_string(),
+    };
+    let production_year = car_req
+        .production_year
+        .or_else(|| decoded.as_ref().and_then(|decoded| decoded.model_year).map(i64::from))
+        .ok_or_else(|| invalid("Invalid car", "productionYear is required unless the VIN gives it".to_string()))?;
+
+    if let Some(details) = decoded.as_ref().and_then(|decoded| vin::mismatch(decoded, &make, production_year)) {
+        return Err(invalid("VIN does not match car", details));
+    }
+
+    Ok((make, production_year, decoded.map(|decoded| decoded.vin)))
+}
+
+/// Another car, deleted or not, already holding the VIN.
+async fn vin_owner(conn: &mut SqliteConnection, vin: &str, car_id: Option<&str>) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT id AS "id!" FROM cars WHERE vin = ?1 AND (?2 IS NULL OR id != ?2)"#,
+        vin,
+        car_id
+    )
+    .fetch_optional(conn)
+    .await
+}
+
+fn vin_taken(vin: &str, car_id: i64) -> HttpResponse {
+    HttpResponse::Conflict().json(json!({
+        "error": "VIN already registered",
+        "details": format!("vin {} is already used by car {}", vin, car_id),
+        "carId": car_id
+    }))
+}
+
+/// Another car, deleted or not, already holding the plate.
+async fn plate_owner(conn: &mut SqliteConnection, key: &str, car_id: Option<&str>) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT id AS "id!" FROM cars WHERE plate_key = ?1 AND (?2 IS NULL OR id != ?2)"#,
+        key,
+        car_id
+    )
+    .fetch_optional(conn)
+    .await
+}
+
+fn plate_taken(plate: &str, car_id: i64) -> HttpResponse {
+    HttpResponse::Conflict().json(json!({
+        "error": "License plate already registered",
+        "details": format!("licensePlate {} is already used by car {}", plate, car_id),
+        "carId": car_id
+    }))
+}
+
 pub async fn create_car(
+    req: HttpRequest,
     data: web::Data<AppState>,
     car_req: web::Json<CreateCarRequest>,
 ) -> impl Responder {
     info!("Received request to create car: {:?}", car_req);
 
+    let Recipient { name: owner_name, email: owner_email, phone: owner_phone } = match owner_contact(&car_req) {
+        Ok(contact) => contact,
+        Err(response) => return response,
+    };
+
+    let (make, production_year, vin) = match vehicle_identity(&car_req, car_req.vin.as_deref()) {
+        Ok(identity) => identity,
+        Err(response) => return response,
+    };
+
+    let (license_plate, plate_key) = match license_plate(&car_req, data.plate_country) {
+        Ok(plate) => plate,
+        Err(response) => return response,
+    };
+
+    let actor = audit::actor(&req);
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("Failed to start transaction: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to start transaction",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    match plate_owner(&mut transaction, &plate_key, None).await {
+        Ok(Some(existing_id)) => return plate_taken(&license_plate, existing_id),
+        Ok(None) => {}
+        Err(err) => {
+            error!("Failed to check license plate: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to create car",
+                "details": err.to_string()
+            }));
+        }
+    }
+
+    if let Some(vin) = vin.as_deref() {
+        match vin_owner(&mut transaction, vin, None).await {
+            Ok(Some(existing_id)) => return vin_taken(vin, existing_id),
+            Ok(None) => {}
+            Err(err) => {
+                error!("Failed to check VIN: {:?}", err);
+                return HttpResponse::InternalServerError().json(json!({
+                    "error": "Failed to create car",
+                    "details": err.to_string()
+                }));
+            }
+        }
+    }
+
     match sqlx::query!(
         r#"
-        INSERT INTO cars (make, model, production_year, license_plate)
-        VALUES (?, ?, ?, ?)
+        INSERT INTO cars (make, model, production_year, license_plate, plate_key, vin, owner_name, owner_email, owner_phone)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
-        car_req.make,
+        make,
         car_req.model,
-        car_req.production_year,
-        car_req.license_plate
+        production_year,
+        license_plate,
+        plate_key,
+        vin,
+        owner_name,
+        owner_email,
+        owner_phone
     )
-    .execute(&data.pool)
+    .execute(&mut *transaction)
     .await
     {
         Ok(result) => {
@@ -38,7 +218,7 @@ pub async fn create_car(
                         car_id,
                         garage_id
                     )
-                    .execute(&data.pool)
+                    .execute(&mut *transaction)
                     .await
                     {
                         error!("Failed to associate car with garage: {:?}", err);
@@ -51,7 +231,7 @@ pub async fn create_car(
                             "#,
                             garage_id
                         )
-                        .fetch_one(&data.pool)
+                        .fetch_one(&mut *transaction)
                         .await
                         {
                             garage_details.push(json!({
@@ -66,21 +246,79 @@ pub async fn create_car(
                 }
             }
 
+            if let Err(err) = audit::record_change(
+                &mut transaction,
+                &actor,
+                AuditResource::Car,
+                &car_id.to_string(),
+                AuditAction::Create,
+                None,
+            )
+            .await
+            {
+                error!("Failed to write audit log: {:?}", err);
+                let _ = transaction.rollback().await;
+                return HttpResponse::InternalServerError().json(json!({
+                    "error": "Failed to create car",
+                    "details": err.to_string()
+                }));
+            }
+
+            if let Err(err) = transaction.commit().await {
+                error!("Failed to commit transaction: {:?}", err);
+                return HttpResponse::InternalServerError().json(json!({
+                    "error": "Failed to create car",
+                    "details": err.to_string()
+                }));
+            }
+
             HttpResponse::Created().json(Car {
                 id: Some(car_id),
-                make: Some(car_req.make.clone()),
+                make: Some(make),
                 model: Some(car_req.model.clone()),
-                production_year: Some(car_req.production_year),
-                license_plate: Some(car_req.license_plate.clone()),
+                production_year: Some(production_year),
+                license_plate: Some(license_plate),
+                vin,
                 garage_ids: car_req
                     .garage_ids
                     .as_ref()
                     .map(|ids| serde_json::to_value(ids).unwrap_or_default()),
                 garages: Some(serde_json::Value::Array(garage_details)),
+                owner_name,
+                owner_email,
+                owner_phone,
+                deleted_at: None,
             })
         }
+        // Taken by a car created since the checks above.
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+            let _ = transaction.rollback().await;
+            let mut conn = match data.pool.acquire().await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    error!("Failed to check license plate: {:?}", err);
+                    return HttpResponse::InternalServerError().json(json!({
+                        "error": "Failed to create car",
+                        "details": err.to_string()
+                    }));
+                }
+            };
+            if let Ok(Some(existing_id)) = plate_owner(&mut conn, &plate_key, None).await {
+                return plate_taken(&license_plate, existing_id);
+            }
+            if let Some(vin) = vin.as_deref() {
+                if let Ok(Some(existing_id)) = vin_owner(&mut conn, vin, None).await {
+                    return vin_taken(vin, existing_id);
+                }
+            }
+            HttpResponse::Conflict().json(json!({
+                "error": "Car already registered",
+                "details": format!("licensePlate {} or its VIN was just registered for another car", license_plate)
+            }))
+        }
         Err(err) => {
             error!("Database error creating car: {:?}", err);
+            let _ = transaction.rollback().await;
             HttpResponse::InternalServerError().json(json!({
                 "error": "Failed to create car",
                 "details": err.to_string()
@@ -89,9 +327,53 @@ pub async fn create_car(
     }
 }
 
-pub async fn get_all_cars(data: web::Data<AppState>) -> impl Responder {
+pub async fn get_all_cars(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    query: web::Query<IncludeDeletedQuery>,
+) -> impl Responder {
     info!("Starting get_all_cars request");
 
+    let include_deleted = query.include_deleted;
+
+    let format = match export::negotiate(&req) {
+        Ok(format) => format,
+        Err(response) => return response,
+    };
+
+    // Exports are flat: one row per car with its garage ids joined, streamed
+    // straight from the cursor instead of loading every car's garages.
+    if format != ExportFormat::Json {
+        let pool = data.pool.clone();
+        let rows = async_stream::try_stream! {
+            let mut cars = sqlx::query_as!(
+                CarExportRow,
+                r#"
+                SELECT
+                    cars.id AS "id!",
+                    cars.make,
+                    cars.model,
+                    cars.production_year,
+                    cars.license_plate,
+                    cars.vin,
+                    COALESCE(group_concat(car_garages.garage_id, ';'), '') AS "garage_ids!: String",
+                    cars.deleted_at
+                FROM cars
+                LEFT JOIN car_garages ON cars.id = car_garages.car_id
+                WHERE ?1 OR cars.deleted_at IS NULL
+                GROUP BY cars.id
+                ORDER BY cars.id
+                "#,
+                include_deleted
+            )
+            .fetch(&pool);
+            while let Some(car) = cars.try_next().await? {
+                yield car;
+            }
+        };
+        return export::respond(format, "cars", rows).await;
+    }
+
     let cars_with_garages = sqlx::query!(
         r#"
         SELECT
@@ -100,11 +382,18 @@ pub async fn get_all_cars(data: web::Data<AppState>) -> impl Responder {
             cars.model,
             cars.production_year,
             cars.license_plate,
+            cars.vin,
+            cars.owner_name,
+            cars.owner_email,
+            cars.owner_phone,
+            cars.deleted_at,
             COALESCE(json_group_array(car_garages.garage_id), '[]') as garage_ids
         FROM cars
         LEFT JOIN car_garages ON cars.id = car_garages.car_id
+        WHERE ?1 OR cars.deleted_at IS NULL
         GROUP BY cars.id
-        "#
+        "#,
+        include_deleted
     )
     .fetch_all(&data.pool)
     .await;
@@ -125,8 +414,10 @@ pub async fn get_all_cars(data: web::Data<AppState>) -> impl Responder {
                     FROM garages
                     JOIN car_garages ON garages.id = car_garages.garage_id
                     WHERE car_garages.car_id = ?
+                      AND (? OR garages.deleted_at IS NULL)
                     "#,
-                    row.id
+                    row.id,
+                    include_deleted
                 )
                 .fetch_all(&data.pool)
                 .await
@@ -151,10 +442,15 @@ pub async fn get_all_cars(data: web::Data<AppState>) -> impl Responder {
                     model: Some(row.model),
                     production_year: Some(row.production_year),
                     license_plate: Some(row.license_plate),
+                    vin: row.vin,
                     garage_ids: Some(serde_json::Value::Array(
                         serde_json::from_str(&row.garage_ids).unwrap_or_default(),
                     )),
                     garages: Some(serde_json::Value::Array(garage_details)),
+                    owner_name: row.owner_name,
+                    owner_email: row.owner_email,
+                    owner_phone: row.owner_phone,
+                    deleted_at: row.deleted_at,
                 });
             }
 
@@ -170,37 +466,255 @@ pub async fn get_all_cars(data: web::Data<AppState>) -> impl Responder {
     }
 }
 
-pub async fn delete_car(
-    id: web::Path<i64>, 
+/// What the VIN tells about a car without looking it up anywhere.
+pub async fn decode_vin(vin: web::Path<String>) -> impl Responder {
+    match vin::normalize(&vin) {
+        Ok(normalized) => HttpResponse::Ok().json(vin::decode(&normalized)),
+        Err(details) => HttpResponse::BadRequest().json(json!({
+            "error": "Invalid VIN",
+            "details": details
+        })),
+    }
+}
+
+/// Finds a car by plate however it is typed: `ab 123 cd` finds `AB-123-CD`.
+pub async fn get_car_by_plate(
+    plate: web::Path<String>,
     data: web::Data<AppState>,
+    query: web::Query<IncludeDeletedQuery>,
 ) -> impl Responder {
-    match sqlx::query!(
+    let plate = plate.into_inner();
+    let key = plates::plate_key(&plate);
+    if key.is_empty() {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid license plate",
+            "details": "licensePlate must contain letters or digits"
+        }));
+    }
+    let include_deleted = query.include_deleted;
+
+    let row = match sqlx::query!(
         r#"
-        DELETE FROM cars
-        WHERE id = ?
+        SELECT
+            cars.id AS "id!",
+            cars.make,
+            cars.model,
+            cars.production_year,
+            cars.license_plate,
+            cars.vin,
+            cars.owner_name,
+            cars.owner_email,
+            cars.owner_phone,
+            cars.deleted_at,
+            COALESCE(json_group_array(car_garages.garage_id) FILTER (WHERE car_garages.garage_id IS NOT NULL), '[]')
+                AS "garage_ids!: String"
+        FROM cars
+        LEFT JOIN car_garages ON cars.id = car_garages.car_id
+        WHERE cars.plate_key = ?1
+          AND (?2 OR cars.deleted_at IS NULL)
+        GROUP BY cars.id
         "#,
-        *id
+        key,
+        include_deleted
     )
-    .execute(&data.pool)
+    .fetch_optional(&data.pool)
     .await
     {
-        Ok(result) => {
-            if result.rows_affected() == 0 {
-                HttpResponse::NotFound().finish()
-            } else {
-                HttpResponse::Ok().json(true)
-            }
+        Ok(Some(row)) => row,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(json!({
+                "error": "Car not found",
+                "details": format!("No car found with licensePlate {}", plate.trim())
+            }));
         }
         Err(err) => {
-            HttpResponse::InternalServerError().json(json!({
-                "error": "Failed to delete car",
+            error!("Failed to look up car by plate: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch car",
                 "details": err.to_string()
-            }))
+            }));
         }
+    };
+
+    let garages = match sqlx::query!(
+        r#"
+        SELECT garages.id, garages.name, garages.location, garages.city, garages.capacity
+        FROM garages
+        JOIN car_garages ON garages.id = car_garages.garage_id
+        WHERE car_garages.car_id = ?
+          AND (? OR garages.deleted_at IS NULL)
+        "#,
+        row.id,
+        include_deleted
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(garages) => garages,
+        Err(err) => {
+            error!("Failed to fetch garages of car {}: {:?}", row.id, err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch car",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    HttpResponse::Ok().json(Car {
+        id: Some(row.id),
+        make: Some(row.make),
+        model: Some(row.model),
+        production_year: Some(row.production_year),
+        license_plate: Some(row.license_plate),
+        vin: row.vin,
+        garage_ids: Some(serde_json::Value::Array(
+            serde_json::from_str(&row.garage_ids).unwrap_or_default(),
+        )),
+        garages: Some(serde_json::Value::Array(
+            garages
+                .into_iter()
+                .map(|garage| {
+                    json!({
+                        "id": garage.id,
+                        "name": garage.name,
+                        "location": garage.location,
+                        "city": garage.city,
+                        "capacity": garage.capacity,
+                    })
+                })
+                .collect(),
+        )),
+        owner_name: row.owner_name,
+        owner_email: row.owner_email,
+        owner_phone: row.owner_phone,
+        deleted_at: row.deleted_at,
+    })
+}
+
+pub async fn delete_car(
+    req: HttpRequest,
+    id: web::Path<i64>, 
+    data: web::Data<AppState>,
+) -> impl Responder {
+    set_car_deleted(&req, &data, *id, true).await
+}
+
+pub async fn restore_car(
+    req: HttpRequest,
+    id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    info!("Received request to restore car with ID {}", id);
+
+    set_car_deleted(&req, &data, *id, false).await
+}
+
+async fn set_car_deleted(
+    req: &HttpRequest,
+    data: &AppState,
+    car_id: i64,
+    deleted: bool,
+) -> HttpResponse {
+    let (action, failure) = if deleted {
+        (AuditAction::Delete, "Failed to delete car")
+    } else {
+        (AuditAction::Restore, "Failed to restore car")
+    };
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("Failed to start transaction: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to start transaction",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let resource_id = car_id.to_string();
+    let before = match audit::snapshot(&mut transaction, AuditResource::Car, &resource_id).await {
+        Ok(before) => before,
+        Err(err) => {
+            error!("Failed to read car before change: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": failure,
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let result = if deleted {
+        sqlx::query!(
+            r#"
+            UPDATE cars
+            SET deleted_at = CURRENT_TIMESTAMP
+            WHERE id = ? AND deleted_at IS NULL
+            "#,
+            car_id
+        )
+        .execute(&mut *transaction)
+        .await
+    } else {
+        sqlx::query!(
+            r#"
+            UPDATE cars
+            SET deleted_at = NULL
+            WHERE id = ? AND deleted_at IS NOT NULL
+            "#,
+            car_id
+        )
+        .execute(&mut *transaction)
+        .await
+    };
+
+    match result {
+        Ok(result) if result.rows_affected() == 0 => {
+            let _ = transaction.rollback().await;
+            return HttpResponse::NotFound().finish();
+        }
+        Ok(_) => {}
+        Err(err) => {
+            error!("{}: {:?}", failure, err);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json(json!({
+                "error": failure,
+                "details": err.to_string()
+            }));
+        }
+    }
+
+    if let Err(err) = audit::record_change(
+        &mut transaction,
+        &audit::actor(req),
+        AuditResource::Car,
+        &resource_id,
+        action,
+        before,
+    )
+    .await
+    {
+        error!("Failed to write audit log: {:?}", err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": failure,
+            "details": err.to_string()
+        }));
+    }
+
+    if let Err(err) = transaction.commit().await {
+        error!("Failed to commit transaction: {:?}", err);
+        return HttpResponse::InternalServerError().json(json!({
+            "error": failure,
+            "details": err.to_string()
+        }));
     }
+
+    HttpResponse::Ok().json(true)
 }
 
 pub async fn edit_car(
+    req: HttpRequest,
     id: web::Path<String>,
     car_req: web::Json<CreateCarRequest>, 
     data: web::Data<AppState>,
@@ -209,6 +723,40 @@ pub async fn edit_car(
 
     let car_id = id.as_str(); 
 
+    let Recipient { name: owner_name, email: owner_email, phone: owner_phone } = match owner_contact(&car_req) {
+        Ok(contact) => contact,
+        Err(response) => return response,
+    };
+
+    // Leaving `vin` out keeps the stored one, which the make and year must
+    // still match; an empty `vin` removes it.
+    let stored_vin = match &car_req.vin {
+        Some(_) => None,
+        None => match sqlx::query_scalar!("SELECT vin FROM cars WHERE id = ? AND deleted_at IS NULL", car_id)
+            .fetch_optional(&data.pool)
+            .await
+        {
+            Ok(vin) => vin.flatten(),
+            Err(err) => {
+                error!("Failed to fetch car {}: {:?}", car_id, err);
+                return HttpResponse::InternalServerError().json(json!({
+                    "error": "Failed to update car",
+                    "details": err.to_string()
+                }));
+            }
+        },
+    };
+
+    let (make, production_year, vin) = match vehicle_identity(&car_req, car_req.vin.as_deref().or(stored_vin.as_deref())) {
+        Ok(identity) => identity,
+        Err(response) => return response,
+    };
+
+    let (license_plate, plate_key) = match license_plate(&car_req, data.plate_country) {
+        Ok(plate) => plate,
+        Err(response) => return response,
+    };
+
     let mut transaction = match data.pool.begin().await {
         Ok(tx) => tx,
         Err(err) => {
@@ -220,27 +768,77 @@ pub async fn edit_car(
         }
     };
 
-    if let Err(err) = sqlx::query!(
+    let before = match audit::snapshot(&mut transaction, AuditResource::Car, car_id).await {
+        Ok(before) => before,
+        Err(err) => {
+            error!("Failed to read car before update: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update car",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    match plate_owner(&mut transaction, &plate_key, Some(car_id)).await {
+        Ok(Some(existing_id)) => return plate_taken(&license_plate, existing_id),
+        Ok(None) => {}
+        Err(err) => {
+            error!("Failed to check license plate: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update car",
+                "details": err.to_string()
+            }));
+        }
+    }
+
+    if let Some(vin) = vin.as_deref() {
+        match vin_owner(&mut transaction, vin, Some(car_id)).await {
+            Ok(Some(existing_id)) => return vin_taken(vin, existing_id),
+            Ok(None) => {}
+            Err(err) => {
+                error!("Failed to check VIN: {:?}", err);
+                return HttpResponse::InternalServerError().json(json!({
+                    "error": "Failed to update car",
+                    "details": err.to_string()
+                }));
+            }
+        }
+    }
+
+    match sqlx::query!(
         r#"
         UPDATE cars
-        SET make = ?, model = ?, production_year = ?, license_plate = ?
-        WHERE id = ?
+        SET make = ?, model = ?, production_year = ?, license_plate = ?, plate_key = ?, vin = ?,
+            owner_name = ?, owner_email = ?, owner_phone = ?
+        WHERE id = ? AND deleted_at IS NULL
         "#,
-        car_req.make,
+        make,
         car_req.model,
-        car_req.production_year,
-        car_req.license_plate,
+        production_year,
+        license_plate,
+        plate_key,
+        vin,
+        owner_name,
+        owner_email,
+        owner_phone,
         car_id 
     )
     .execute(&mut *transaction)
     .await
     {
-        error!("Failed to update car: {:?}", err);
-        let _ = transaction.rollback().await;
-        return HttpResponse::InternalServerError().json(json!({
-            "error": "Failed to update car",
-            "details": err.to_string()
-        }));
+        Ok(result) if result.rows_affected() == 0 => {
+            let _ = transaction.rollback().await;
+            return HttpResponse::NotFound().finish();
+        }
+        Ok(_) => {}
+        Err(err) => {
+            error!("Failed to update car: {:?}", err);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update car",
+                "details": err.to_string()
+            }));
+        }
     }
 
     if let Err(err) = sqlx::query!(
@@ -284,6 +882,24 @@ pub async fn edit_car(
         }
     }
 
+    if let Err(err) = audit::record_change(
+        &mut transaction,
+        &audit::actor(&req),
+        AuditResource::Car,
+        car_id,
+        AuditAction::Update,
+        before,
+    )
+    .await
+    {
+        error!("Failed to write audit log: {:?}", err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to update car",
+            "details": err.to_string()
+        }));
+    }
+
     if let Err(err) = transaction.commit().await {
         error!("Failed to commit transaction: {:?}", err);
         return HttpResponse::InternalServerError().json(json!({
@@ -294,10 +910,14 @@ pub async fn edit_car(
 
     HttpResponse::Ok().json(json!({
         "id": car_id,
-        "make": car_req.make,
+        "make": make,
         "model": car_req.model,
-        "productionYear": car_req.production_year,
-        "licensePlate": car_req.license_plate,
-        "garageIds": car_req.garage_ids
+        "productionYear": production_year,
+        "licensePlate": license_plate,
+        "vin": vin,
+        "garageIds": car_req.garage_ids,
+        "ownerName": owner_name,
+        "ownerEmail": owner_email,
+        "ownerPhone": owner_phone
     }))
 }
@@ -0,0 +1,100 @@
+use crate::app_state::AppState;
+use crate::events::{self, LoggedEvent};
+use crate::models::event::EventStreamQueryParams;
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::web::Bytes;
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use log::{error, info};
+use tokio::sync::broadcast::error::RecvError;
+
+const LAST_EVENT_ID_HEADER: &str = "Last-Event-ID";
+const KEEP_ALIVE_EVERY: std::time::Duration = std::time::Duration::from_secs(15);
+/// Events read from the log per query while catching up.
+const CATCH_UP_PAGE_SIZE: i64 = 1000;
+
+fn sse_frame(event: &LoggedEvent) -> Bytes {
+    Bytes::from(format!(
+        "id: {}\nevent: {}\ndata: {}\n\n",
+        event.sequence, event.event_type, event.payload
+    ))
+}
+
+/// Streams booking and capacity events as Server-Sent Events, optionally
+/// for one garage. Clients that reconnect with `Last-Event-ID` first get
+/// what they missed from the event log.
+pub async fn get_event_stream(
+    req: HttpRequest,
+    query: web::Query<EventStreamQueryParams>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let garage_id = query.garage_id;
+    let resume_from = req
+        .headers()
+        .get(LAST_EVENT_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<i64>().ok());
+    info!("Opening event stream (garage: {:?}, resuming after: {:?})", garage_id, resume_from);
+
+    // Subscribe before catching up so nothing committed in between is lost;
+    // the sequence check drops what the catch-up already sent.
+    let mut receiver = data.events.subscribe();
+    let pool = data.pool.clone();
+
+    let body = async_stream::stream! {
+        let mut last_sent = resume_from;
+        yield Ok::<_, actix_web::Error>(Bytes::from_static(b": connected\n\n"));
+
+        // Page through everything missed before going live; stopping early
+        // would skip whatever lies between the last page and live events.
+        while let Some(after) = last_sent {
+            let missed = match events::logged_since(&pool, after, garage_id, CATCH_UP_PAGE_SIZE).await {
+                Ok(missed) => missed,
+                Err(err) => {
+                    error!("Failed to replay events after {}: {:?}", after, err);
+                    break;
+                }
+            };
+            for event in &missed {
+                last_sent = Some(event.sequence);
+                yield Ok(sse_frame(event));
+            }
+            if (missed.len() as i64) < CATCH_UP_PAGE_SIZE {
+                break;
+            }
+        }
+
+        let mut keep_alive = tokio::time::interval(KEEP_ALIVE_EVERY);
+        keep_alive.tick().await;
+
+        loop {
+            tokio::select! {
+                received = receiver.recv() => match received {
+                    Ok(event) => {
+                        if last_sent.is_some_and(|last| event.sequence <= last) {
+                            continue;
+                        }
+                        last_sent = Some(event.sequence);
+                        if garage_id.is_none() || event.garage_id == garage_id {
+                            yield Ok(sse_frame(&event));
+                        }
+                    }
+                    // Too slow to keep up; the client can resume from the
+                    // last id it saw.
+                    Err(RecvError::Lagged(skipped)) => {
+                        yield Ok(Bytes::from(format!("event: lagged\ndata: {}\n\n", skipped)));
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = keep_alive.tick() => {
+                    yield Ok(Bytes::from_static(b": keep-alive\n\n"));
+                }
+            }
+        }
+    };
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(body)
+}
@@ -1,11 +1,59 @@
-use crate::{app_state::AppState, models::garage::{CreateGarageRequest, Garage, GarageReportQueryParams, GarageDailyAvailabilityReportDTO }};
-use actix_web::{web, HttpResponse, Responder};
+use crate::{app_state::AppState, audit::{self, AuditAction, AuditResource}, export::{self, ExportFormat}, models::common::IncludeDeletedQuery, models::garage::{CreateGarageRequest, Garage, GarageBookingDTO, GarageReportQueryParams, GarageDailyAvailabilityReportDTO, GarageScheduleDayDTO, GarageScheduleQueryParams, RescheduleGarageDayDTO, RescheduleReportDTO }, models::maintenance::MaintenanceStatus, scheduler, slots};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use futures_util::TryStreamExt;
 use serde::Deserialize;
-use sqlx::{query, query_as};
+use sqlx::{query, query_scalar};
+
+pub const DEFAULT_OPENING_TIME: &str = "08:00";
+pub const DEFAULT_CLOSING_TIME: &str = "18:00";
+pub const DEFAULT_SLOT_MINUTES: i64 = 60;
+
+/// Opening hours must be valid `HH:MM` times, open before they close and
+/// fit at least one slot.
+pub fn hours_error(opening_time: &str, closing_time: &str, slot_minutes: i64) -> Option<String> {
+    match (slots::parse_time(opening_time), slots::parse_time(closing_time)) {
+        (Some(opening), Some(closing)) if opening < closing => {
+            if slot_minutes <= 0 || slot_minutes > closing - opening {
+                Some("slotMinutes must be positive and fit within opening hours".to_string())
+            } else {
+                None
+            }
+        }
+        (Some(_), Some(_)) => Some("openingTime must be before closingTime".to_string()),
+        _ => Some("openingTime and closingTime must be formatted as HH:MM".to_string()),
+    }
+}
+
+pub async fn get_all_garages(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    query: web::Query<IncludeDeletedQuery>,
+) -> impl Responder {
+    let format = match export::negotiate(&req) {
+        Ok(format) => format,
+        Err(response) => return response,
+    };
+
+    if format != ExportFormat::Json {
+        let pool = data.pool.clone();
+        let include_deleted = query.include_deleted;
+        let rows = async_stream::try_stream! {
+            let mut garages = sqlx::query_as!(
+                Garage,
+                "SELECT id, name, location, city, capacity, opening_time, closing_time, slot_minutes, deleted_at FROM garages WHERE ?1 OR deleted_at IS NULL ORDER BY id",
+                include_deleted
+            )
+            .fetch(&pool);
+            while let Some(garage) = garages.try_next().await? {
+                yield garage;
+            }
+        };
+        return export::respond(format, "garages", rows).await;
+    }
 
-pub async fn get_all_garages(data: web::Data<AppState>) -> impl Responder {
     let garages = sqlx::query!(
-        "SELECT id, name, location, city, capacity FROM garages"
+        "SELECT id, name, location, city, capacity, opening_time, closing_time, slot_minutes, deleted_at FROM garages WHERE ? OR deleted_at IS NULL",
+        query.include_deleted
     )
     .fetch_all(&data.pool)
     .await;
@@ -20,6 +68,10 @@ pub async fn get_all_garages(data: web::Data<AppState>) -> impl Responder {
                     location: row.location,
                     city: row.city,
                     capacity: row.capacity,
+                    opening_time: row.opening_time,
+                    closing_time: row.closing_time,
+                    slot_minutes: row.slot_minutes,
+                    deleted_at: row.deleted_at,
                 })
                 .collect();
 
@@ -30,103 +82,290 @@ pub async fn get_all_garages(data: web::Data<AppState>) -> impl Responder {
 }
 
 pub async fn create_garage(
+    req: HttpRequest,
     data: web::Data<AppState>,
     garage_req: web::Json<CreateGarageRequest>,
 ) -> impl Responder {
+    let opening_time = garage_req.opening_time.clone().unwrap_or_else(|| DEFAULT_OPENING_TIME.to_string());
+    let closing_time = garage_req.closing_time.clone().unwrap_or_else(|| DEFAULT_CLOSING_TIME.to_string());
+    let slot_minutes = garage_req.slot_minutes.unwrap_or(DEFAULT_SLOT_MINUTES);
+    if let Some(details) = hours_error(&opening_time, &closing_time, slot_minutes) {
+        return HttpResponse::BadRequest().body(details);
+    }
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(_) => return HttpResponse::InternalServerError().body("Failed to start transaction"),
+    };
+
     let result = sqlx::query!(
-        "INSERT INTO garages (name, location, city, capacity) VALUES (?, ?, ?, ?)",
+        "INSERT INTO garages (name, location, city, capacity, opening_time, closing_time, slot_minutes) VALUES (?, ?, ?, ?, ?, ?, ?)",
         garage_req.name,
         garage_req.location,
         garage_req.city,
-        garage_req.capacity
+        garage_req.capacity,
+        opening_time,
+        closing_time,
+        slot_minutes
+    )
+    .execute(&mut *transaction)
+    .await;
+
+    let new_id = match result {
+        Ok(query_result) => query_result.last_insert_rowid(),
+        Err(err) => {
+            return HttpResponse::InternalServerError().json(format!("Failed to create garage: {}", err))
+        }
+    };
+
+    if let Err(err) = audit::record_change(
+        &mut transaction,
+        &audit::actor(&req),
+        AuditResource::Garage,
+        &new_id.to_string(),
+        AuditAction::Create,
+        None,
+    )
+    .await
+    {
+        log::error!("Failed to write audit log: {:?}", err);
+        return HttpResponse::InternalServerError().json(format!("Failed to create garage: {}", err));
+    }
+
+    if let Err(err) = transaction.commit().await {
+        return HttpResponse::InternalServerError().json(format!("Failed to create garage: {}", err));
+    }
+
+    let garage = Garage {
+        id: new_id,
+        name: garage_req.name.clone(),
+        location: garage_req.location.clone(),
+        city: garage_req.city.clone(),
+        capacity: garage_req.capacity,
+        opening_time,
+        closing_time,
+        slot_minutes,
+        deleted_at: None,
+    };
+
+    HttpResponse::Ok().json(garage)
+}
+
+pub async fn delete_garage(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    garage_id: web::Path<String>,
+) -> impl Responder {
+    let id = garage_id.into_inner(); 
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(_) => return HttpResponse::InternalServerError().body("Failed to delete garage"),
+    };
+
+    let before = match audit::snapshot(&mut transaction, AuditResource::Garage, &id).await {
+        Ok(before) => before,
+        Err(_) => return HttpResponse::InternalServerError().body("Failed to delete garage"),
+    };
+
+    let result = sqlx::query!(
+        "UPDATE garages SET deleted_at = CURRENT_TIMESTAMP WHERE id = ? AND deleted_at IS NULL",
+        id
     )
-    .execute(&data.pool)
+    .execute(&mut *transaction)
     .await;
 
     match result {
-        Ok(query_result) => {
-            let new_id = query_result.last_insert_rowid();
+        Ok(query_result) if query_result.rows_affected() == 0 => return HttpResponse::NotFound().body("Garage not found"),
+        Ok(_) => {}
+        Err(_) => return HttpResponse::InternalServerError().body("Failed to delete garage"),
+    }
 
-            let garage = Garage {
-                id: new_id,
-                name: garage_req.name.clone(),
-                location: garage_req.location.clone(),
-                city: garage_req.city.clone(),
-                capacity: garage_req.capacity,
-            };
+    let audited = audit::record_change(
+        &mut transaction,
+        &audit::actor(&req),
+        AuditResource::Garage,
+        &id,
+        AuditAction::Delete,
+        before,
+    )
+    .await;
 
-            HttpResponse::Ok().json(garage)
-        }
+    match audited {
+        Ok(_) => match transaction.commit().await {
+            Ok(_) => HttpResponse::Ok().body("Garage deleted successfully"),
+            Err(_) => HttpResponse::InternalServerError().body("Failed to delete garage"),
+        },
         Err(err) => {
-            HttpResponse::InternalServerError().json(format!("Failed to create garage: {}", err))
+            log::error!("Failed to write audit log: {:?}", err);
+            HttpResponse::InternalServerError().body("Failed to delete garage")
         }
     }
 }
 
-pub async fn delete_garage(
+pub async fn restore_garage(
+    req: HttpRequest,
     data: web::Data<AppState>,
     garage_id: web::Path<String>,
 ) -> impl Responder {
-    let id = garage_id.into_inner(); 
+    let id = garage_id.into_inner();
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(_) => return HttpResponse::InternalServerError().body("Failed to restore garage"),
+    };
+
+    let before = match audit::snapshot(&mut transaction, AuditResource::Garage, &id).await {
+        Ok(before) => before,
+        Err(_) => return HttpResponse::InternalServerError().body("Failed to restore garage"),
+    };
+
     let result = sqlx::query!(
-        "DELETE FROM garages WHERE id = ?",
+        "UPDATE garages SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL",
         id
     )
-    .execute(&data.pool)
+    .execute(&mut *transaction)
     .await;
 
     match result {
-        Ok(_) => HttpResponse::Ok().body("Garage deleted successfully"),
-        Err(_) => HttpResponse::InternalServerError().body("Failed to delete garage"),
+        Ok(query_result) if query_result.rows_affected() == 0 => return HttpResponse::NotFound().body("Deleted garage not found"),
+        Ok(_) => {}
+        Err(_) => return HttpResponse::InternalServerError().body("Failed to restore garage"),
+    }
+
+    let audited = audit::record_change(
+        &mut transaction,
+        &audit::actor(&req),
+        AuditResource::Garage,
+        &id,
+        AuditAction::Restore,
+        before,
+    )
+    .await;
+
+    match audited {
+        Ok(_) => match transaction.commit().await {
+            Ok(_) => HttpResponse::Ok().body("Garage restored successfully"),
+            Err(_) => HttpResponse::InternalServerError().body("Failed to restore garage"),
+        },
+        Err(err) => {
+            log::error!("Failed to write audit log: {:?}", err);
+            HttpResponse::InternalServerError().body("Failed to restore garage")
+        }
     }
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct EditGarageRequest {
     name: Option<String>,
     location: Option<String>,
     city: Option<String>,
     capacity: Option<i64>,
+    opening_time: Option<String>,
+    closing_time: Option<String>,
+    slot_minutes: Option<i64>,
 }
 
 pub async fn edit_garage(
+    req: HttpRequest,
     data: web::Data<AppState>,
     garage_id: web::Path<String>,
     garage_req: web::Json<EditGarageRequest>,
 ) -> impl Responder {
     let id = garage_id.into_inner(); 
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(_) => return HttpResponse::InternalServerError().body("Failed to update garage"),
+    };
+
+    let before = match audit::snapshot(&mut transaction, AuditResource::Garage, &id).await {
+        Ok(before) => before,
+        Err(_) => return HttpResponse::InternalServerError().body("Failed to update garage"),
+    };
+
+    let current = match sqlx::query!(
+        "SELECT opening_time, closing_time, slot_minutes FROM garages WHERE id = ? AND deleted_at IS NULL",
+        id
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    {
+        Ok(Some(current)) => current,
+        Ok(None) => return HttpResponse::NotFound().body("Garage not found"),
+        Err(_) => return HttpResponse::InternalServerError().body("Failed to update garage"),
+    };
+
+    let opening_time = garage_req.opening_time.clone().unwrap_or(current.opening_time);
+    let closing_time = garage_req.closing_time.clone().unwrap_or(current.closing_time);
+    let slot_minutes = garage_req.slot_minutes.unwrap_or(current.slot_minutes);
+    if let Some(details) = hours_error(&opening_time, &closing_time, slot_minutes) {
+        return HttpResponse::BadRequest().body(details);
+    }
+
     let result = sqlx::query!(
         "UPDATE garages 
         SET 
             name = COALESCE(?, name),
             location = COALESCE(?, location),
             city = COALESCE(?, city),
-            capacity = COALESCE(?, capacity)
-        WHERE id = ?",
+            capacity = COALESCE(?, capacity),
+            opening_time = ?,
+            closing_time = ?,
+            slot_minutes = ?
+        WHERE id = ? AND deleted_at IS NULL",
         garage_req.name,
         garage_req.location,
         garage_req.city,
         garage_req.capacity,
+        opening_time,
+        closing_time,
+        slot_minutes,
         id
     )
-    .execute(&data.pool)
+    .execute(&mut *transaction)
     .await;
 
     match result {
-        Ok(_) => HttpResponse::Ok().body("Garage updated successfully"),
-        Err(_) => HttpResponse::InternalServerError().body("Failed to update garage"),
+        Ok(query_result) if query_result.rows_affected() == 0 => return HttpResponse::NotFound().body("Garage not found"),
+        Ok(_) => {}
+        Err(_) => return HttpResponse::InternalServerError().body("Failed to update garage"),
+    }
+
+    let audited = audit::record_change(
+        &mut transaction,
+        &audit::actor(&req),
+        AuditResource::Garage,
+        &id,
+        AuditAction::Update,
+        before,
+    )
+    .await;
+
+    match audited {
+        Ok(_) => match transaction.commit().await {
+            Ok(_) => {
+                data.events.relay(&data.pool).await;
+                HttpResponse::Ok().body("Garage updated successfully")
+            }
+            Err(_) => HttpResponse::InternalServerError().body("Failed to update garage"),
+        },
+        Err(err) => {
+            log::error!("Failed to write audit log: {:?}", err);
+            HttpResponse::InternalServerError().body("Failed to update garage")
+        }
     }
 }
 
 pub async fn get_single_garage(
     data: web::Data<AppState>,
     garage_id: web::Path<String>,
+    query: web::Query<IncludeDeletedQuery>,
 ) -> impl Responder {
     let id = garage_id.into_inner();
 
     let result = sqlx::query!(
-        "SELECT id, name, location, city, capacity FROM garages WHERE id = ?",
-        id
+        "SELECT id, name, location, city, capacity, opening_time, closing_time, slot_minutes, deleted_at FROM garages WHERE id = ? AND (? OR deleted_at IS NULL)",
+        id,
+        query.include_deleted
     )
     .fetch_one(&data.pool)
     .await;
@@ -139,6 +378,10 @@ pub async fn get_single_garage(
                 location: row.location,
                 city: row.city,
                 capacity: row.capacity,
+                opening_time: row.opening_time,
+                closing_time: row.closing_time,
+                slot_minutes: row.slot_minutes,
+                deleted_at: row.deleted_at,
             };
             HttpResponse::Ok().json(garage)
         }
@@ -151,31 +394,53 @@ pub async fn get_single_garage(
 
 
 pub async fn get_garage_report(
+    req: HttpRequest,
     data: web::Data<AppState>,
     query_params: web::Query<GarageReportQueryParams>,
 ) -> impl Responder {
     log::debug!("Received request parameters: {:?}", query_params);
-    
+
+    let format = match export::negotiate(&req) {
+        Ok(format) => format,
+        Err(response) => return response,
+    };
+
     let garage_id = query_params.garage_id;
-    log::debug!("Processing request for garage_id: {}", garage_id);
 
-    log::debug!("Querying garage with id orspu: {}", garage_id);
+    let (start_date, end_date) = match scheduler::parse_date_range(
+        &query_params.start_date,
+        &query_params.end_date,
+        data.report_max_days,
+    ) {
+        Ok(range) => range,
+        Err(details) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Invalid date range",
+                "details": details
+            }));
+        }
+    };
 
-    let garage = query!(
-        r#"
-        SELECT id, capacity 
-        FROM garages 
-        WHERE id = ?1
-        "#,
-        garage_id
-    )
-    .fetch_optional(&data.pool)
-    .await;
+    let mut conn = match data.pool.acquire().await {
+        Ok(conn) => conn,
+        Err(err) => {
+            log::error!("Failed to acquire connection: {:?}", err);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Database error",
+                "details": err.to_string()
+            }));
+        }
+    };
 
-    let garage = match garage {
-        Ok(Some(garage)) => {
-            log::debug!("Found garage with id {}: capacity {}", garage.id, garage.capacity);
-            garage
+    let hours = match slots::garage_hours(&mut conn, garage_id).await {
+        Ok(Some(hours)) => {
+            log::debug!(
+                "Found garage with id {}: daily capacity {} ({} bays)",
+                garage_id,
+                hours.daily_capacity(),
+                hours.bays.len()
+            );
+            hours
         }
         Ok(None) => {
             log::error!("No garage found with id {}", garage_id);
@@ -193,62 +458,288 @@ pub async fn get_garage_report(
         }
     };
 
-    let start_date = &query_params.start_date;
-    let end_date = &query_params.end_date;
-    
-    log::debug!("Fetching availability report for garage {} between {} and {}", 
-        garage_id, start_date, end_date);
+    let start = start_date.format(scheduler::DATE_FORMAT).to_string();
+    let end = end_date.format(scheduler::DATE_FORMAT).to_string();
+
+    log::debug!("Fetching availability report for garage {} between {} and {}", garage_id, start, end);
+
+    let rows = match query!(
+        r#"
+        SELECT
+            date(scheduled_date) AS "date!: String",
+            duration_minutes,
+            COUNT(*) AS "bookings!: i32"
+        FROM maintenance
+        WHERE garage_id = ?
+          AND status != 'CANCELLED'
+          AND deleted_at IS NULL
+          AND date(scheduled_date) BETWEEN ? AND ?
+        GROUP BY 1, 2
+        "#,
+        garage_id,
+        start,
+        end
+    )
+    .fetch_all(&mut *conn)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(err) => {
+            log::error!("Failed to generate availability report: {:?}", err);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch daily availability report",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    // Bay garages are planned in slots and a booking takes as many slots as
+    // its duration needs; otherwise one booking is one unit of capacity.
+    let mut used: std::collections::HashMap<String, (i32, i64)> = std::collections::HashMap::new();
+    for row in rows {
+        let units = if hours.uses_bays() {
+            hours.units_for(row.duration_minutes.unwrap_or(hours.slot_minutes))
+        } else {
+            1
+        };
+        let day = used.entry(row.date).or_default();
+        day.0 += row.bookings;
+        day.1 += units * i64::from(row.bookings);
+    }
+
+    let daily_capacity = hours.daily_capacity();
+    let report: Vec<GarageDailyAvailabilityReportDTO> = scheduler::report_days(start_date, end_date)
+        .map(|date| {
+            let (requests, used_units) = used.get(&date).copied().unwrap_or_default();
+            GarageDailyAvailabilityReportDTO {
+                date,
+                requests,
+                available_capacity: (daily_capacity - used_units) as i32,
+            }
+        })
+        .collect();
+
+    log::debug!("Successfully generated report with {} records", report.len());
+    export::respond_with(format, "daily-availability", report).await
+}
+
+pub async fn get_garage_schedule(
+    data: web::Data<AppState>,
+    garage_id: web::Path<i64>,
+    query_params: web::Query<GarageScheduleQueryParams>,
+) -> impl Responder {
+    let garage_id = garage_id.into_inner();
+    log::debug!("Received schedule request for garage {}: {:?}", garage_id, query_params);
+
+    let (start_date, end_date) = match scheduler::parse_date_range(
+        &query_params.start_date,
+        &query_params.end_date,
+        data.report_max_days,
+    ) {
+        Ok(range) => range,
+        Err(details) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Invalid date range",
+                "details": details
+            }));
+        }
+    };
+    let start_date = start_date.format(scheduler::DATE_FORMAT).to_string();
+    let end_date = end_date.format(scheduler::DATE_FORMAT).to_string();
+
+    match query_scalar!(
+        "SELECT id FROM garages WHERE id = ? AND deleted_at IS NULL",
+        garage_id
+    )
+    .fetch_optional(&data.pool)
+    .await
+    {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Garage not found",
+                "details": format!("No garage found with id {}", garage_id)
+            }));
+        }
+        Err(err) => {
+            log::error!("Database error while fetching garage {}: {:?}", garage_id, err);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Database error",
+                "details": err.to_string()
+            }));
+        }
+    }
 
-    let result = query_as!(
-        GarageDailyAvailabilityReportDTO,
+    let rows = query!(
         r#"
-        WITH RECURSIVE dates(date) AS (
-            SELECT date(?1) as date
-            UNION ALL
-            SELECT date(date, '+1 day')
-            FROM dates
-            WHERE date < date(?2)
-        ),
-        daily_counts AS (
-            SELECT 
-                date(scheduled_date) as scheduled_date,
-                COUNT(*) as request_count
-            FROM maintenance
-            WHERE garage_id = ?3
-            AND date(scheduled_date) BETWEEN date(?1) AND date(?2)
-            GROUP BY date(scheduled_date)
-        )
-        SELECT 
-            dates.date as "date!: String",
-            CAST(COALESCE(daily_counts.request_count, 0) as INTEGER) as "requests!: i32",
-            CAST(
-                ?4 - COALESCE(daily_counts.request_count, 0)
-                as INTEGER
-            ) as "available_capacity!: i32"
-        FROM dates
-        LEFT JOIN daily_counts ON dates.date = daily_counts.scheduled_date
-        ORDER BY dates.date
+        SELECT
+            date(maintenance.scheduled_date) AS "date!: String",
+            maintenance.id AS "maintenance_id!",
+            maintenance.car_id,
+            cars.make || ' ' || cars.model AS "car_name!: String",
+            cars.license_plate,
+            maintenance.service_type,
+            maintenance.status AS "status: MaintenanceStatus",
+            maintenance.bay_id,
+            maintenance.start_time,
+            maintenance.duration_minutes,
+            maintenance.mechanic_id,
+            maintenance.notes
+        FROM maintenance
+        JOIN cars ON maintenance.car_id = cars.id
+        WHERE maintenance.garage_id = ?1
+          AND maintenance.deleted_at IS NULL
+          AND (?4 OR maintenance.status != 'CANCELLED')
+          AND date(maintenance.scheduled_date) BETWEEN ?2 AND ?3
+        ORDER BY date(maintenance.scheduled_date), maintenance.start_time, maintenance.id
         "#,
+        garage_id,
         start_date,
         end_date,
-        garage_id,
-        garage.capacity
+        query_params.include_cancelled
     )
     .fetch_all(&data.pool)
     .await;
 
-    match result {
-        Ok(records) => {
-            log::debug!("Successfully generated report with {} records", records.len());
-            HttpResponse::Ok().json(records)
+    match rows {
+        Ok(rows) => {
+            let mut schedule: Vec<GarageScheduleDayDTO> = Vec::new();
+            for row in rows {
+                let booking = GarageBookingDTO {
+                    maintenance_id: row.maintenance_id,
+                    car_id: row.car_id,
+                    car_name: row.car_name,
+                    license_plate: row.license_plate,
+                    service_type: row.service_type,
+                    status: row.status,
+                    bay_id: row.bay_id,
+                    start_time: row.start_time,
+                    duration_minutes: row.duration_minutes,
+                    mechanic_id: row.mechanic_id,
+                    notes: row.notes,
+                };
+                match schedule.last_mut() {
+                    Some(day) if day.date == row.date => day.bookings.push(booking),
+                    _ => schedule.push(GarageScheduleDayDTO {
+                        date: row.date,
+                        bookings: vec![booking],
+                    }),
+                }
+            }
+
+            HttpResponse::Ok().json(schedule)
         }
         Err(err) => {
-            log::error!("Failed to generate availability report: {:?}", err);
+            log::error!("Failed to fetch schedule for garage {}: {:?}", garage_id, err);
             HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch daily availability report",
+                "error": "Failed to fetch garage schedule",
                 "details": err.to_string()
             }))
         }
     }
 }
 
+pub async fn reschedule_garage_day(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    garage_id: web::Path<i64>,
+    reschedule_req: web::Json<RescheduleGarageDayDTO>,
+) -> impl Responder {
+    let garage_id = garage_id.into_inner();
+    log::info!("Received reschedule request for garage {}: {:?}", garage_id, reschedule_req);
+
+    let Some(date) = scheduler::parse_date(&reschedule_req.date) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Invalid date",
+            "details": "date must be formatted as YYYY-MM-DD"
+        }));
+    };
+    let max_days_ahead = reschedule_req.max_days_ahead.unwrap_or(scheduler::MAX_SHIFT_DAYS);
+    // Each day ahead is searched for a free slot, so the search is capped
+    // like the scheduler's own horizon.
+    if max_days_ahead > scheduler::MAX_HORIZON_DAYS as u64 {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Invalid maxDaysAhead",
+            "details": format!("maxDaysAhead must be at most {}", scheduler::MAX_HORIZON_DAYS)
+        }));
+    }
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            log::error!("Failed to start transaction: {:?}", err);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to start transaction",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    match query_scalar!(
+        "SELECT id FROM garages WHERE id = ? AND deleted_at IS NULL",
+        garage_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Garage not found",
+                "details": format!("No garage found with id {}", garage_id)
+            }));
+        }
+        Err(err) => {
+            log::error!("Database error while fetching garage {}: {:?}", garage_id, err);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Database error",
+                "details": err.to_string()
+            }));
+        }
+    }
+
+    let result = scheduler::reschedule_garage_day(
+        &mut transaction,
+        &audit::actor(&req),
+        garage_id,
+        date,
+        reschedule_req.strategy,
+        max_days_ahead,
+    )
+    .await;
+
+    let (moved, unmoved) = match result {
+        Ok(result) => result,
+        Err(err) => {
+            log::error!("Failed to reschedule bookings of garage {}: {:?}", garage_id, err);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to reschedule bookings",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let finished = if reschedule_req.dry_run {
+        transaction.rollback().await
+    } else {
+        transaction.commit().await
+    };
+    if let Err(err) = finished {
+        log::error!("Failed to finish reschedule transaction: {:?}", err);
+        return HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Failed to reschedule bookings",
+            "details": err.to_string()
+        }));
+    }
+    data.events.relay(&data.pool).await;
+
+    HttpResponse::Ok().json(RescheduleReportDTO {
+        garage_id,
+        date: date.format(scheduler::DATE_FORMAT).to_string(),
+        strategy: reschedule_req.strategy,
+        dry_run: reschedule_req.dry_run,
+        moved,
+        unmoved,
+    })
+}
@@ -0,0 +1,504 @@
+use crate::app_state::AppState;
+use crate::audit::{self, AuditAction, AuditResource};
+use crate::controllers::garage_controller::{hours_error, DEFAULT_CLOSING_TIME, DEFAULT_OPENING_TIME, DEFAULT_SLOT_MINUTES};
+use crate::import::{self, row_error, CsvRow};
+use crate::notifications;
+use crate::plates::{self, PlateCountry};
+use crate::vin;
+use crate::models::import::{ImportQueryParams, ImportReportDTO, ImportRowErrorDTO};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use chrono::{Datelike, Local};
+use log::{error, info};
+use serde_json::json;
+use sqlx::{Sqlite, Transaction};
+use std::collections::{HashMap, HashSet};
+
+const CAR_COLUMNS: &[&str] = &["make", "model", "productionYear", "licensePlate"];
+const GARAGE_COLUMNS: &[&str] = &["name", "location", "city", "capacity"];
+
+/// The first production car; anything older is a typo.
+const MIN_PRODUCTION_YEAR: i64 = 1886;
+
+struct CarRow {
+    line: u64,
+    make: String,
+    model: String,
+    production_year: i64,
+    license_plate: String,
+    plate_key: String,
+    vin: Option<String>,
+    garage_ids: Vec<i64>,
+    owner_name: Option<String>,
+    owner_email: Option<String>,
+    owner_phone: Option<String>,
+}
+
+struct GarageRow {
+    line: u64,
+    name: String,
+    location: String,
+    city: String,
+    capacity: i64,
+    opening_time: String,
+    closing_time: String,
+    slot_minutes: i64,
+}
+
+/// Garages a car row can reference, by id and by lower-cased name.
+struct GarageLookup {
+    ids: HashSet<i64>,
+    names: HashMap<String, Vec<i64>>,
+}
+
+impl GarageLookup {
+    async fn load(pool: &sqlx::SqlitePool) -> Result<Self, sqlx::Error> {
+        let garages = sqlx::query!("SELECT id, name FROM garages WHERE deleted_at IS NULL")
+            .fetch_all(pool)
+            .await?;
+
+        let mut lookup = GarageLookup { ids: HashSet::new(), names: HashMap::new() };
+        for garage in garages {
+            lookup.ids.insert(garage.id);
+            lookup.names.entry(garage.name.to_lowercase()).or_default().push(garage.id);
+        }
+        Ok(lookup)
+    }
+
+    /// Resolves a `;`-separated list of garage ids or names.
+    fn resolve(&self, row: &CsvRow, column: &str, errors: &mut Vec<ImportRowErrorDTO>) -> Vec<i64> {
+        let mut ids = Vec::new();
+        for reference in row.get(column).unwrap_or_default().split(';').map(str::trim) {
+            if reference.is_empty() {
+                continue;
+            }
+            let found = match reference.parse::<i64>() {
+                Ok(id) if self.ids.contains(&id) => Ok(id),
+                Ok(id) => Err(format!("No garage with id {}", id)),
+                Err(_) => match self.names.get(&reference.to_lowercase()).map(Vec::as_slice) {
+                    Some([id]) => Ok(*id),
+                    Some(_) => Err(format!("Garage name '{}' is ambiguous; use its id", reference)),
+                    None => Err(format!("No garage named '{}'", reference)),
+                },
+            };
+            match found {
+                Ok(id) if !ids.contains(&id) => ids.push(id),
+                Ok(_) => {}
+                Err(message) => errors.push(row_error(row.line, Some(column), message)),
+            }
+        }
+        ids
+    }
+}
+
+fn bad_upload(details: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(json!({
+        "error": "Invalid CSV upload",
+        "details": details
+    }))
+}
+
+fn import_failed(what: &str, err: &sqlx::Error) -> HttpResponse {
+    error!("Failed to import {}: {:?}", what, err);
+    HttpResponse::InternalServerError().json(json!({
+        "error": format!("Failed to import {}", what),
+        "details": err.to_string()
+    }))
+}
+
+/// Rolls back unless every row went in and this is not a dry run. Invalid
+/// uploads are rejected with 422, so an import is all or nothing.
+async fn finish(
+    transaction: Transaction<'_, Sqlite>,
+    what: &str,
+    dry_run: bool,
+    total_rows: usize,
+    valid_rows: usize,
+    created_ids: Vec<i64>,
+    mut errors: Vec<ImportRowErrorDTO>,
+) -> HttpResponse {
+    errors.sort_by_key(|err| err.line);
+
+    if !errors.is_empty() || dry_run {
+        if let Err(err) = transaction.rollback().await {
+            return import_failed(what, &err);
+        }
+        let report = ImportReportDTO {
+            dry_run,
+            total_rows,
+            valid_rows,
+            created_ids: Vec::new(),
+            errors,
+        };
+        return if report.errors.is_empty() {
+            HttpResponse::Ok().json(report)
+        } else {
+            HttpResponse::UnprocessableEntity().json(report)
+        };
+    }
+
+    if let Err(err) = transaction.commit().await {
+        return import_failed(what, &err);
+    }
+
+    info!("Imported {} {}", created_ids.len(), what);
+    HttpResponse::Created().json(ImportReportDTO {
+        dry_run,
+        total_rows,
+        valid_rows,
+        created_ids,
+        errors,
+    })
+}
+
+fn validate_car(
+    row: &CsvRow,
+    garages: &GarageLookup,
+    max_year: i64,
+    default_country: Option<PlateCountry>,
+    errors: &mut Vec<ImportRowErrorDTO>,
+) -> Option<CarRow> {
+    let before = errors.len();
+
+    let make = import::required_text(row, "make", errors);
+    let model = import::required_text(row, "model", errors);
+    let production_year = import::integer(row, "productionYear", true, errors);
+    if let Some(year) = production_year {
+        if !(MIN_PRODUCTION_YEAR..=max_year).contains(&year) {
+            errors.push(row_error(
+                row.line,
+                Some("productionYear"),
+                format!("productionYear must be between {} and {}", MIN_PRODUCTION_YEAR, max_year),
+            ));
+        }
+    }
+    let country = match row.get("plateCountry").map(str::parse::<PlateCountry>) {
+        Some(Ok(country)) => Some(country),
+        Some(Err(details)) => {
+            errors.push(row_error(row.line, Some("plateCountry"), details));
+            None
+        }
+        None => None,
+    };
+    let license_plate = match import::required_text(row, "licensePlate", errors) {
+        Some(plate) => match plates::normalize(&plate, country, default_country) {
+            Ok(plate) => Some(plate),
+            Err(details) => {
+                errors.push(row_error(row.line, Some("licensePlate"), details));
+                None
+            }
+        },
+        None => None,
+    };
+    let vin = match row.get("vin").map(vin::normalize) {
+        Some(Ok(vin)) => Some(vin),
+        Some(Err(details)) => {
+            errors.push(row_error(row.line, Some("vin"), details));
+            None
+        }
+        None => None,
+    };
+    if let (Some(vin), Some(make), Some(year)) = (&vin, &make, production_year) {
+        if let Some(details) = vin::mismatch(&vin::decode(vin), make, year) {
+            errors.push(row_error(row.line, Some("vin"), details));
+        }
+    }
+
+    let column = if row.get("garageIds").is_some() { "garageIds" } else { "garages" };
+    let garage_ids = garages.resolve(row, column, errors);
+
+    let owner_email = row.get("ownerEmail");
+    let owner_phone = row.get("ownerPhone");
+    if let Some(details) = notifications::contact_error(owner_email, owner_phone) {
+        errors.push(row_error(row.line, None, details));
+    }
+
+    if errors.len() > before {
+        return None;
+    }
+
+    let license_plate = license_plate?;
+    Some(CarRow {
+        line: row.line,
+        make: make?,
+        model: model?,
+        production_year: production_year?,
+        plate_key: plates::plate_key(&license_plate),
+        license_plate,
+        vin,
+        garage_ids,
+        owner_name: row.get("ownerName").map(str::to_string),
+        owner_email: owner_email.map(str::to_string),
+        owner_phone: owner_phone.map(str::to_string),
+    })
+}
+
+async fn insert_car(
+    transaction: &mut Transaction<'_, Sqlite>,
+    actor: &str,
+    car: &CarRow,
+) -> Result<i64, sqlx::Error> {
+    let car_id = sqlx::query!(
+        r#"
+        INSERT INTO cars (make, model, production_year, license_plate, plate_key, vin, owner_name, owner_email, owner_phone)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        car.make,
+        car.model,
+        car.production_year,
+        car.license_plate,
+        car.plate_key,
+        car.vin,
+        car.owner_name,
+        car.owner_email,
+        car.owner_phone
+    )
+    .execute(&mut **transaction)
+    .await?
+    .last_insert_rowid();
+
+    for garage_id in &car.garage_ids {
+        sqlx::query!(
+            "INSERT INTO car_garages (car_id, garage_id) VALUES (?, ?)",
+            car_id,
+            garage_id
+        )
+        .execute(&mut **transaction)
+        .await?;
+    }
+
+    audit::record_change(transaction, actor, AuditResource::Car, &car_id.to_string(), AuditAction::Create, None)
+        .await?;
+
+    Ok(car_id)
+}
+
+pub async fn import_cars(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    query: web::Query<ImportQueryParams>,
+    body: web::Bytes,
+) -> impl Responder {
+    let (rows, mut errors) = match import::read_rows(&body, CAR_COLUMNS) {
+        Ok(parsed) => parsed,
+        Err(details) => return bad_upload(details),
+    };
+    info!("Importing {} car rows (dry run: {})", rows.len(), query.dry_run);
+
+    let garages = match GarageLookup::load(&data.pool).await {
+        Ok(garages) => garages,
+        Err(err) => return import_failed("cars", &err),
+    };
+
+    let max_year = i64::from(Local::now().year()) + 1;
+    let mut seen_plates: HashMap<String, u64> = HashMap::new();
+    let mut seen_vins: HashMap<String, u64> = HashMap::new();
+    let mut cars = Vec::new();
+    for row in &rows {
+        let Some(car) = validate_car(row, &garages, max_year, data.plate_country, &mut errors) else {
+            continue;
+        };
+
+        if let Some(first_line) = seen_plates.get(&car.plate_key) {
+            errors.push(row_error(
+                car.line,
+                Some("licensePlate"),
+                format!("licensePlate {} already appears on line {}", car.license_plate, first_line),
+            ));
+            continue;
+        }
+        seen_plates.insert(car.plate_key.clone(), car.line);
+
+        if let Some(vin) = &car.vin {
+            if let Some(first_line) = seen_vins.get(vin) {
+                errors.push(row_error(
+                    car.line,
+                    Some("vin"),
+                    format!("vin {} already appears on line {}", vin, first_line),
+                ));
+                continue;
+            }
+            seen_vins.insert(vin.clone(), car.line);
+        }
+
+        // Soft-deleted cars still hold their plate and VIN.
+        match sqlx::query!(
+            r#"SELECT id AS "id!", plate_key = ?1 AS "same_plate!: bool" FROM cars WHERE plate_key = ?1 OR vin = ?2"#,
+            car.plate_key,
+            car.vin
+        )
+        .fetch_optional(&data.pool)
+        .await
+        {
+            Ok(Some(existing)) if existing.same_plate => errors.push(row_error(
+                car.line,
+                Some("licensePlate"),
+                format!("licensePlate {} is already used by car {}", car.license_plate, existing.id),
+            )),
+            Ok(Some(existing)) => errors.push(row_error(
+                car.line,
+                Some("vin"),
+                format!("vin {} is already used by car {}", car.vin.as_deref().unwrap_or_default(), existing.id),
+            )),
+            Ok(None) => cars.push(car),
+            Err(err) => return import_failed("cars", &err),
+        }
+    }
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return import_failed("cars", &err),
+    };
+
+    let actor = audit::actor(&req);
+    let mut created_ids = Vec::new();
+    if errors.is_empty() {
+        for car in &cars {
+            match insert_car(&mut transaction, &actor, car).await {
+                Ok(car_id) => created_ids.push(car_id),
+                // A plate or VIN taken since validation still fails just this row.
+                Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+                    errors.push(row_error(
+                        car.line,
+                        Some("licensePlate"),
+                        format!("licensePlate {} or its VIN already exists", car.license_plate),
+                    ));
+                    break;
+                }
+                Err(err) => return import_failed("cars", &err),
+            }
+        }
+    }
+
+    finish(transaction, "cars", query.dry_run, rows.len(), cars.len(), created_ids, errors).await
+}
+
+fn validate_garage(row: &CsvRow, errors: &mut Vec<ImportRowErrorDTO>) -> Option<GarageRow> {
+    let before = errors.len();
+
+    let name = import::required_text(row, "name", errors);
+    let location = import::required_text(row, "location", errors);
+    let city = import::required_text(row, "city", errors);
+    let capacity = import::integer(row, "capacity", true, errors);
+    if capacity.is_some_and(|capacity| capacity <= 0) {
+        errors.push(row_error(row.line, Some("capacity"), "capacity must be positive"));
+    }
+
+    let opening_time = row.get("openingTime").unwrap_or(DEFAULT_OPENING_TIME).to_string();
+    let closing_time = row.get("closingTime").unwrap_or(DEFAULT_CLOSING_TIME).to_string();
+    let slot_minutes = import::integer(row, "slotMinutes", false, errors);
+    if let Some(details) = hours_error(&opening_time, &closing_time, slot_minutes.unwrap_or(DEFAULT_SLOT_MINUTES)) {
+        errors.push(row_error(row.line, None, details));
+    }
+
+    if errors.len() > before {
+        return None;
+    }
+
+    Some(GarageRow {
+        line: row.line,
+        name: name?,
+        location: location?,
+        city: city?,
+        capacity: capacity?,
+        opening_time,
+        closing_time,
+        slot_minutes: slot_minutes.unwrap_or(DEFAULT_SLOT_MINUTES),
+    })
+}
+
+async fn insert_garage(
+    transaction: &mut Transaction<'_, Sqlite>,
+    actor: &str,
+    garage: &GarageRow,
+) -> Result<i64, sqlx::Error> {
+    let garage_id = sqlx::query!(
+        "INSERT INTO garages (name, location, city, capacity, opening_time, closing_time, slot_minutes) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        garage.name,
+        garage.location,
+        garage.city,
+        garage.capacity,
+        garage.opening_time,
+        garage.closing_time,
+        garage.slot_minutes
+    )
+    .execute(&mut **transaction)
+    .await?
+    .last_insert_rowid();
+
+    audit::record_change(transaction, actor, AuditResource::Garage, &garage_id.to_string(), AuditAction::Create, None)
+        .await?;
+
+    Ok(garage_id)
+}
+
+pub async fn import_garages(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    query: web::Query<ImportQueryParams>,
+    body: web::Bytes,
+) -> impl Responder {
+    let (rows, mut errors) = match import::read_rows(&body, GARAGE_COLUMNS) {
+        Ok(parsed) => parsed,
+        Err(details) => return bad_upload(details),
+    };
+    info!("Importing {} garage rows (dry run: {})", rows.len(), query.dry_run);
+
+    // Car imports reference garages by name, so a name may only be used
+    // once per city.
+    let existing = match sqlx::query!(
+        "SELECT id, lower(name) AS \"name!: String\", lower(city) AS \"city!: String\" FROM garages WHERE deleted_at IS NULL"
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(existing) => existing
+            .into_iter()
+            .map(|garage| ((garage.name, garage.city), garage.id))
+            .collect::<HashMap<_, _>>(),
+        Err(err) => return import_failed("garages", &err),
+    };
+
+    let mut seen: HashMap<(String, String), u64> = HashMap::new();
+    let mut garages = Vec::new();
+    for row in &rows {
+        let Some(garage) = validate_garage(row, &mut errors) else {
+            continue;
+        };
+
+        let key = (garage.name.to_lowercase(), garage.city.to_lowercase());
+        if let Some(existing_id) = existing.get(&key) {
+            errors.push(row_error(
+                garage.line,
+                Some("name"),
+                format!("Garage {} already exists in {} with id {}", garage.name, garage.city, existing_id),
+            ));
+        } else if let Some(first_line) = seen.get(&key) {
+            errors.push(row_error(
+                garage.line,
+                Some("name"),
+                format!("Garage {} in {} already appears on line {}", garage.name, garage.city, first_line),
+            ));
+        } else {
+            seen.insert(key, garage.line);
+            garages.push(garage);
+        }
+    }
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return import_failed("garages", &err),
+    };
+
+    let actor = audit::actor(&req);
+    let mut created_ids = Vec::new();
+    if errors.is_empty() {
+        for garage in &garages {
+            match insert_garage(&mut transaction, &actor, garage).await {
+                Ok(garage_id) => created_ids.push(garage_id),
+                Err(err) => return import_failed("garages", &err),
+            }
+        }
+    }
+
+    finish(transaction, "garages", query.dry_run, rows.len(), garages.len(), created_ids, errors).await
+}
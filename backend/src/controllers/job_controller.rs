@@ -0,0 +1,336 @@
+use crate::app_state::AppState;
+use crate::cron::Cron;
+use crate::jobs::{self, JobKind};
+use crate::models::job::{JobDTO, JobQueryParams, JobScheduleDTO, UpdateJobScheduleRequest};
+use actix_web::{web, HttpResponse, Responder};
+use log::{error, info};
+use serde_json::json;
+use sqlx::SqlitePool;
+
+const DEFAULT_JOB_LIMIT: i64 = 100;
+const MAX_JOB_LIMIT: i64 = 1000;
+const JOB_STATUSES: &[&str] = &["PENDING", "RUNNING", "SUCCEEDED", "FAILED"];
+
+struct JobRow {
+    id: i64,
+    kind: String,
+    payload: String,
+    status: String,
+    attempts: i64,
+    max_attempts: i64,
+    run_at: String,
+    schedule: Option<String>,
+    result: Option<String>,
+    last_error: Option<String>,
+    created_at: String,
+    started_at: Option<String>,
+    finished_at: Option<String>,
+}
+
+impl From<JobRow> for JobDTO {
+    fn from(row: JobRow) -> Self {
+        JobDTO {
+            id: row.id,
+            kind: row.kind,
+            payload: serde_json::from_str(&row.payload).unwrap_or_default(),
+            status: row.status,
+            attempts: row.attempts,
+            max_attempts: row.max_attempts,
+            run_at: row.run_at,
+            schedule: row.schedule,
+            result: row.result.and_then(|result| serde_json::from_str(&result).ok()),
+            last_error: row.last_error,
+            created_at: row.created_at,
+            started_at: row.started_at,
+            finished_at: row.finished_at,
+        }
+    }
+}
+
+fn job_failed(message: &str, err: sqlx::Error) -> HttpResponse {
+    error!("{}: {:?}", message, err);
+    HttpResponse::InternalServerError().json(json!({
+        "error": message,
+        "details": err.to_string()
+    }))
+}
+
+fn job_not_found(id: i64) -> HttpResponse {
+    HttpResponse::NotFound().json(json!({
+        "error": "Job not found",
+        "details": format!("No job found with id {}", id)
+    }))
+}
+
+fn schedule_not_found(name: &str) -> HttpResponse {
+    HttpResponse::NotFound().json(json!({
+        "error": "Job schedule not found",
+        "details": format!("No job schedule named '{}'", name)
+    }))
+}
+
+async fn fetch_job(pool: &SqlitePool, id: i64) -> Result<Option<JobDTO>, sqlx::Error> {
+    let row = sqlx::query_as!(
+        JobRow,
+        r#"
+        SELECT id, kind, payload, status, attempts, max_attempts, run_at, schedule, result, last_error,
+               created_at, started_at, finished_at
+        FROM jobs
+        WHERE id = ?
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(JobDTO::from))
+}
+
+async fn fetch_schedule(pool: &SqlitePool, name: &str) -> Result<Option<JobScheduleDTO>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT name AS "name!", kind, payload, cron, active AS "active: bool", next_run_at, last_run_at
+        FROM job_schedules
+        WHERE name = ?
+        "#,
+        name
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|row| JobScheduleDTO {
+        name: row.name,
+        kind: row.kind,
+        payload: serde_json::from_str(&row.payload).unwrap_or_default(),
+        cron: row.cron,
+        active: row.active,
+        next_run_at: row.next_run_at,
+        last_run_at: row.last_run_at,
+    }))
+}
+
+/// Newest jobs first, optionally narrowed to one status or kind.
+pub async fn get_all_jobs(
+    query: web::Query<JobQueryParams>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let status = query.status.as_deref().map(str::to_ascii_uppercase);
+    if let Some(status) = status.as_deref() {
+        if !JOB_STATUSES.contains(&status) {
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Invalid status parameter",
+                "details": format!("status must be one of {}", JOB_STATUSES.join(", "))
+            }));
+        }
+    }
+    let kind = match query.kind.as_deref().map(str::parse::<JobKind>) {
+        Some(Ok(kind)) => Some(kind.as_str()),
+        Some(Err(details)) => {
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Invalid kind parameter",
+                "details": details
+            }));
+        }
+        None => None,
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_JOB_LIMIT).clamp(1, MAX_JOB_LIMIT);
+
+    match sqlx::query_as!(
+        JobRow,
+        r#"
+        SELECT id, kind, payload, status, attempts, max_attempts, run_at, schedule, result, last_error,
+               created_at, started_at, finished_at
+        FROM jobs
+        WHERE (?1 IS NULL OR status = ?1)
+          AND (?2 IS NULL OR kind = ?2)
+        ORDER BY id DESC
+        LIMIT ?3
+        "#,
+        status,
+        kind,
+        limit
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(rows) => HttpResponse::Ok().json(rows.into_iter().map(JobDTO::from).collect::<Vec<_>>()),
+        Err(err) => job_failed("Failed to fetch jobs", err),
+    }
+}
+
+pub async fn get_job(
+    id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let id = id.into_inner();
+    match fetch_job(&data.pool, id).await {
+        Ok(Some(job)) => HttpResponse::Ok().json(job),
+        Ok(None) => job_not_found(id),
+        Err(err) => job_failed("Failed to fetch job", err),
+    }
+}
+
+/// Gives a failed job a fresh set of attempts, starting now.
+pub async fn retry_job(
+    id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let id = id.into_inner();
+
+    let job = match fetch_job(&data.pool, id).await {
+        Ok(Some(job)) => job,
+        Ok(None) => return job_not_found(id),
+        Err(err) => return job_failed("Failed to retry job", err),
+    };
+    if job.status != "FAILED" {
+        return HttpResponse::Conflict().json(json!({
+            "error": "Job cannot be retried",
+            "details": format!("Job {} is {}; only failed jobs can be retried", id, job.status)
+        }));
+    }
+
+    match sqlx::query!(
+        r#"
+        UPDATE jobs
+        SET status = 'PENDING', attempts = 0, run_at = datetime('now'), started_at = NULL, finished_at = NULL
+        WHERE id = ? AND status = 'FAILED'
+        "#,
+        id
+    )
+    .execute(&data.pool)
+    .await
+    {
+        Ok(_) => {}
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+            return HttpResponse::Conflict().json(json!({
+                "error": "Job cannot be retried",
+                "details": format!("An equivalent {} job is already pending", job.kind)
+            }));
+        }
+        Err(err) => return job_failed("Failed to retry job", err),
+    }
+    info!("Job {} ({}) queued for retry", id, job.kind);
+
+    match fetch_job(&data.pool, id).await {
+        Ok(Some(job)) => HttpResponse::Ok().json(job),
+        Ok(None) => job_not_found(id),
+        Err(err) => job_failed("Failed to retry job", err),
+    }
+}
+
+pub async fn get_job_schedules(data: web::Data<AppState>) -> impl Responder {
+    match sqlx::query!(
+        r#"
+        SELECT name AS "name!", kind, payload, cron, active AS "active: bool", next_run_at, last_run_at
+        FROM job_schedules
+        ORDER BY name
+        "#
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(rows) => HttpResponse::Ok().json(
+            rows.into_iter()
+                .map(|row| JobScheduleDTO {
+                    name: row.name,
+                    kind: row.kind,
+                    payload: serde_json::from_str(&row.payload).unwrap_or_default(),
+                    cron: row.cron,
+                    active: row.active,
+                    next_run_at: row.next_run_at,
+                    last_run_at: row.last_run_at,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Err(err) => job_failed("Failed to fetch job schedules", err),
+    }
+}
+
+/// Changes when a schedule fires or pauses it. The next run is worked out
+/// again from now.
+pub async fn update_job_schedule(
+    name: web::Path<String>,
+    schedule_req: web::Json<UpdateJobScheduleRequest>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let name = name.into_inner();
+
+    let schedule = match fetch_schedule(&data.pool, &name).await {
+        Ok(Some(schedule)) => schedule,
+        Ok(None) => return schedule_not_found(&name),
+        Err(err) => return job_failed("Failed to update job schedule", err),
+    };
+
+    let expression = schedule_req.cron.as_deref().map_or(schedule.cron.as_str(), str::trim);
+    let next_run_at = match expression.parse::<Cron>() {
+        Ok(cron) => jobs::next_run_at(&cron),
+        Err(details) => {
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Invalid cron expression",
+                "details": details
+            }));
+        }
+    };
+    let Some(next_run_at) = next_run_at else {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid cron expression",
+            "details": format!("'{}' never fires", expression)
+        }));
+    };
+    let active = schedule_req.active.unwrap_or(schedule.active);
+
+    if let Err(err) = sqlx::query!(
+        "UPDATE job_schedules SET cron = ?, active = ?, next_run_at = ? WHERE name = ?",
+        expression,
+        active,
+        next_run_at,
+        name
+    )
+    .execute(&data.pool)
+    .await
+    {
+        return job_failed("Failed to update job schedule", err);
+    }
+    info!("Job schedule '{}' set to '{}' (active: {})", name, expression, active);
+
+    match fetch_schedule(&data.pool, &name).await {
+        Ok(Some(schedule)) => HttpResponse::Ok().json(schedule),
+        Ok(None) => schedule_not_found(&name),
+        Err(err) => job_failed("Failed to update job schedule", err),
+    }
+}
+
+/// Queues a run of the schedule's job now, without moving its next run.
+pub async fn run_job_schedule(
+    name: web::Path<String>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let name = name.into_inner();
+
+    match fetch_schedule(&data.pool, &name).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return schedule_not_found(&name),
+        Err(err) => return job_failed("Failed to run job schedule", err),
+    }
+
+    let mut conn = match data.pool.acquire().await {
+        Ok(conn) => conn,
+        Err(err) => return job_failed("Failed to run job schedule", err),
+    };
+    let id = match jobs::fire_schedule(&mut conn, &name).await {
+        Ok(Some(id)) => id,
+        Ok(None) => {
+            return HttpResponse::Conflict().json(json!({
+                "error": "Job already queued",
+                "details": format!("A run of '{}' is already pending", name)
+            }));
+        }
+        Err(err) => return job_failed("Failed to run job schedule", err),
+    };
+
+    match fetch_job(&data.pool, id).await {
+        Ok(Some(job)) => HttpResponse::Accepted().json(job),
+        Ok(None) => job_not_found(id),
+        Err(err) => job_failed("Failed to run job schedule", err),
+    }
+}
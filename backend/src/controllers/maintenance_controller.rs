@@ -1,47 +1,90 @@
 use crate::app_state::AppState; 
+use crate::audit::{self, AuditAction, AuditResource};
+use crate::models::common::IncludeDeletedQuery;
 use crate::models::maintenance::{CreateMaintenanceDTO, ResponseMaintenanceDTO};
-use actix_web::{web, HttpResponse, Responder}; 
-use crate::models::maintenance::{UpdateMaintenanceDTO};
+use actix_web::{web, HttpRequest, HttpResponse, Responder}; 
+use crate::models::maintenance::{
+    CarMaintenanceEntryDTO, CarMaintenanceHistoryDTO, CarMaintenanceSummaryDTO, LastServiceDTO, Maintenance, MaintenanceStatus, MaintenanceStatusHistoryDTO, TransitionMaintenanceDTO,
+    UpdateMaintenanceDTO,
+};
+use crate::{billing, export, inventory, mechanics, odometer, scheduler, slots};
+use chrono::Local;
+use futures_util::TryStreamExt;
 use serde_json::json;
-use std::collections::HashMap;
+use sqlx::SqliteConnection;
 use log::{error, info, warn};
 
 pub async fn get_all_maintenances(
+    req: HttpRequest,
     data: web::Data<AppState>,
+    query: web::Query<IncludeDeletedQuery>,
 ) -> impl Responder {
-    match sqlx::query_as!(
-        ResponseMaintenanceDTO,
-        r#"
-        SELECT
-            maintenance.id,
-            maintenance.car_id AS "car_id!",
-            maintenance.garage_id AS "garage_id!",
-            cars.make || ' ' || cars.model AS car_name,
-            garages.name AS garage_name,
-            maintenance.service_type,
-            maintenance.scheduled_date
-        FROM maintenance
-        JOIN cars ON maintenance.car_id = cars.id
-        JOIN garages ON maintenance.garage_id = garages.id
-        "#
-    )
-    .fetch_all(&data.pool)
-    .await
-    {
-        Ok(records) => HttpResponse::Ok().json(records),
-        Err(err) => {
-            error!("Failed to fetch maintenances: {:?}", err);
-            HttpResponse::InternalServerError().json(json!({
-                "error": "Failed to fetch maintenances",
-                "details": err.to_string()
-            }))
+    let format = match export::negotiate(&req) {
+        Ok(format) => format,
+        Err(response) => return response,
+    };
+
+    let pool = data.pool.clone();
+    let tax_rate_bps = data.tax_rate_bps;
+    let include_deleted = query.include_deleted;
+    let rows = async_stream::try_stream! {
+        let mut records = sqlx::query_as!(
+            ResponseMaintenanceDTO,
+            r#"
+            SELECT
+                maintenance.id,
+                maintenance.car_id AS "car_id!",
+                maintenance.garage_id AS "garage_id!",
+                cars.make || ' ' || cars.model AS car_name,
+                garages.name AS garage_name,
+                maintenance.service_type,
+                maintenance.scheduled_date,
+                maintenance.status AS "status: MaintenanceStatus",
+                maintenance.bay_id,
+                maintenance.start_time,
+                maintenance.duration_minutes,
+                maintenance.mechanic_id,
+                maintenance.notes,
+                maintenance.deleted_at,
+                maintenance.quoted_total_cents,
+                COALESCE(invoices.subtotal_cents, line_totals.subtotal_cents, 0) AS "subtotal_cents!: i64",
+                COALESCE(
+                    invoices.tax_cents,
+                    (COALESCE(line_totals.subtotal_cents, 0) * ?1 + 5000) / 10000
+                ) AS "tax_cents!: i64",
+                COALESCE(
+                    invoices.total_cents,
+                    COALESCE(line_totals.subtotal_cents, 0)
+                        + (COALESCE(line_totals.subtotal_cents, 0) * ?1 + 5000) / 10000
+                ) AS "total_cents!: i64"
+            FROM maintenance
+            JOIN cars ON maintenance.car_id = cars.id
+            JOIN garages ON maintenance.garage_id = garages.id
+            LEFT JOIN invoices ON invoices.maintenance_id = maintenance.id
+            LEFT JOIN (
+                SELECT maintenance_id, SUM(total_cents) AS subtotal_cents
+                FROM maintenance_line_items
+                GROUP BY maintenance_id
+            ) AS line_totals ON line_totals.maintenance_id = maintenance.id
+            WHERE ?2 OR (maintenance.deleted_at IS NULL AND cars.deleted_at IS NULL AND garages.deleted_at IS NULL)
+            ORDER BY maintenance.id
+            "#,
+            tax_rate_bps,
+            include_deleted
+        )
+        .fetch(&pool);
+        while let Some(record) = records.try_next().await? {
+            yield record;
         }
-    }
+    };
+
+    export::respond(format, "maintenance", rows).await
 }
 
 pub async fn get_maintenance_by_id(
     id: web::Path<i64>,
     data: web::Data<AppState>,
+    query: web::Query<IncludeDeletedQuery>,
 ) -> impl Responder {
     let maintenance_id = id.into_inner();
     info!("Fetching maintenance with ID: {}", maintenance_id);
@@ -56,13 +99,40 @@ pub async fn get_maintenance_by_id(
             cars.make || ' ' || cars.model AS car_name,
             garages.name AS garage_name,
             maintenance.service_type,
-            maintenance.scheduled_date
+            maintenance.scheduled_date,
+            maintenance.status AS "status: MaintenanceStatus",
+            maintenance.bay_id,
+            maintenance.start_time,
+            maintenance.duration_minutes,
+            maintenance.mechanic_id,
+            maintenance.notes,
+            maintenance.deleted_at,
+            maintenance.quoted_total_cents,
+            COALESCE(invoices.subtotal_cents, line_totals.subtotal_cents, 0) AS "subtotal_cents!: i64",
+            COALESCE(
+                invoices.tax_cents,
+                (COALESCE(line_totals.subtotal_cents, 0) * ?1 + 5000) / 10000
+            ) AS "tax_cents!: i64",
+            COALESCE(
+                invoices.total_cents,
+                COALESCE(line_totals.subtotal_cents, 0)
+                    + (COALESCE(line_totals.subtotal_cents, 0) * ?1 + 5000) / 10000
+            ) AS "total_cents!: i64"
         FROM maintenance
         JOIN cars ON maintenance.car_id = cars.id
         JOIN garages ON maintenance.garage_id = garages.id
-        WHERE maintenance.id = ?
+        LEFT JOIN invoices ON invoices.maintenance_id = maintenance.id
+        LEFT JOIN (
+            SELECT maintenance_id, SUM(total_cents) AS subtotal_cents
+            FROM maintenance_line_items
+            GROUP BY maintenance_id
+        ) AS line_totals ON line_totals.maintenance_id = maintenance.id
+        WHERE maintenance.id = ?2
+          AND (?3 OR maintenance.deleted_at IS NULL)
         "#,
-        maintenance_id
+        data.tax_rate_bps,
+        maintenance_id,
+        query.include_deleted
     )
     .fetch_optional(&data.pool)
     .await
@@ -85,46 +155,157 @@ pub async fn get_maintenance_by_id(
 }
 
 pub async fn create_maintenance(
+    req: HttpRequest,
     data: web::Data<AppState>,
     maintenance_req: web::Json<CreateMaintenanceDTO>,
 ) -> impl Responder {
-    match sqlx::query!(
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("Failed to start transaction: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to start transaction",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let status = MaintenanceStatus::Scheduled;
+
+    if let Err(response) = check_car(&mut transaction, &maintenance_req.car_id).await {
+        let _ = transaction.rollback().await;
+        return response;
+    }
+
+    let placement = match resolve_placement(
+        &mut transaction,
+        &maintenance_req.garage_id,
+        &maintenance_req.scheduled_date,
+        &maintenance_req.service_type,
+        maintenance_req.start_time.as_deref(),
+        maintenance_req.bay_id,
+        None,
+    )
+    .await
+    {
+        Ok(placement) => placement,
+        Err(response) => {
+            let _ = transaction.rollback().await;
+            return response;
+        }
+    };
+
+    let id = match sqlx::query!(
         r#"
-        INSERT INTO maintenance (car_id, garage_id, service_type, scheduled_date)
-        VALUES (?, ?, ?, ?)
+        INSERT INTO maintenance
+            (car_id, garage_id, service_type, scheduled_date, status, bay_id, start_time, duration_minutes, notes)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
         maintenance_req.car_id,
         maintenance_req.garage_id,
         maintenance_req.service_type,
         maintenance_req.scheduled_date,
+        status,
+        placement.bay_id,
+        placement.start_time,
+        placement.duration_minutes,
+        maintenance_req.notes,
     )
-    .execute(&data.pool)
+    .execute(&mut *transaction)
     .await
     {
-        Ok(result) => {
-            let id = result.last_insert_rowid();
-            HttpResponse::Created().json(ResponseMaintenanceDTO {
-                id,
-                car_id: maintenance_req.car_id.clone(),
-                garage_id: maintenance_req.garage_id.clone(),
-                car_name: "Car Name Placeholder".to_string(),
-                garage_name: "Garage Name Placeholder".to_string(),
-                service_type: maintenance_req.service_type.clone(),
-                scheduled_date: maintenance_req.scheduled_date.clone(),
-            })
-        }
+        Ok(result) => result.last_insert_rowid(),
         Err(err) => {
             error!("Failed to create maintenance: {:?}", err);
-            HttpResponse::InternalServerError().json(json!({
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json(json!({
                 "error": "Failed to create maintenance",
                 "details": err.to_string()
-            }))
+            }));
         }
+    };
+
+    if let Err(err) = sqlx::query!(
+        r#"
+        INSERT INTO maintenance_status_history (maintenance_id, from_status, to_status)
+        VALUES (?, NULL, ?)
+        "#,
+        id,
+        status,
+    )
+    .execute(&mut *transaction)
+    .await
+    {
+        error!("Failed to record maintenance status history: {:?}", err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to create maintenance",
+            "details": err.to_string()
+        }));
+    }
+
+    if let Err(err) = inventory::sync_reservations(&mut transaction, id).await {
+        error!("Failed to reserve parts for maintenance {}: {:?}", id, err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to create maintenance",
+            "details": err.to_string()
+        }));
+    }
+
+    if let Err(err) = audit::record_change(
+        &mut transaction,
+        &audit::actor(&req),
+        AuditResource::Maintenance,
+        &id.to_string(),
+        AuditAction::Create,
+        None,
+    )
+    .await
+    {
+        error!("Failed to write audit log: {:?}", err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to create maintenance",
+            "details": err.to_string()
+        }));
+    }
+
+    if let Err(err) = transaction.commit().await {
+        error!("Failed to commit transaction: {:?}", err);
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to create maintenance",
+            "details": err.to_string()
+        }));
     }
+
+    data.events.relay(&data.pool).await;
+
+    HttpResponse::Created().json(ResponseMaintenanceDTO {
+        id,
+        car_id: maintenance_req.car_id.clone(),
+        garage_id: maintenance_req.garage_id.clone(),
+        car_name: "Car Name Placeholder".to_string(),
+        garage_name: "Garage Name Placeholder".to_string(),
+        service_type: maintenance_req.service_type.clone(),
+        scheduled_date: maintenance_req.scheduled_date.clone(),
+        status,
+        bay_id: placement.bay_id,
+        start_time: placement.start_time,
+        duration_minutes: Some(placement.duration_minutes),
+        mechanic_id: None,
+        notes: maintenance_req.notes.clone(),
+        deleted_at: None,
+        quoted_total_cents: None,
+        subtotal_cents: 0,
+        tax_cents: 0,
+        total_cents: 0,
+    })
 }
 
 
 pub async fn edit_maintenance(
+    req: HttpRequest,
     id: web::Path<String>,
     maintenance_req: web::Json<UpdateMaintenanceDTO>, 
     data: web::Data<AppState>,
@@ -151,27 +332,202 @@ pub async fn edit_maintenance(
     let garage_id = maintenance_req.garage_id.as_str();
     let service_type = maintenance_req.service_type.as_deref();
     let scheduled_date = maintenance_req.scheduled_date.as_deref();
+    let notes = maintenance_req.notes.as_deref();
 
-    if let Err(err) = sqlx::query!(
+    let before = match audit::snapshot(&mut transaction, AuditResource::Maintenance, maintenance_id).await {
+        Ok(before) => before,
+        Err(err) => {
+            error!("Failed to read maintenance before update: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update maintenance",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let current = match sqlx::query!(
+        r#"
+        SELECT
+            id, garage_id AS "garage_id!", service_type, scheduled_date, bay_id, start_time, duration_minutes, mechanic_id,
+            status AS "status: MaintenanceStatus"
+        FROM maintenance
+        WHERE id = ? AND deleted_at IS NULL
+        "#,
+        maintenance_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    {
+        Ok(Some(current)) => current,
+        Ok(None) => {
+            warn!("Maintenance with ID {} not found", maintenance_id);
+            let _ = transaction.rollback().await;
+            return HttpResponse::NotFound().json(json!({
+                "error": "Maintenance not found"
+            }));
+        }
+        Err(err) => {
+            error!("Failed to fetch maintenance {}: {:?}", maintenance_id, err);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update maintenance",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    // Completed, cancelled and no-show bookings may already be invoiced or
+    // have used their parts, so they stay as they were.
+    if current.status.is_final() {
+        let _ = transaction.rollback().await;
+        return HttpResponse::Conflict().json(json!({
+            "error": "Maintenance is locked",
+            "details": format!("Cannot edit a {} maintenance", current.status)
+        }));
+    }
+
+    if let Some(car_id) = car_id {
+        if let Err(response) = check_car(&mut transaction, car_id).await {
+            let _ = transaction.rollback().await;
+            return response;
+        }
+    }
+
+    // Only changes to when, where or what is done move the booking; other
+    // edits keep its bay and slot untouched.
+    let moves = garage_id != current.garage_id
+        || scheduled_date.is_some()
+        || service_type.is_some()
+        || maintenance_req.start_time.is_some()
+        || maintenance_req.bay_id.is_some();
+
+    let (bay_id, start_time, duration_minutes) = if moves {
+        let start_time = maintenance_req.start_time.as_deref().or(
+            (garage_id == current.garage_id && maintenance_req.bay_id.is_none())
+                .then_some(current.start_time.as_deref())
+                .flatten(),
+        );
+        match resolve_placement(
+            &mut transaction,
+            garage_id,
+            scheduled_date.unwrap_or(&current.scheduled_date),
+            service_type.unwrap_or(&current.service_type),
+            start_time,
+            maintenance_req.bay_id,
+            Some(current.id),
+        )
+        .await
+        {
+            Ok(placement) => (placement.bay_id, placement.start_time, Some(placement.duration_minutes)),
+            Err(response) => {
+                let _ = transaction.rollback().await;
+                return response;
+            }
+        }
+    } else {
+        (current.bay_id, current.start_time, current.duration_minutes)
+    };
+
+    // Mechanics work for one garage, so moving the booking elsewhere drops
+    // the assignment; a move within the garage must still suit the mechanic.
+    let mechanic_id = current.mechanic_id.filter(|_| garage_id == current.garage_id);
+    if let Some(mechanic_id) = mechanic_id.filter(|_| moves) {
+        match mechanics::find_conflict(
+            &mut transaction,
+            mechanic_id,
+            scheduled_date.unwrap_or(&current.scheduled_date),
+            start_time.as_deref(),
+            duration_minutes,
+            Some(current.id),
+        )
+        .await
+        {
+            Ok(None) => {}
+            Ok(Some(details)) => {
+                let _ = transaction.rollback().await;
+                return HttpResponse::Conflict().json(json!({
+                    "error": "Mechanic is already booked",
+                    "details": details
+                }));
+            }
+            Err(err) => {
+                error!("Failed to check mechanic {} availability: {:?}", mechanic_id, err);
+                let _ = transaction.rollback().await;
+                return HttpResponse::InternalServerError().json(json!({
+                    "error": "Failed to update maintenance",
+                    "details": err.to_string()
+                }));
+            }
+        }
+    }
+
+    match sqlx::query!(
         r#"
         UPDATE maintenance
         SET 
             car_id = COALESCE(?, car_id), 
             garage_id = COALESCE(?, garage_id), 
             service_type = COALESCE(?, service_type), 
-            scheduled_date = COALESCE(?, scheduled_date)
-        WHERE id = ?
+            scheduled_date = COALESCE(?, scheduled_date),
+            bay_id = ?,
+            start_time = ?,
+            duration_minutes = ?,
+            mechanic_id = ?,
+            notes = COALESCE(?, notes)
+        WHERE id = ? AND deleted_at IS NULL
         "#,
         car_id,
         garage_id,
         service_type,
         scheduled_date,
+        bay_id,
+        start_time,
+        duration_minutes,
+        mechanic_id,
+        notes,
         maintenance_id
     )
     .execute(&mut *transaction)
     .await
     {
-        error!("Failed to update maintenance: {:?}", err);
+        Ok(result) if result.rows_affected() == 0 => {
+            warn!("Maintenance with ID {} not found", maintenance_id);
+            let _ = transaction.rollback().await;
+            return HttpResponse::NotFound().json(json!({
+                "error": "Maintenance not found"
+            }));
+        }
+        Ok(_) => {}
+        Err(err) => {
+            error!("Failed to update maintenance: {:?}", err);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update maintenance",
+                "details": err.to_string()
+            }));
+        }
+    }
+
+    if let Err(err) = inventory::sync_reservations(&mut transaction, current.id).await {
+        error!("Failed to reserve parts for maintenance {}: {:?}", current.id, err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to update maintenance",
+            "details": err.to_string()
+        }));
+    }
+
+    if let Err(err) = audit::record_change(
+        &mut transaction,
+        &audit::actor(&req),
+        AuditResource::Maintenance,
+        maintenance_id,
+        AuditAction::Update,
+        before,
+    )
+    .await
+    {
+        error!("Failed to write audit log: {:?}", err);
         let _ = transaction.rollback().await;
         return HttpResponse::InternalServerError().json(json!({
             "error": "Failed to update maintenance",
@@ -187,126 +543,865 @@ pub async fn edit_maintenance(
         }));
     }
 
+    data.events.relay(&data.pool).await;
+
     HttpResponse::Ok().json(json!({
         "id": maintenance_id,
         "updated": true,
     }))
 }
 
-pub async fn delete_maintenance(
-    id: web::Path<String>,
-    data: web::Data<AppState>,
-) -> impl Responder {
-    info!("Received request to delete maintenance with ID {}", id);
+/// Bookings can only be made for cars that exist and are not deleted.
+async fn check_car(conn: &mut SqliteConnection, car_id: &str) -> Result<(), HttpResponse> {
+    match sqlx::query_scalar!(
+        r#"SELECT id AS "id!" FROM cars WHERE id = ? AND deleted_at IS NULL"#,
+        car_id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(HttpResponse::NotFound().json(json!({
+            "error": "Car not found"
+        }))),
+        Err(err) => {
+            error!("Failed to fetch car {}: {:?}", car_id, err);
+            Err(HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to schedule maintenance",
+                "details": err.to_string()
+            })))
+        }
+    }
+}
 
-    let maintenance_id = id.as_str();
+/// Validates the booking day and finds its bay and slot in the garage. The
+/// error side is the response to send back as-is.
+async fn resolve_placement(
+    conn: &mut SqliteConnection,
+    garage_id: &str,
+    scheduled_date: &str,
+    service_type: &str,
+    start_time: Option<&str>,
+    bay_id: Option<i64>,
+    exclude_maintenance_id: Option<i64>,
+) -> Result<slots::Placement, HttpResponse> {
+    let Some(date) = scheduler::parse_date(scheduled_date) else {
+        return Err(HttpResponse::BadRequest().json(json!({
+            "error": "Invalid scheduledDate",
+            "details": "scheduledDate must be formatted as YYYY-MM-DD"
+        })));
+    };
 
-    match sqlx::query!(
+    let hours = match garage_id.parse::<i64>() {
+        Ok(garage_id) => slots::garage_hours(&mut *conn, garage_id).await,
+        Err(_) => Ok(None),
+    };
+    let hours = match hours {
+        Ok(Some(hours)) => hours,
+        Ok(None) => {
+            return Err(HttpResponse::NotFound().json(json!({
+                "error": "Garage not found"
+            })));
+        }
+        Err(err) => {
+            error!("Failed to fetch garage {}: {:?}", garage_id, err);
+            return Err(HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to schedule maintenance",
+                "details": err.to_string()
+            })));
+        }
+    };
+
+    match slots::place_on_day(
+        &mut *conn,
+        &hours,
+        date,
+        service_type,
+        start_time,
+        bay_id,
+        exclude_maintenance_id,
+    )
+    .await
+    {
+        Ok(Ok(placement)) => Ok(placement),
+        Ok(Err(slots::SlotError::Window(details))) => Err(HttpResponse::BadRequest().json(json!({
+            "error": "Invalid booking time",
+            "details": details
+        }))),
+        Ok(Err(slots::SlotError::Taken(details))) => Err(HttpResponse::Conflict().json(json!({
+            "error": "Booking slot is not available",
+            "details": details
+        }))),
+        Err(err) => {
+            error!("Failed to place booking in garage {}: {:?}", garage_id, err);
+            Err(HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to schedule maintenance",
+                "details": err.to_string()
+            })))
+        }
+    }
+}
+
+/// Places a deleted booking back where it was, checking its bay, slot and
+/// mechanic are still free. Cancelled bookings hold nothing and are
+/// restored as they are, as are missing ones, which the caller reports.
+async fn restored_placement(
+    conn: &mut SqliteConnection,
+    maintenance_id: &str,
+) -> Result<Option<slots::Placement>, HttpResponse> {
+    let booking = match sqlx::query!(
         r#"
-        DELETE FROM maintenance
-        WHERE id = ?
+        SELECT id AS "id!", garage_id AS "garage_id!", service_type, scheduled_date, bay_id, start_time, mechanic_id
+        FROM maintenance
+        WHERE id = ? AND deleted_at IS NOT NULL AND status != 'CANCELLED'
         "#,
         maintenance_id
     )
-    .execute(&data.pool)
+    .fetch_optional(&mut *conn)
     .await
     {
-        Ok(result) => {
-            if result.rows_affected() == 0 {
-                error!("Maintenance with ID {} not found", maintenance_id);
-                HttpResponse::NotFound().json(json!({
-                    "error": "Maintenance not found",
-                }))
-            } else {
-                HttpResponse::Ok().json(json!({
-                    "id": maintenance_id,
-                    "deleted": true,
-                }))
-            }
-        }
+        Ok(Some(booking)) => booking,
+        Ok(None) => return Ok(None),
         Err(err) => {
-            error!("Failed to delete maintenance: {:?}", err);
-            HttpResponse::InternalServerError().json(json!({
-                "error": "Failed to delete maintenance",
-                "details": err.to_string(),
-            }))
+            error!("Failed to fetch maintenance {}: {:?}", maintenance_id, err);
+            return Err(HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to restore maintenance",
+                "details": err.to_string()
+            })));
+        }
+    };
+
+    let placement = resolve_placement(
+        &mut *conn,
+        &booking.garage_id,
+        &booking.scheduled_date,
+        &booking.service_type,
+        booking.start_time.as_deref(),
+        booking.bay_id,
+        Some(booking.id),
+    )
+    .await?;
+
+    if let Some(mechanic_id) = booking.mechanic_id {
+        match mechanics::find_conflict(
+            &mut *conn,
+            mechanic_id,
+            &booking.scheduled_date,
+            placement.start_time.as_deref(),
+            Some(placement.duration_minutes),
+            Some(booking.id),
+        )
+        .await
+        {
+            Ok(None) => {}
+            Ok(Some(details)) => {
+                return Err(HttpResponse::Conflict().json(json!({
+                    "error": "Mechanic is already booked",
+                    "details": details
+                })));
+            }
+            Err(err) => {
+                error!("Failed to check mechanic {} availability: {:?}", mechanic_id, err);
+                return Err(HttpResponse::InternalServerError().json(json!({
+                    "error": "Failed to restore maintenance",
+                    "details": err.to_string()
+                })));
+            }
         }
     }
+
+    Ok(Some(placement))
+}
+
+pub async fn delete_maintenance(
+    req: HttpRequest,
+    id: web::Path<String>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    info!("Received request to delete maintenance with ID {}", id);
+
+    set_maintenance_deleted(&req, &data, id.as_str(), true).await
 }
 
-pub async fn monthly_requests_report(
-    query: web::Query<HashMap<String, String>>,
+pub async fn restore_maintenance(
+    req: HttpRequest,
+    id: web::Path<String>,
     data: web::Data<AppState>,
 ) -> impl Responder {
-    let garage_id = match query.get("garageId").and_then(|v| v.parse::<i64>().ok()) {
-        Some(id) => id,
-        None => {
-            return HttpResponse::BadRequest().json(json!({
-                "error": "Missing or invalid garageId parameter"
+    info!("Received request to restore maintenance with ID {}", id);
+
+    set_maintenance_deleted(&req, &data, id.as_str(), false).await
+}
+
+async fn set_maintenance_deleted(
+    req: &HttpRequest,
+    data: &AppState,
+    maintenance_id: &str,
+    deleted: bool,
+) -> HttpResponse {
+    let (action, failure) = if deleted {
+        (AuditAction::Delete, "Failed to delete maintenance")
+    } else {
+        (AuditAction::Restore, "Failed to restore maintenance")
+    };
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("Failed to start transaction: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to start transaction",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let before = match audit::snapshot(&mut transaction, AuditResource::Maintenance, maintenance_id).await {
+        Ok(before) => before,
+        Err(err) => {
+            error!("Failed to read maintenance before change: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": failure,
+                "details": err.to_string(),
             }));
         }
     };
 
-    let start_month = query.get("startMonth").map(String::from).unwrap_or_default();
-    if start_month.is_empty() {
-        return HttpResponse::BadRequest().json(json!({
-            "error": "Missing startMonth parameter"
+    let result = if deleted {
+        sqlx::query!(
+            r#"
+            UPDATE maintenance
+            SET deleted_at = CURRENT_TIMESTAMP
+            WHERE id = ? AND deleted_at IS NULL
+            "#,
+            maintenance_id
+        )
+        .execute(&mut *transaction)
+        .await
+    } else {
+        // Its bay, slot or mechanic may have been booked since the delete.
+        let placement = match restored_placement(&mut transaction, maintenance_id).await {
+            Ok(placement) => placement,
+            Err(response) => {
+                let _ = transaction.rollback().await;
+                return response;
+            }
+        };
+        let placed = placement.is_some();
+        let (bay_id, start_time, duration_minutes) = match placement {
+            Some(placement) => (placement.bay_id, placement.start_time, Some(placement.duration_minutes)),
+            None => (None, None, None),
+        };
+        sqlx::query!(
+            r#"
+            UPDATE maintenance
+            SET deleted_at = NULL,
+                bay_id = CASE WHEN ?1 THEN ?2 ELSE bay_id END,
+                start_time = CASE WHEN ?1 THEN ?3 ELSE start_time END,
+                duration_minutes = CASE WHEN ?1 THEN ?4 ELSE duration_minutes END
+            WHERE id = ?5 AND deleted_at IS NOT NULL
+            "#,
+            placed,
+            bay_id,
+            start_time,
+            duration_minutes,
+            maintenance_id
+        )
+        .execute(&mut *transaction)
+        .await
+    };
+
+    match result {
+        Ok(result) if result.rows_affected() == 0 => {
+            error!("Maintenance with ID {} not found", maintenance_id);
+            let _ = transaction.rollback().await;
+            return HttpResponse::NotFound().json(json!({
+                "error": "Maintenance not found",
+            }));
+        }
+        Ok(_) => {}
+        Err(err) => {
+            error!("{}: {:?}", failure, err);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json(json!({
+                "error": failure,
+                "details": err.to_string(),
+            }));
+        }
+    }
+
+    if let Ok(id) = maintenance_id.parse::<i64>() {
+        if let Err(err) = inventory::sync_reservations(&mut transaction, id).await {
+            error!("Failed to update parts reserved for maintenance {}: {:?}", id, err);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json(json!({
+                "error": failure,
+                "details": err.to_string(),
+            }));
+        }
+    }
+
+    if let Err(err) = audit::record_change(
+        &mut transaction,
+        &audit::actor(req),
+        AuditResource::Maintenance,
+        maintenance_id,
+        action,
+        before,
+    )
+    .await
+    {
+        error!("Failed to write audit log: {:?}", err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": failure,
+            "details": err.to_string(),
         }));
     }
 
-    let end_month = query.get("endMonth").map(String::from).unwrap_or_default();
-    if end_month.is_empty() {
-        return HttpResponse::BadRequest().json(json!({
-            "error": "Missing endMonth parameter"
+    if let Err(err) = transaction.commit().await {
+        error!("Failed to commit transaction: {:?}", err);
+        return HttpResponse::InternalServerError().json(json!({
+            "error": failure,
+            "details": err.to_string(),
         }));
     }
 
+    data.events.relay(&data.pool).await;
+
+    if deleted {
+        HttpResponse::Ok().json(json!({
+            "id": maintenance_id,
+            "deleted": true,
+        }))
+    } else {
+        HttpResponse::Ok().json(json!({
+            "id": maintenance_id,
+            "restored": true,
+        }))
+    }
+}
+
+async fn transition_maintenance(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    maintenance_id: i64,
+    target: MaintenanceStatus,
+    body: TransitionMaintenanceDTO,
+) -> HttpResponse {
     info!(
-        "Generating monthly requests report for garageId: {}, startMonth: {}, endMonth: {}",
-        garage_id, start_month, end_month
+        "Received request to move maintenance {} to {}",
+        maintenance_id, target
     );
 
-    match sqlx::query!(
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("Failed to start transaction: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to start transaction",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let maintenance = match sqlx::query_as!(
+        Maintenance,
         r#"
         SELECT
-            strftime('%Y', scheduled_date) AS year,
-            strftime('%m', scheduled_date) AS month,
-            COUNT(*) AS requests
+            id AS "id!",
+            car_id,
+            garage_id,
+            service_type,
+            scheduled_date,
+            status AS "status: MaintenanceStatus"
         FROM maintenance
-        WHERE garage_id = ? 
-          AND strftime('%Y-%m', scheduled_date) BETWEEN ? AND ?
-        GROUP BY year, month
-        ORDER BY year, month
+        WHERE id = ? AND deleted_at IS NULL
         "#,
-        garage_id,
-        start_month,
-        end_month
+        maintenance_id
     )
-    .fetch_all(&data.pool)
+    .fetch_optional(&mut *transaction)
     .await
     {
-        Ok(records) => {
-            let report: Vec<serde_json::Value> = records
-                .into_iter()
-                .map(|record| {
-                    json!({
-                        "yearMonth": {
-                            "year": record.year.unwrap_or_default(),
-                            "month": record.month.unwrap_or_default().trim(), 
-                        },
-                        "requests": record.requests,
-                    })
-                })
-                .collect();
-
-            HttpResponse::Ok().json(report)
+        Ok(Some(maintenance)) => maintenance,
+        Ok(None) => {
+            warn!("Maintenance with ID {} not found", maintenance_id);
+            return HttpResponse::NotFound().json(json!({
+                "error": "Maintenance not found"
+            }));
+        }
+        Err(err) => {
+            error!("Failed to fetch maintenance with ID {}: {:?}", maintenance_id, err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch maintenance",
+                "details": err.to_string()
+            }));
         }
+    };
+
+    let resource_id = maintenance_id.to_string();
+    let before = match audit::snapshot(&mut transaction, AuditResource::Maintenance, &resource_id).await {
+        Ok(before) => before,
         Err(err) => {
-            error!("Failed to generate monthly requests report: {:?}", err);
+            error!("Failed to read maintenance before status change: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update maintenance status",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let current = maintenance.status;
+    if !current.can_transition_to(target) {
+        warn!(
+            "Rejected transition of maintenance {} from {} to {}",
+            maintenance_id, current, target
+        );
+        return HttpResponse::Conflict().json(json!({
+            "error": "Invalid status transition",
+            "details": format!("Cannot move maintenance from {} to {}", current, target)
+        }));
+    }
+
+    if let Err(err) = sqlx::query!(
+        r#"
+        UPDATE maintenance
+        SET status = ?
+        WHERE id = ?
+        "#,
+        target,
+        maintenance_id
+    )
+    .execute(&mut *transaction)
+    .await
+    {
+        error!("Failed to update maintenance status: {:?}", err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to update maintenance status",
+            "details": err.to_string()
+        }));
+    }
+
+    if let Err(err) = sqlx::query!(
+        r#"
+        INSERT INTO maintenance_status_history (maintenance_id, from_status, to_status, note)
+        VALUES (?, ?, ?, ?)
+        "#,
+        maintenance_id,
+        current,
+        target,
+        body.note
+    )
+    .execute(&mut *transaction)
+    .await
+    {
+        error!("Failed to record maintenance status history: {:?}", err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to update maintenance status",
+            "details": err.to_string()
+        }));
+    }
+
+    if let Err(response) =
+        apply_billing(&mut transaction, maintenance_id, current, target, data.tax_rate_bps).await
+    {
+        let _ = transaction.rollback().await;
+        return response;
+    }
+
+    if let (MaintenanceStatus::Completed, Some(odometer_km)) = (target, body.odometer_km) {
+        if let Err(response) =
+            record_completion_odometer(&mut transaction, &maintenance, odometer_km).await
+        {
+            let _ = transaction.rollback().await;
+            return response;
+        }
+    }
+
+    let stock = match target {
+        MaintenanceStatus::Completed => inventory::consume(&mut transaction, maintenance_id).await,
+        _ => inventory::sync_reservations(&mut transaction, maintenance_id).await,
+    };
+    if let Err(err) = stock {
+        error!("Failed to update parts stock for maintenance {}: {:?}", maintenance_id, err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to update maintenance status",
+            "details": err.to_string()
+        }));
+    }
+
+    if let Err(err) = audit::record_change(
+        &mut transaction,
+        &audit::actor(&req),
+        AuditResource::Maintenance,
+        &resource_id,
+        AuditAction::StatusChange,
+        before,
+    )
+    .await
+    {
+        error!("Failed to write audit log: {:?}", err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to update maintenance status",
+            "details": err.to_string()
+        }));
+    }
+
+    if let Err(err) = transaction.commit().await {
+        error!("Failed to commit transaction: {:?}", err);
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to finalize update",
+            "details": err.to_string()
+        }));
+    }
+
+    data.events.relay(&data.pool).await;
+
+    HttpResponse::Ok().json(json!({
+        "id": maintenance_id,
+        "previousStatus": current,
+        "status": target,
+    }))
+}
+
+/// Money side of a status change: quoting freezes the quoted total,
+/// bookings with priced work need a quote before they are confirmed or
+/// started, and completion issues the invoice.
+async fn apply_billing(
+    conn: &mut SqliteConnection,
+    maintenance_id: i64,
+    current: MaintenanceStatus,
+    target: MaintenanceStatus,
+    tax_rate_bps: i64,
+) -> Result<(), HttpResponse> {
+    let failed = |err: sqlx::Error| {
+        error!("Failed to update billing of maintenance {}: {:?}", maintenance_id, err);
+        HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to update maintenance status",
+            "details": err.to_string()
+        }))
+    };
+
+    match (current, target) {
+        (_, MaintenanceStatus::Quoted) => {
+            let line_items = billing::line_items(&mut *conn, maintenance_id).await.map_err(failed)?;
+            if line_items.is_empty() {
+                return Err(HttpResponse::Conflict().json(json!({
+                    "error": "Nothing to quote",
+                    "details": "Add line items before quoting the maintenance"
+                })));
+            }
+            let subtotal = billing::subtotal(&mut *conn, maintenance_id).await.map_err(failed)?;
+            let quoted_total = subtotal + billing::tax_cents(subtotal, tax_rate_bps);
+            sqlx::query!(
+                "UPDATE maintenance SET quoted_total_cents = ? WHERE id = ?",
+                quoted_total,
+                maintenance_id
+            )
+            .execute(&mut *conn)
+            .await
+            .map_err(failed)?;
+        }
+        (MaintenanceStatus::Scheduled, MaintenanceStatus::Confirmed | MaintenanceStatus::InProgress) => {
+            let line_items = billing::line_items(&mut *conn, maintenance_id).await.map_err(failed)?;
+            if !line_items.is_empty() {
+                return Err(HttpResponse::Conflict().json(json!({
+                    "error": "Quote required",
+                    "details": "Maintenance with line items must be quoted before it is confirmed or started"
+                })));
+            }
+        }
+        (_, MaintenanceStatus::Completed) => {
+            billing::issue_invoice(&mut *conn, maintenance_id, tax_rate_bps)
+                .await
+                .map_err(failed)?;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+async fn record_completion_odometer(
+    conn: &mut SqliteConnection,
+    maintenance: &Maintenance,
+    odometer_km: i64,
+) -> Result<(), HttpResponse> {
+    let Ok(car_id) = maintenance.car_id.parse::<i64>() else {
+        return Err(HttpResponse::BadRequest().json(json!({
+            "error": "Invalid odometer reading",
+            "details": format!("Maintenance {} has no valid car", maintenance.id)
+        })));
+    };
+    let today = Local::now().date_naive();
+
+    match odometer::check_monotonic(&mut *conn, car_id, today, odometer_km).await {
+        Ok(None) => {}
+        Ok(Some(reason)) => {
+            return Err(HttpResponse::BadRequest().json(json!({
+                "error": "Invalid odometer reading",
+                "details": reason
+            })));
+        }
+        Err(err) => {
+            error!("Failed to validate odometer reading: {:?}", err);
+            return Err(HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update maintenance status",
+                "details": err.to_string()
+            })));
+        }
+    }
+
+    if let Err(err) = odometer::insert_reading(conn, car_id, today, odometer_km, Some(maintenance.id)).await {
+        error!("Failed to record odometer reading: {:?}", err);
+        return Err(HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to update maintenance status",
+            "details": err.to_string()
+        })));
+    }
+
+    Ok(())
+}
+
+pub async fn quote_maintenance(
+    req: HttpRequest,
+    id: web::Path<i64>,
+    body: Option<web::Json<TransitionMaintenanceDTO>>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let body = body.map(|b| b.into_inner()).unwrap_or_default();
+    transition_maintenance(req, data, id.into_inner(), MaintenanceStatus::Quoted, body).await
+}
+
+pub async fn confirm_maintenance(
+    req: HttpRequest,
+    id: web::Path<i64>,
+    body: Option<web::Json<TransitionMaintenanceDTO>>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let body = body.map(|b| b.into_inner()).unwrap_or_default();
+    transition_maintenance(req, data, id.into_inner(), MaintenanceStatus::Confirmed, body).await
+}
+
+pub async fn start_maintenance(
+    req: HttpRequest,
+    id: web::Path<i64>,
+    body: Option<web::Json<TransitionMaintenanceDTO>>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let body = body.map(|b| b.into_inner()).unwrap_or_default();
+    transition_maintenance(req, data, id.into_inner(), MaintenanceStatus::InProgress, body).await
+}
+
+pub async fn complete_maintenance(
+    req: HttpRequest,
+    id: web::Path<i64>,
+    body: Option<web::Json<TransitionMaintenanceDTO>>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let body = body.map(|b| b.into_inner()).unwrap_or_default();
+    transition_maintenance(req, data, id.into_inner(), MaintenanceStatus::Completed, body).await
+}
+
+pub async fn cancel_maintenance(
+    req: HttpRequest,
+    id: web::Path<i64>,
+    body: Option<web::Json<TransitionMaintenanceDTO>>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let body = body.map(|b| b.into_inner()).unwrap_or_default();
+    transition_maintenance(req, data, id.into_inner(), MaintenanceStatus::Cancelled, body).await
+}
+
+pub async fn mark_maintenance_no_show(
+    req: HttpRequest,
+    id: web::Path<i64>,
+    body: Option<web::Json<TransitionMaintenanceDTO>>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let body = body.map(|b| b.into_inner()).unwrap_or_default();
+    transition_maintenance(req, data, id.into_inner(), MaintenanceStatus::NoShow, body).await
+}
+
+pub async fn get_maintenance_history(
+    id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let maintenance_id = id.into_inner();
+    info!("Fetching status history for maintenance with ID: {}", maintenance_id);
+
+    match sqlx::query_scalar!(
+        "SELECT id FROM maintenance WHERE id = ?",
+        maintenance_id
+    )
+    .fetch_optional(&data.pool)
+    .await
+    {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            warn!("Maintenance with ID {} not found", maintenance_id);
+            return HttpResponse::NotFound().json(json!({
+                "error": "Maintenance not found"
+            }));
+        }
+        Err(err) => {
+            error!("Failed to fetch maintenance with ID {}: {:?}", maintenance_id, err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch maintenance",
+                "details": err.to_string()
+            }));
+        }
+    }
+
+    match sqlx::query_as!(
+        MaintenanceStatusHistoryDTO,
+        r#"
+        SELECT
+            id AS "id!",
+            maintenance_id,
+            from_status AS "from_status: MaintenanceStatus",
+            to_status AS "to_status: MaintenanceStatus",
+            note,
+            changed_at
+        FROM maintenance_status_history
+        WHERE maintenance_id = ?
+        ORDER BY changed_at, id
+        "#,
+        maintenance_id
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(records) => HttpResponse::Ok().json(records),
+        Err(err) => {
+            error!("Failed to fetch maintenance status history: {:?}", err);
             HttpResponse::InternalServerError().json(json!({
-                "error": "Failed to generate monthly requests report",
+                "error": "Failed to fetch maintenance status history",
                 "details": err.to_string()
             }))
         }
     }
 }
+
+pub async fn get_car_maintenance_history(
+    id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let car_id = id.into_inner();
+    info!("Fetching maintenance history for car with ID: {}", car_id);
+
+    let car = match sqlx::query!(
+        r#"
+        SELECT id, make || ' ' || model AS "car_name!: String", license_plate
+        FROM cars
+        WHERE id = ? AND deleted_at IS NULL
+        "#,
+        car_id
+    )
+    .fetch_optional(&data.pool)
+    .await
+    {
+        Ok(Some(car)) => car,
+        Ok(None) => {
+            warn!("Car with ID {} not found", car_id);
+            return HttpResponse::NotFound().json(json!({
+                "error": "Car not found"
+            }));
+        }
+        Err(err) => {
+            error!("Failed to fetch car with ID {}: {:?}", car_id, err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch car",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let history = match sqlx::query_as!(
+        CarMaintenanceEntryDTO,
+        r#"
+        SELECT
+            maintenance.id AS "id!",
+            maintenance.scheduled_date,
+            maintenance.service_type,
+            maintenance.status AS "status: MaintenanceStatus",
+            maintenance.notes,
+            maintenance.garage_id,
+            garages.name AS garage_name,
+            (
+                SELECT MAX(reading_km)
+                FROM odometer_readings
+                WHERE odometer_readings.maintenance_id = maintenance.id
+            ) AS "odometer_km: i64",
+            (
+                SELECT MAX(changed_at)
+                FROM maintenance_status_history
+                WHERE maintenance_status_history.maintenance_id = maintenance.id
+                  AND maintenance_status_history.to_status = 'COMPLETED'
+            ) AS "completed_at: String",
+            (
+                SELECT total_cents
+                FROM invoices
+                WHERE invoices.maintenance_id = maintenance.id
+            ) AS "invoice_total_cents: i64"
+        FROM maintenance
+        JOIN garages ON maintenance.garage_id = garages.id
+        WHERE maintenance.car_id = ?
+          AND maintenance.deleted_at IS NULL
+        ORDER BY date(maintenance.scheduled_date), maintenance.id
+        "#,
+        car_id
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(history) => history,
+        Err(err) => {
+            error!("Failed to fetch maintenance history for car {}: {:?}", car_id, err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch maintenance history",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    // History is chronological, so the last completed entry per type wins.
+    let mut last_service_per_type: Vec<LastServiceDTO> = Vec::new();
+    for entry in history.iter().filter(|entry| entry.status == MaintenanceStatus::Completed) {
+        let last = LastServiceDTO {
+            service_type: entry.service_type.clone(),
+            maintenance_id: entry.id,
+            date: entry.scheduled_date.clone(),
+            garage_name: entry.garage_name.clone(),
+        };
+        match last_service_per_type
+            .iter_mut()
+            .find(|service| service.service_type == entry.service_type)
+        {
+            Some(service) => *service = last,
+            None => last_service_per_type.push(last),
+        }
+    }
+
+    let summary = CarMaintenanceSummaryDTO {
+        total_bookings: history.len(),
+        completed: history
+            .iter()
+            .filter(|entry| entry.status == MaintenanceStatus::Completed)
+            .count(),
+        last_service_per_type,
+        total_spend: Some(history.iter().filter_map(|entry| entry.invoice_total_cents).sum()),
+    };
+
+    HttpResponse::Ok().json(CarMaintenanceHistoryDTO {
+        car_id: car.id,
+        car_name: car.car_name,
+        license_plate: car.license_plate,
+        history,
+        summary,
+    })
+}
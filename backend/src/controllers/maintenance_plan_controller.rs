@@ -0,0 +1,293 @@
+use crate::app_state::AppState;
+use crate::models::maintenance_plan::{
+    CreateMaintenancePlanDTO, MaintenancePlanDTO, MaterializeQueryParams, UpdateMaintenancePlanDTO,
+};
+use crate::scheduler::{self, PlanInterval, DATE_FORMAT};
+use actix_web::{web, HttpResponse, Responder};
+use chrono::Local;
+use log::{error, info, warn};
+use serde_json::json;
+
+pub async fn get_car_plans(
+    id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let car_id = id.into_inner();
+
+    match sqlx::query_as!(
+        MaintenancePlanDTO,
+        r#"
+        SELECT
+            maintenance_plans.id AS "id!",
+            maintenance_plans.car_id,
+            maintenance_plans.garage_id,
+            garages.name AS garage_name,
+            maintenance_plans.service_type,
+            maintenance_plans.interval_days,
+            maintenance_plans.interval_months,
+            maintenance_plans.interval_km,
+            maintenance_plans.start_date,
+            maintenance_plans.next_due_date,
+            maintenance_plans.active AS "active: bool",
+            maintenance_plans.created_at
+        FROM maintenance_plans
+        JOIN garages ON garages.id = maintenance_plans.garage_id
+        WHERE maintenance_plans.car_id = ?
+        ORDER BY maintenance_plans.id
+        "#,
+        car_id
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(plans) => HttpResponse::Ok().json(plans),
+        Err(err) => {
+            error!("Failed to fetch maintenance plans for car {}: {:?}", car_id, err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch maintenance plans",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
+
+pub async fn create_car_plan(
+    id: web::Path<i64>,
+    plan_req: web::Json<CreateMaintenancePlanDTO>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let car_id = id.into_inner();
+    info!("Received request to create maintenance plan for car {}: {:?}", car_id, plan_req);
+
+    let interval = PlanInterval {
+        days: plan_req.interval_days,
+        months: plan_req.interval_months,
+    };
+
+    if interval.days.is_some() && interval.months.is_some() {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid interval",
+            "details": "Use either intervalDays or intervalMonths, not both"
+        }));
+    }
+
+    let intervals = [plan_req.interval_days, plan_req.interval_months, plan_req.interval_km];
+    if intervals.iter().all(Option::is_none) || intervals.iter().flatten().any(|value| *value <= 0) {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid interval",
+            "details": "At least one positive intervalDays, intervalMonths or intervalKm is required"
+        }));
+    }
+
+    let Some(start_date) = scheduler::parse_date(&plan_req.start_date) else {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid startDate",
+            "details": "Expected YYYY-MM-DD"
+        }));
+    };
+    let start_date = start_date.format(DATE_FORMAT).to_string();
+
+    let car = sqlx::query_scalar!(
+        "SELECT id FROM cars WHERE id = ? AND deleted_at IS NULL",
+        car_id
+    )
+    .fetch_optional(&data.pool)
+    .await;
+    let garage = sqlx::query_scalar!(
+        "SELECT id FROM garages WHERE id = ? AND deleted_at IS NULL",
+        plan_req.garage_id
+    )
+    .fetch_optional(&data.pool)
+    .await;
+
+    match (car, garage) {
+        (Ok(Some(_)), Ok(Some(_))) => {}
+        (Ok(None), _) => {
+            return HttpResponse::NotFound().json(json!({
+                "error": "Car not found"
+            }));
+        }
+        (_, Ok(None)) => {
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Garage not found",
+                "details": format!("No garage found with id {}", plan_req.garage_id)
+            }));
+        }
+        (Err(err), _) | (_, Err(err)) => {
+            error!("Failed to validate maintenance plan: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to create maintenance plan",
+                "details": err.to_string()
+            }));
+        }
+    }
+
+    match sqlx::query!(
+        r#"
+        INSERT INTO maintenance_plans
+            (car_id, garage_id, service_type, interval_days, interval_months, interval_km, start_date, next_due_date)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        car_id,
+        plan_req.garage_id,
+        plan_req.service_type,
+        plan_req.interval_days,
+        plan_req.interval_months,
+        plan_req.interval_km,
+        start_date,
+        start_date
+    )
+    .execute(&data.pool)
+    .await
+    {
+        Ok(result) => HttpResponse::Created().json(json!({
+            "id": result.last_insert_rowid(),
+            "carId": car_id,
+            "garageId": plan_req.garage_id,
+            "serviceType": plan_req.service_type,
+            "intervalDays": plan_req.interval_days,
+            "intervalMonths": plan_req.interval_months,
+            "intervalKm": plan_req.interval_km,
+            "startDate": start_date,
+            "nextDueDate": start_date,
+            "active": true,
+        })),
+        Err(err) => {
+            error!("Failed to create maintenance plan: {:?}", err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to create maintenance plan",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
+
+pub async fn update_plan(
+    id: web::Path<i64>,
+    plan_req: web::Json<UpdateMaintenancePlanDTO>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let plan_id = id.into_inner();
+    info!("Received request to update maintenance plan {}: {:?}", plan_id, plan_req);
+
+    if let Some(garage_id) = plan_req.garage_id {
+        match sqlx::query_scalar!(
+            "SELECT id FROM garages WHERE id = ? AND deleted_at IS NULL",
+            garage_id
+        )
+        .fetch_optional(&data.pool)
+        .await
+        {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return HttpResponse::BadRequest().json(json!({
+                    "error": "Garage not found",
+                    "details": format!("No garage found with id {}", garage_id)
+                }));
+            }
+            Err(err) => {
+                error!("Failed to validate maintenance plan: {:?}", err);
+                return HttpResponse::InternalServerError().json(json!({
+                    "error": "Failed to update maintenance plan",
+                    "details": err.to_string()
+                }));
+            }
+        }
+    }
+
+    match sqlx::query!(
+        r#"
+        UPDATE maintenance_plans
+        SET
+            garage_id = COALESCE(?, garage_id),
+            service_type = COALESCE(?, service_type),
+            active = COALESCE(?, active)
+        WHERE id = ?
+        "#,
+        plan_req.garage_id,
+        plan_req.service_type,
+        plan_req.active,
+        plan_id
+    )
+    .execute(&data.pool)
+    .await
+    {
+        Ok(result) if result.rows_affected() == 0 => {
+            warn!("Maintenance plan with ID {} not found", plan_id);
+            HttpResponse::NotFound().json(json!({
+                "error": "Maintenance plan not found"
+            }))
+        }
+        Ok(_) => HttpResponse::Ok().json(json!({
+            "id": plan_id,
+            "updated": true,
+        })),
+        Err(err) => {
+            error!("Failed to update maintenance plan: {:?}", err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update maintenance plan",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
+
+/// Plans are deactivated rather than removed so bookings keep their origin.
+pub async fn delete_plan(
+    id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let plan_id = id.into_inner();
+    info!("Received request to deactivate maintenance plan {}", plan_id);
+
+    match sqlx::query!(
+        "UPDATE maintenance_plans SET active = 0 WHERE id = ? AND active = 1",
+        plan_id
+    )
+    .execute(&data.pool)
+    .await
+    {
+        Ok(result) if result.rows_affected() == 0 => HttpResponse::NotFound().json(json!({
+            "error": "Maintenance plan not found"
+        })),
+        Ok(_) => HttpResponse::Ok().json(json!({
+            "id": plan_id,
+            "deleted": true,
+        })),
+        Err(err) => {
+            error!("Failed to deactivate maintenance plan: {:?}", err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to delete maintenance plan",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
+
+pub async fn materialize_plans(
+    query: web::Query<MaterializeQueryParams>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let horizon_days = query.horizon_days.unwrap_or(scheduler::DEFAULT_HORIZON_DAYS);
+    if !(0..=scheduler::MAX_HORIZON_DAYS).contains(&horizon_days) {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid horizonDays parameter",
+            "details": format!("Expected a value between 0 and {}", scheduler::MAX_HORIZON_DAYS)
+        }));
+    }
+
+    let today = Local::now().date_naive();
+    match scheduler::materialize_plans(&data.pool, today, horizon_days).await {
+        Ok(report) => {
+            data.events.relay(&data.pool).await;
+            HttpResponse::Ok().json(report)
+        }
+        Err(err) => {
+            error!("Failed to materialize maintenance plans: {:?}", err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to materialize maintenance plans",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
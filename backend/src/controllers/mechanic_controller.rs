@@ -0,0 +1,534 @@
+use crate::app_state::AppState;
+use crate::audit::{self, AuditAction, AuditResource};
+use crate::models::garage::GarageReportQueryParams;
+use crate::models::mechanic::{AssignMechanicDTO, CreateMechanicRequest, MechanicDTO, MechanicWorkloadDTO};
+use crate::{mechanics, scheduler, slots};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use log::{error, info, warn};
+use serde_json::json;
+use std::collections::HashMap;
+
+pub async fn get_garage_mechanics(
+    garage_id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let garage_id = garage_id.into_inner();
+
+    match sqlx::query_as!(
+        MechanicDTO,
+        r#"
+        SELECT id AS "id!", garage_id, name, active AS "active: bool"
+        FROM mechanics
+        WHERE garage_id = ?
+        ORDER BY name
+        "#,
+        garage_id
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(mechanics) => HttpResponse::Ok().json(mechanics),
+        Err(err) => {
+            error!("Failed to fetch mechanics of garage {}: {:?}", garage_id, err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch mechanics",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
+
+pub async fn create_mechanic(
+    req: HttpRequest,
+    garage_id: web::Path<i64>,
+    mechanic_req: web::Json<CreateMechanicRequest>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let garage_id = garage_id.into_inner();
+    info!("Received request to create mechanic for garage {}: {:?}", garage_id, mechanic_req);
+
+    if mechanic_req.name.trim().is_empty() {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid name",
+            "details": "name must not be empty"
+        }));
+    }
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("Failed to start transaction: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to start transaction",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    match sqlx::query_scalar!(
+        "SELECT id FROM garages WHERE id = ? AND deleted_at IS NULL",
+        garage_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(json!({
+                "error": "Garage not found"
+            }));
+        }
+        Err(err) => {
+            error!("Failed to fetch garage {}: {:?}", garage_id, err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to create mechanic",
+                "details": err.to_string()
+            }));
+        }
+    }
+
+    let name = mechanic_req.name.trim();
+
+    let mechanic_id = match sqlx::query!(
+        "INSERT INTO mechanics (garage_id, name) VALUES (?, ?)",
+        garage_id,
+        name
+    )
+    .execute(&mut *transaction)
+    .await
+    {
+        Ok(result) => result.last_insert_rowid(),
+        Err(err) => {
+            error!("Failed to create mechanic: {:?}", err);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to create mechanic",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    if let Err(err) = audit::record_change(
+        &mut transaction,
+        &audit::actor(&req),
+        AuditResource::Mechanic,
+        &mechanic_id.to_string(),
+        AuditAction::Create,
+        None,
+    )
+    .await
+    {
+        error!("Failed to write audit log: {:?}", err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to create mechanic",
+            "details": err.to_string()
+        }));
+    }
+
+    if let Err(err) = transaction.commit().await {
+        error!("Failed to commit transaction: {:?}", err);
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to create mechanic",
+            "details": err.to_string()
+        }));
+    }
+
+    HttpResponse::Created().json(MechanicDTO {
+        id: mechanic_id,
+        garage_id,
+        name: name.to_string(),
+        active: true,
+    })
+}
+
+/// Mechanics are deactivated rather than deleted so past bookings keep their
+/// assignee; they can no longer be assigned.
+pub async fn delete_mechanic(
+    req: HttpRequest,
+    id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let mechanic_id = id.into_inner();
+    info!("Received request to deactivate mechanic {}", mechanic_id);
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("Failed to start transaction: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to start transaction",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let resource_id = mechanic_id.to_string();
+    let before = match audit::snapshot(&mut transaction, AuditResource::Mechanic, &resource_id).await {
+        Ok(before) => before,
+        Err(err) => {
+            error!("Failed to read mechanic before change: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to delete mechanic",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    match sqlx::query!(
+        "UPDATE mechanics SET active = 0 WHERE id = ? AND active = 1",
+        mechanic_id
+    )
+    .execute(&mut *transaction)
+    .await
+    {
+        Ok(result) if result.rows_affected() == 0 => {
+            let _ = transaction.rollback().await;
+            return HttpResponse::NotFound().json(json!({
+                "error": "Mechanic not found"
+            }));
+        }
+        Ok(_) => {}
+        Err(err) => {
+            error!("Failed to deactivate mechanic {}: {:?}", mechanic_id, err);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to delete mechanic",
+                "details": err.to_string()
+            }));
+        }
+    }
+
+    if let Err(err) = audit::record_change(
+        &mut transaction,
+        &audit::actor(&req),
+        AuditResource::Mechanic,
+        &resource_id,
+        AuditAction::Delete,
+        before,
+    )
+    .await
+    {
+        error!("Failed to write audit log: {:?}", err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to delete mechanic",
+            "details": err.to_string()
+        }));
+    }
+
+    if let Err(err) = transaction.commit().await {
+        error!("Failed to commit transaction: {:?}", err);
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to delete mechanic",
+            "details": err.to_string()
+        }));
+    }
+
+    HttpResponse::Ok().json(json!({
+        "id": mechanic_id,
+        "deleted": true,
+    }))
+}
+
+pub async fn assign_mechanic(
+    req: HttpRequest,
+    id: web::Path<i64>,
+    assign_req: web::Json<AssignMechanicDTO>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let maintenance_id = id.into_inner();
+    info!("Received request to assign mechanic to maintenance {}: {:?}", maintenance_id, assign_req);
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("Failed to start transaction: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to start transaction",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let booking = match sqlx::query!(
+        r#"
+        SELECT garage_id AS "garage_id!", scheduled_date, start_time, duration_minutes
+        FROM maintenance
+        WHERE id = ? AND deleted_at IS NULL
+        "#,
+        maintenance_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    {
+        Ok(Some(booking)) => booking,
+        Ok(None) => {
+            warn!("Maintenance with ID {} not found", maintenance_id);
+            return HttpResponse::NotFound().json(json!({
+                "error": "Maintenance not found"
+            }));
+        }
+        Err(err) => {
+            error!("Failed to fetch maintenance {}: {:?}", maintenance_id, err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to assign mechanic",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    if let Some(mechanic_id) = assign_req.mechanic_id {
+        match sqlx::query!(
+            "SELECT garage_id, active AS \"active: bool\" FROM mechanics WHERE id = ?",
+            mechanic_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await
+        {
+            Ok(Some(mechanic)) if mechanic.active && mechanic.garage_id.to_string() == booking.garage_id => {}
+            Ok(Some(_)) => {
+                return HttpResponse::BadRequest().json(json!({
+                    "error": "Invalid mechanic",
+                    "details": format!(
+                        "Mechanic {} is not an active mechanic of garage {}",
+                        mechanic_id, booking.garage_id
+                    )
+                }));
+            }
+            Ok(None) => {
+                return HttpResponse::NotFound().json(json!({
+                    "error": "Mechanic not found"
+                }));
+            }
+            Err(err) => {
+                error!("Failed to fetch mechanic {}: {:?}", mechanic_id, err);
+                return HttpResponse::InternalServerError().json(json!({
+                    "error": "Failed to assign mechanic",
+                    "details": err.to_string()
+                }));
+            }
+        }
+
+        match mechanics::find_conflict(
+            &mut transaction,
+            mechanic_id,
+            &booking.scheduled_date,
+            booking.start_time.as_deref(),
+            booking.duration_minutes,
+            Some(maintenance_id),
+        )
+        .await
+        {
+            Ok(None) => {}
+            Ok(Some(details)) => {
+                return HttpResponse::Conflict().json(json!({
+                    "error": "Mechanic is already booked",
+                    "details": details
+                }));
+            }
+            Err(err) => {
+                error!("Failed to check mechanic {} availability: {:?}", mechanic_id, err);
+                return HttpResponse::InternalServerError().json(json!({
+                    "error": "Failed to assign mechanic",
+                    "details": err.to_string()
+                }));
+            }
+        }
+    }
+
+    let resource_id = maintenance_id.to_string();
+    let before = match audit::snapshot(&mut transaction, AuditResource::Maintenance, &resource_id).await {
+        Ok(before) => before,
+        Err(err) => {
+            error!("Failed to read maintenance before update: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to assign mechanic",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    if let Err(err) = sqlx::query!(
+        "UPDATE maintenance SET mechanic_id = ? WHERE id = ?",
+        assign_req.mechanic_id,
+        maintenance_id
+    )
+    .execute(&mut *transaction)
+    .await
+    {
+        error!("Failed to assign mechanic: {:?}", err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to assign mechanic",
+            "details": err.to_string()
+        }));
+    }
+
+    if let Err(err) = audit::record_change(
+        &mut transaction,
+        &audit::actor(&req),
+        AuditResource::Maintenance,
+        &resource_id,
+        AuditAction::Update,
+        before,
+    )
+    .await
+    {
+        error!("Failed to write audit log: {:?}", err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to assign mechanic",
+            "details": err.to_string()
+        }));
+    }
+
+    if let Err(err) = transaction.commit().await {
+        error!("Failed to commit transaction: {:?}", err);
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to assign mechanic",
+            "details": err.to_string()
+        }));
+    }
+
+    data.events.relay(&data.pool).await;
+
+    HttpResponse::Ok().json(json!({
+        "id": maintenance_id,
+        "mechanicId": assign_req.mechanic_id,
+    }))
+}
+
+/// Bookings and booked minutes per mechanic per day. Active mechanics are
+/// listed on every day of the range; deactivated ones only where they still
+/// have work.
+pub async fn get_mechanic_workload_report(
+    data: web::Data<AppState>,
+    query_params: web::Query<GarageReportQueryParams>,
+) -> impl Responder {
+    let garage_id = query_params.garage_id;
+
+    let (start_date, end_date) = match scheduler::parse_date_range(
+        &query_params.start_date,
+        &query_params.end_date,
+        data.report_max_days,
+    ) {
+        Ok(range) => range,
+        Err(details) => {
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Invalid date range",
+                "details": details
+            }));
+        }
+    };
+    let mut conn = match data.pool.acquire().await {
+        Ok(conn) => conn,
+        Err(err) => {
+            error!("Failed to acquire connection: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Database error",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let hours = match slots::garage_hours(&mut conn, garage_id).await {
+        Ok(Some(hours)) => hours,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(json!({
+                "error": "Garage not found",
+                "details": format!("No garage found with id {}", garage_id)
+            }));
+        }
+        Err(err) => {
+            error!("Database error while fetching garage {}: {:?}", garage_id, err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Database error",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let start = start_date.format(scheduler::DATE_FORMAT).to_string();
+    let end = end_date.format(scheduler::DATE_FORMAT).to_string();
+
+    let mechanics = match sqlx::query!(
+        r#"
+        SELECT id AS "id!", name, active AS "active: bool"
+        FROM mechanics
+        WHERE garage_id = ?
+        ORDER BY name, id
+        "#,
+        garage_id
+    )
+    .fetch_all(&mut *conn)
+    .await
+    {
+        Ok(mechanics) => mechanics,
+        Err(err) => {
+            error!("Failed to generate mechanic workload report: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch mechanic workload report",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let rows = match sqlx::query!(
+        r#"
+        SELECT
+            mechanic_id AS "mechanic_id!: i64",
+            date(scheduled_date) AS "date!: String",
+            COUNT(*) AS "bookings!: i64",
+            SUM(COALESCE(duration_minutes, ?4)) AS "booked_minutes!: i64"
+        FROM maintenance
+        WHERE garage_id = ?1
+          AND mechanic_id IS NOT NULL
+          AND status != 'CANCELLED'
+          AND deleted_at IS NULL
+          AND date(scheduled_date) BETWEEN ?2 AND ?3
+        GROUP BY 1, 2
+        "#,
+        garage_id,
+        start,
+        end,
+        hours.slot_minutes
+    )
+    .fetch_all(&mut *conn)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(err) => {
+            error!("Failed to generate mechanic workload report: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch mechanic workload report",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let workload: HashMap<(i64, String), (i64, i64)> = rows
+        .into_iter()
+        .map(|row| ((row.mechanic_id, row.date), (row.bookings, row.booked_minutes)))
+        .collect();
+
+    let mut report = Vec::new();
+    for date in scheduler::report_days(start_date, end_date) {
+        for mechanic in &mechanics {
+            let work = workload.get(&(mechanic.id, date.clone()));
+            if !mechanic.active && work.is_none() {
+                continue;
+            }
+            let (bookings, booked_minutes) = work.copied().unwrap_or_default();
+            report.push(MechanicWorkloadDTO {
+                date: date.clone(),
+                mechanic_id: mechanic.id,
+                mechanic_name: mechanic.name.clone(),
+                bookings,
+                booked_minutes,
+            });
+        }
+    }
+
+    HttpResponse::Ok().json(report)
+}
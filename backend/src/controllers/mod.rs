@@ -1,3 +1,19 @@
+pub mod audit_controller;
+pub mod bay_controller;
+pub mod billing_controller;
+pub mod calendar_controller;
 pub mod car_controller;
+pub mod event_controller;
 pub mod garage_controller;
+pub mod import_controller;
+pub mod job_controller;
 pub mod maintenance_controller;
+pub mod maintenance_plan_controller;
+pub mod mechanic_controller;
+pub mod notification_controller;
+pub mod odometer_controller;
+pub mod part_controller;
+pub mod report_controller;
+pub mod search_controller;
+pub mod service_type_controller;
+pub mod webhook_controller;
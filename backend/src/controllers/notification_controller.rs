@@ -0,0 +1,47 @@
+use crate::app_state::AppState;
+use crate::models::notification::NotificationDTO;
+use actix_web::{web, HttpResponse, Responder};
+use log::error;
+use serde_json::json;
+
+/// Messages sent, or attempted, to the owner about a booking.
+pub async fn get_maintenance_notifications(
+    id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let maintenance_id = id.into_inner();
+
+    match sqlx::query_as!(
+        NotificationDTO,
+        r#"
+        SELECT
+            id AS "id!",
+            maintenance_id,
+            kind,
+            channel,
+            recipient,
+            subject,
+            body,
+            scheduled_date,
+            status,
+            error,
+            created_at
+        FROM notifications
+        WHERE maintenance_id = ?
+        ORDER BY id
+        "#,
+        maintenance_id
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(notifications) => HttpResponse::Ok().json(notifications),
+        Err(err) => {
+            error!("Failed to fetch notifications for maintenance {}: {:?}", maintenance_id, err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch notifications",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
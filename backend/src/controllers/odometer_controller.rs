@@ -0,0 +1,174 @@
+use crate::app_state::AppState;
+use crate::models::odometer::{CreateOdometerReadingDTO, DueForServiceQueryParams, OdometerReadingDTO};
+use crate::odometer;
+use crate::scheduler::{self, DATE_FORMAT};
+use actix_web::{web, HttpResponse, Responder};
+use chrono::Local;
+use log::{error, info, warn};
+use serde_json::json;
+
+pub async fn get_car_odometer(
+    id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let car_id = id.into_inner();
+
+    match sqlx::query_as!(
+        OdometerReadingDTO,
+        r#"
+        SELECT
+            id AS "id!",
+            car_id,
+            reading_km,
+            recorded_on,
+            maintenance_id,
+            created_at
+        FROM odometer_readings
+        WHERE car_id = ?
+        ORDER BY recorded_on, reading_km, id
+        "#,
+        car_id
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(readings) => HttpResponse::Ok().json(readings),
+        Err(err) => {
+            error!("Failed to fetch odometer readings for car {}: {:?}", car_id, err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch odometer readings",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
+
+pub async fn add_odometer_reading(
+    id: web::Path<i64>,
+    reading_req: web::Json<CreateOdometerReadingDTO>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let car_id = id.into_inner();
+    info!("Received odometer reading for car {}: {:?}", car_id, reading_req);
+
+    let recorded_on = match reading_req.recorded_on.as_deref() {
+        Some(value) => match scheduler::parse_date(value) {
+            Some(date) => date,
+            None => {
+                return HttpResponse::BadRequest().json(json!({
+                    "error": "Invalid recordedOn",
+                    "details": "Expected YYYY-MM-DD"
+                }));
+            }
+        },
+        None => Local::now().date_naive(),
+    };
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("Failed to start transaction: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to start transaction",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    match sqlx::query_scalar!(
+        "SELECT id FROM cars WHERE id = ? AND deleted_at IS NULL",
+        car_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(json!({
+                "error": "Car not found"
+            }));
+        }
+        Err(err) => {
+            error!("Failed to fetch car {}: {:?}", car_id, err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to record odometer reading",
+                "details": err.to_string()
+            }));
+        }
+    }
+
+    match odometer::check_monotonic(&mut transaction, car_id, recorded_on, reading_req.reading_km).await {
+        Ok(None) => {}
+        Ok(Some(reason)) => {
+            warn!("Rejected odometer reading for car {}: {}", car_id, reason);
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Invalid odometer reading",
+                "details": reason
+            }));
+        }
+        Err(err) => {
+            error!("Failed to validate odometer reading: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to record odometer reading",
+                "details": err.to_string()
+            }));
+        }
+    }
+
+    let reading_id =
+        match odometer::insert_reading(&mut transaction, car_id, recorded_on, reading_req.reading_km, None).await {
+            Ok(reading_id) => reading_id,
+            Err(err) => {
+                error!("Failed to record odometer reading: {:?}", err);
+                return HttpResponse::InternalServerError().json(json!({
+                    "error": "Failed to record odometer reading",
+                    "details": err.to_string()
+                }));
+            }
+        };
+
+    if let Err(err) = transaction.commit().await {
+        error!("Failed to commit transaction: {:?}", err);
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to record odometer reading",
+            "details": err.to_string()
+        }));
+    }
+
+    HttpResponse::Created().json(json!({
+        "id": reading_id,
+        "carId": car_id,
+        "readingKm": reading_req.reading_km,
+        "recordedOn": recorded_on.format(DATE_FORMAT).to_string(),
+    }))
+}
+
+pub async fn get_due_for_service(
+    query: web::Query<DueForServiceQueryParams>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    info!("Computing cars due for service: {:?}", query);
+
+    let mut conn = match data.pool.acquire().await {
+        Ok(conn) => conn,
+        Err(err) => {
+            error!("Failed to acquire connection: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to compute cars due for service",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let today = Local::now().date_naive();
+    match odometer::due_for_service(&mut conn, today, query.within_days, query.within_km, query.car_id).await {
+        Ok(due) => HttpResponse::Ok().json(due),
+        Err(err) => {
+            error!("Failed to compute cars due for service: {:?}", err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to compute cars due for service",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
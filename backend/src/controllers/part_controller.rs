@@ -0,0 +1,488 @@
+use crate::app_state::AppState;
+use crate::inventory;
+use crate::models::part::{
+    CreatePartRequest, LowStockDTO, LowStockQueryParams, PartDTO, ServiceTypePartDTO, StockLevelDTO, UpdatePartRequest,
+    UpdateStockRequest,
+};
+use actix_web::{web, HttpResponse, Responder};
+use log::{error, info};
+use serde_json::json;
+
+pub async fn get_all_parts(data: web::Data<AppState>) -> impl Responder {
+    match sqlx::query_as!(
+        PartDTO,
+        r#"SELECT id AS "id!", sku, name FROM parts ORDER BY sku"#
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(parts) => HttpResponse::Ok().json(parts),
+        Err(err) => {
+            error!("Failed to fetch parts: {:?}", err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch parts",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
+
+pub async fn create_part(
+    part_req: web::Json<CreatePartRequest>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    info!("Received request to create part: {:?}", part_req);
+
+    let sku = part_req.sku.trim();
+    if sku.is_empty() {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid sku",
+            "details": "sku must not be empty"
+        }));
+    }
+
+    match sqlx::query!(
+        "INSERT INTO parts (sku, name) VALUES (?, ?)",
+        sku,
+        part_req.name
+    )
+    .execute(&data.pool)
+    .await
+    {
+        Ok(result) => HttpResponse::Created().json(PartDTO {
+            id: result.last_insert_rowid(),
+            sku: sku.to_string(),
+            name: part_req.name.clone(),
+        }),
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+            HttpResponse::Conflict().json(json!({
+                "error": "Part already exists",
+                "details": err.to_string()
+            }))
+        }
+        Err(err) => {
+            error!("Failed to create part: {:?}", err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to create part",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
+
+pub async fn edit_part(
+    id: web::Path<i64>,
+    part_req: web::Json<UpdatePartRequest>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let part_id = id.into_inner();
+    info!("Received request to update part {}: {:?}", part_id, part_req);
+
+    let sku = part_req.sku.as_deref().map(str::trim);
+    if sku.is_some_and(str::is_empty) {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid sku",
+            "details": "sku must not be empty"
+        }));
+    }
+
+    match sqlx::query!(
+        "UPDATE parts SET sku = COALESCE(?, sku), name = COALESCE(?, name) WHERE id = ?",
+        sku,
+        part_req.name,
+        part_id
+    )
+    .execute(&data.pool)
+    .await
+    {
+        Ok(result) if result.rows_affected() == 0 => HttpResponse::NotFound().json(json!({
+            "error": "Part not found"
+        })),
+        Ok(_) => HttpResponse::Ok().json(json!({
+            "id": part_id,
+            "updated": true,
+        })),
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+            HttpResponse::Conflict().json(json!({
+                "error": "Part already exists",
+                "details": err.to_string()
+            }))
+        }
+        Err(err) => {
+            error!("Failed to update part: {:?}", err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update part",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
+
+pub async fn get_garage_stock(
+    garage_id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let garage_id = garage_id.into_inner();
+
+    match sqlx::query_as!(
+        StockLevelDTO,
+        r#"
+        SELECT
+            parts.id AS "part_id!",
+            parts.sku,
+            parts.name,
+            COALESCE(garage_parts.on_hand, 0) AS "on_hand!: i64",
+            COALESCE(reserved.quantity, 0) AS "reserved!: i64",
+            COALESCE(garage_parts.on_hand, 0) - COALESCE(reserved.quantity, 0) AS "available!: i64",
+            COALESCE(garage_parts.reorder_level, 0) AS "reorder_level!: i64"
+        FROM parts
+        LEFT JOIN garage_parts
+            ON garage_parts.part_id = parts.id AND garage_parts.garage_id = ?1
+        LEFT JOIN (
+            SELECT part_id, SUM(quantity) AS quantity
+            FROM maintenance_part_reservations
+            WHERE garage_id = ?1 AND consumed_at IS NULL
+            GROUP BY part_id
+        ) AS reserved ON reserved.part_id = parts.id
+        ORDER BY parts.sku
+        "#,
+        garage_id
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(stock) => HttpResponse::Ok().json(stock),
+        Err(err) => {
+            error!("Failed to fetch stock of garage {}: {:?}", garage_id, err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch stock",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
+
+pub async fn update_garage_stock(
+    path: web::Path<(i64, i64)>,
+    stock_req: web::Json<UpdateStockRequest>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let (garage_id, part_id) = path.into_inner();
+    info!("Received request to update stock of part {} in garage {}: {:?}", part_id, garage_id, stock_req);
+
+    if stock_req.on_hand.is_some_and(|on_hand| on_hand < 0)
+        || stock_req.reorder_level.is_some_and(|level| level < 0)
+    {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid stock level",
+            "details": "onHand and reorderLevel must not be negative"
+        }));
+    }
+
+    match sqlx::query!(
+        r#"
+        SELECT
+            (SELECT id FROM garages WHERE id = ? AND deleted_at IS NULL) AS garage_id,
+            (SELECT id FROM parts WHERE id = ?) AS part_id
+        "#,
+        garage_id,
+        part_id
+    )
+    .fetch_one(&data.pool)
+    .await
+    {
+        Ok(found) if found.garage_id.is_none() => {
+            return HttpResponse::NotFound().json(json!({
+                "error": "Garage not found"
+            }));
+        }
+        Ok(found) if found.part_id.is_none() => {
+            return HttpResponse::NotFound().json(json!({
+                "error": "Part not found"
+            }));
+        }
+        Ok(_) => {}
+        Err(err) => {
+            error!("Failed to look up garage {} and part {}: {:?}", garage_id, part_id, err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update stock",
+                "details": err.to_string()
+            }));
+        }
+    }
+
+    match sqlx::query!(
+        r#"
+        INSERT INTO garage_parts (garage_id, part_id, on_hand, reorder_level)
+        VALUES (?1, ?2, COALESCE(?3, 0), COALESCE(?4, 0))
+        ON CONFLICT (garage_id, part_id) DO UPDATE SET
+            on_hand = COALESCE(?3, on_hand),
+            reorder_level = COALESCE(?4, reorder_level)
+        "#,
+        garage_id,
+        part_id,
+        stock_req.on_hand,
+        stock_req.reorder_level
+    )
+    .execute(&data.pool)
+    .await
+    {
+        Ok(_) => HttpResponse::Ok().json(json!({
+            "garageId": garage_id,
+            "partId": part_id,
+            "updated": true,
+        })),
+        Err(err) => {
+            error!("Failed to update stock: {:?}", err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update stock",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
+
+pub async fn get_service_type_parts(
+    id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let service_type_id = id.into_inner();
+
+    match sqlx::query_as!(
+        ServiceTypePartDTO,
+        r#"
+        SELECT part_id, quantity
+        FROM service_type_parts
+        WHERE service_type_id = ?
+        ORDER BY part_id
+        "#,
+        service_type_id
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(parts) => HttpResponse::Ok().json(parts),
+        Err(err) => {
+            error!("Failed to fetch parts of service type {}: {:?}", service_type_id, err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch service type parts",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
+
+/// Replaces the parts a service type consumes and re-reserves the open
+/// bookings of that service.
+pub async fn set_service_type_parts(
+    id: web::Path<i64>,
+    parts_req: web::Json<Vec<ServiceTypePartDTO>>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let service_type_id = id.into_inner();
+    info!("Received request to set parts of service type {}: {:?}", service_type_id, parts_req);
+
+    if parts_req.iter().any(|part| part.quantity <= 0) {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid quantity",
+            "details": "quantity must be positive"
+        }));
+    }
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("Failed to start transaction: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to start transaction",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    match sqlx::query_scalar!("SELECT id FROM service_types WHERE id = ?", service_type_id)
+        .fetch_optional(&mut *transaction)
+        .await
+    {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(json!({
+                "error": "Service type not found"
+            }));
+        }
+        Err(err) => {
+            error!("Failed to fetch service type {}: {:?}", service_type_id, err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to set service type parts",
+                "details": err.to_string()
+            }));
+        }
+    }
+
+    if let Err(err) = sqlx::query!(
+        "DELETE FROM service_type_parts WHERE service_type_id = ?",
+        service_type_id
+    )
+    .execute(&mut *transaction)
+    .await
+    {
+        error!("Failed to clear service type parts: {:?}", err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to set service type parts",
+            "details": err.to_string()
+        }));
+    }
+
+    for part in parts_req.iter() {
+        match sqlx::query!(
+            r#"
+            INSERT INTO service_type_parts (service_type_id, part_id, quantity)
+            SELECT ?1, id, ?3 FROM parts WHERE id = ?2
+            ON CONFLICT (service_type_id, part_id) DO UPDATE SET quantity = quantity + excluded.quantity
+            "#,
+            service_type_id,
+            part.part_id,
+            part.quantity
+        )
+        .execute(&mut *transaction)
+        .await
+        {
+            Ok(result) if result.rows_affected() == 0 => {
+                let _ = transaction.rollback().await;
+                return HttpResponse::BadRequest().json(json!({
+                    "error": "Part not found",
+                    "details": format!("No part with id {}", part.part_id)
+                }));
+            }
+            Ok(_) => {}
+            Err(err) => {
+                error!("Failed to add service type part: {:?}", err);
+                let _ = transaction.rollback().await;
+                return HttpResponse::InternalServerError().json(json!({
+                    "error": "Failed to set service type parts",
+                    "details": err.to_string()
+                }));
+            }
+        }
+    }
+
+    if let Err(err) = inventory::sync_service_type(&mut transaction, service_type_id).await {
+        error!("Failed to re-reserve parts for service type {}: {:?}", service_type_id, err);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to set service type parts",
+            "details": err.to_string()
+        }));
+    }
+
+    // Duplicate part ids were merged above, so answer with what was stored.
+    let parts = match sqlx::query_as!(
+        ServiceTypePartDTO,
+        r#"
+        SELECT part_id, quantity
+        FROM service_type_parts
+        WHERE service_type_id = ?
+        ORDER BY part_id
+        "#,
+        service_type_id
+    )
+    .fetch_all(&mut *transaction)
+    .await
+    {
+        Ok(parts) => parts,
+        Err(err) => {
+            error!("Failed to fetch parts of service type {}: {:?}", service_type_id, err);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to set service type parts",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    if let Err(err) = transaction.commit().await {
+        error!("Failed to commit transaction: {:?}", err);
+        return HttpResponse::InternalServerError().json(json!({
+            "error": "Failed to set service type parts",
+            "details": err.to_string()
+        }));
+    }
+
+    HttpResponse::Ok().json(parts)
+}
+
+/// Parts whose stock left after reservations is at or below the reorder
+/// level, across all garages or for one.
+pub async fn low_stock_report(
+    query: web::Query<LowStockQueryParams>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    match sqlx::query!(
+        r#"
+        WITH stock AS (
+            SELECT garage_id, part_id FROM garage_parts
+            UNION
+            SELECT garage_id, part_id FROM maintenance_part_reservations WHERE consumed_at IS NULL
+        ),
+        reserved AS (
+            SELECT garage_id, part_id, SUM(quantity) AS quantity
+            FROM maintenance_part_reservations
+            WHERE consumed_at IS NULL
+            GROUP BY garage_id, part_id
+        )
+        SELECT
+            garages.id AS "garage_id!",
+            garages.name AS garage_name,
+            parts.id AS "part_id!",
+            parts.sku,
+            parts.name,
+            COALESCE(garage_parts.on_hand, 0) AS "on_hand!: i64",
+            COALESCE(reserved.quantity, 0) AS "reserved!: i64",
+            COALESCE(garage_parts.reorder_level, 0) AS "reorder_level!: i64"
+        FROM stock
+        JOIN garages ON garages.id = stock.garage_id
+        JOIN parts ON parts.id = stock.part_id
+        LEFT JOIN garage_parts
+            ON garage_parts.garage_id = stock.garage_id AND garage_parts.part_id = stock.part_id
+        LEFT JOIN reserved
+            ON reserved.garage_id = stock.garage_id AND reserved.part_id = stock.part_id
+        WHERE (?1 IS NULL OR stock.garage_id = ?1)
+          AND garages.deleted_at IS NULL
+          AND COALESCE(garage_parts.on_hand, 0) - COALESCE(reserved.quantity, 0)
+              <= COALESCE(garage_parts.reorder_level, 0)
+        ORDER BY garages.id, parts.sku
+        "#,
+        query.garage_id
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(rows) => HttpResponse::Ok().json(
+            rows.into_iter()
+                .map(|row| {
+                    let available = row.on_hand - row.reserved;
+                    LowStockDTO {
+                        garage_id: row.garage_id,
+                        garage_name: row.garage_name,
+                        part_id: row.part_id,
+                        sku: row.sku,
+                        name: row.name,
+                        on_hand: row.on_hand,
+                        reserved: row.reserved,
+                        available,
+                        reorder_level: row.reorder_level,
+                        shortfall: (row.reorder_level - available).max(0),
+                    }
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Err(err) => {
+            error!("Failed to generate low stock report: {:?}", err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to generate low stock report",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
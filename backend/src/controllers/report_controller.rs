@@ -0,0 +1,392 @@
+use crate::app_state::AppState;
+use crate::export;
+use crate::models::maintenance::MaintenanceStatus;
+use crate::models::report::{GarageRevenueDTO, MonthlyRequestsReportDTO, MonthlyRevenueDTO, ServiceMixDTO, StatusCountDTO, YearMonth};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use log::{error, info};
+use serde_json::json;
+use std::collections::HashMap;
+
+fn average_ticket(revenue_cents: i64, invoices: i64) -> i64 {
+    if invoices == 0 {
+        return 0;
+    }
+    (revenue_cents + invoices / 2) / invoices
+}
+
+/// Reads `startMonth`/`endMonth` as `YYYY-MM`, rejecting garbage, reversed
+/// ranges and ranges of more than `max_months` months.
+fn month_range(query: &HashMap<String, String>, max_months: i64) -> Result<(YearMonth, YearMonth), HttpResponse> {
+    let mut months = Vec::with_capacity(2);
+    for param in ["startMonth", "endMonth"] {
+        let Some(value) = query.get(param).filter(|value| !value.is_empty()) else {
+            return Err(HttpResponse::BadRequest().json(json!({
+                "error": format!("Missing {} parameter", param)
+            })));
+        };
+        let Some(month) = YearMonth::parse(value) else {
+            return Err(HttpResponse::BadRequest().json(json!({
+                "error": format!("Invalid {} parameter", param),
+                "details": format!("'{}' is not a month formatted as YYYY-MM", value)
+            })));
+        };
+        months.push(month);
+    }
+
+    let (start_month, end_month) = (months[0], months[1]);
+    if end_month < start_month {
+        return Err(HttpResponse::BadRequest().json(json!({
+            "error": "Invalid month range",
+            "details": "endMonth must not be before startMonth"
+        })));
+    }
+    let months = YearMonth::months_between(start_month, end_month);
+    if months > max_months {
+        return Err(HttpResponse::BadRequest().json(json!({
+            "error": "Invalid month range",
+            "details": format!("Month range covers {} months; at most {} are allowed", months, max_months)
+        })));
+    }
+
+    Ok((start_month, end_month))
+}
+
+/// Bookings per month, with a zero entry for months without any.
+pub async fn monthly_requests_report(
+    req: HttpRequest,
+    query: web::Query<HashMap<String, String>>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let format = match export::negotiate(&req) {
+        Ok(format) => format,
+        Err(response) => return response,
+    };
+
+    let garage_id = match query.get("garageId").and_then(|v| v.parse::<i64>().ok()) {
+        Some(id) => id,
+        None => {
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Missing or invalid garageId parameter"
+            }));
+        }
+    };
+
+    let (start_month, end_month) = match month_range(&query, data.report_max_months) {
+        Ok(range) => range,
+        Err(response) => return response,
+    };
+
+    let status = match query.get("status").map(|v| v.parse::<MaintenanceStatus>()) {
+        Some(Ok(status)) => Some(status),
+        Some(Err(err)) => {
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Invalid status parameter",
+                "details": err
+            }));
+        }
+        None => None,
+    };
+
+    info!(
+        "Generating monthly requests report for garageId: {}, startMonth: {}, endMonth: {}, status: {:?}",
+        garage_id, start_month, end_month, status
+    );
+
+    let start = start_month.to_string();
+    let end = end_month.to_string();
+
+    let counts = match sqlx::query!(
+        r#"
+        SELECT
+            strftime('%Y-%m', scheduled_date) AS "month!: String",
+            status AS "status!: MaintenanceStatus",
+            COUNT(*) AS "requests!: i32"
+        FROM maintenance
+        WHERE garage_id = ?1
+          AND strftime('%Y-%m', scheduled_date) BETWEEN ?2 AND ?3
+          AND (?4 IS NULL OR status = ?4)
+          AND deleted_at IS NULL
+        GROUP BY 1, 2
+        "#,
+        garage_id,
+        start,
+        end,
+        status
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(records) => records
+            .into_iter()
+            .map(|record| ((record.month, record.status.as_str()), record.requests))
+            .collect::<HashMap<_, _>>(),
+        Err(err) => {
+            error!("Failed to generate monthly requests report: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to generate monthly requests report",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let report: Vec<MonthlyRequestsReportDTO> = YearMonth::range(start_month, end_month)
+        .map(|year_month| {
+            let month = year_month.to_string();
+            let by_status: Vec<StatusCountDTO> = MaintenanceStatus::ALL
+                .into_iter()
+                .map(|status| StatusCountDTO {
+                    requests: counts.get(&(month.clone(), status.as_str())).copied().unwrap_or(0),
+                    status,
+                })
+                .collect();
+            MonthlyRequestsReportDTO {
+                requests: by_status.iter().map(|count| count.requests).sum(),
+                year_month,
+                by_status,
+            }
+        })
+        .collect();
+
+    export::respond_with(format, "monthly-requests", report).await
+}
+
+pub async fn monthly_revenue_report(
+    query: web::Query<HashMap<String, String>>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let garage_id = match query.get("garageId").and_then(|v| v.parse::<i64>().ok()) {
+        Some(id) => id,
+        None => {
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Missing or invalid garageId parameter"
+            }));
+        }
+    };
+
+    let (start_month, end_month) = match month_range(&query, data.report_max_months) {
+        Ok(range) => range,
+        Err(response) => return response,
+    };
+
+    info!(
+        "Generating monthly revenue report for garageId: {}, startMonth: {}, endMonth: {}",
+        garage_id, start_month, end_month
+    );
+
+    let start = start_month.to_string();
+    let end = end_month.to_string();
+
+    let rows = match sqlx::query!(
+        r#"
+        SELECT
+            strftime('%Y-%m', maintenance.scheduled_date) AS "month!: String",
+            maintenance.service_type,
+            COUNT(*) AS "invoices!: i64",
+            CAST(SUM(invoices.subtotal_cents) AS INTEGER) AS "revenue_cents!: i64",
+            CAST(SUM(invoices.tax_cents) AS INTEGER) AS "tax_cents!: i64"
+        FROM maintenance
+        JOIN invoices ON invoices.maintenance_id = maintenance.id
+        WHERE maintenance.garage_id = ?
+          AND strftime('%Y-%m', maintenance.scheduled_date) BETWEEN ? AND ?
+          AND maintenance.deleted_at IS NULL
+        GROUP BY 1, maintenance.service_type
+        ORDER BY 1, 4 DESC, maintenance.service_type
+        "#,
+        garage_id,
+        start,
+        end
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(err) => {
+            error!("Failed to generate monthly revenue report: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to generate monthly revenue report",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let mut report: Vec<MonthlyRevenueDTO> = YearMonth::range(start_month, end_month)
+        .map(|year_month| MonthlyRevenueDTO {
+            year_month,
+            invoices: 0,
+            revenue_cents: 0,
+            tax_cents: 0,
+            average_ticket_cents: 0,
+            service_mix: Vec::new(),
+        })
+        .collect();
+    let index: HashMap<String, usize> = report
+        .iter()
+        .enumerate()
+        .map(|(position, month)| (month.year_month.to_string(), position))
+        .collect();
+
+    for row in rows {
+        let Some(month) = index.get(&row.month).map(|position| &mut report[*position]) else {
+            continue;
+        };
+        month.invoices += row.invoices;
+        month.revenue_cents += row.revenue_cents;
+        month.tax_cents += row.tax_cents;
+        month.service_mix.push(ServiceMixDTO {
+            service_type: row.service_type,
+            invoices: row.invoices,
+            revenue_cents: row.revenue_cents,
+        });
+    }
+    for month in &mut report {
+        month.average_ticket_cents = average_ticket(month.revenue_cents, month.invoices);
+    }
+
+    HttpResponse::Ok().json(report)
+}
+
+/// Compares every garage of a city over the same months, best earning first.
+pub async fn city_revenue_report(
+    query: web::Query<HashMap<String, String>>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let city = match query.get("city").map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        Some(city) => city.to_string(),
+        None => {
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Missing city parameter"
+            }));
+        }
+    };
+
+    let (start_month, end_month) = match month_range(&query, data.report_max_months) {
+        Ok(range) => range,
+        Err(response) => return response,
+    };
+
+    info!(
+        "Generating city revenue report for city: {}, startMonth: {}, endMonth: {}",
+        city, start_month, end_month
+    );
+
+    let start = start_month.to_string();
+    let end = end_month.to_string();
+
+    let rows = match sqlx::query!(
+        r#"
+        SELECT
+            garages.id AS "garage_id!",
+            garages.name AS garage_name,
+            revenue.service_type AS "service_type?: String",
+            CAST(COALESCE(revenue.invoices, 0) AS INTEGER) AS "invoices!: i64",
+            CAST(COALESCE(revenue.revenue_cents, 0) AS INTEGER) AS "revenue_cents!: i64"
+        FROM garages
+        LEFT JOIN (
+            SELECT
+                maintenance.garage_id,
+                maintenance.service_type,
+                COUNT(*) AS invoices,
+                SUM(invoices.subtotal_cents) AS revenue_cents
+            FROM maintenance
+            JOIN invoices ON invoices.maintenance_id = maintenance.id
+            WHERE strftime('%Y-%m', maintenance.scheduled_date) BETWEEN ?2 AND ?3
+              AND maintenance.deleted_at IS NULL
+            GROUP BY maintenance.garage_id, maintenance.service_type
+        ) AS revenue ON revenue.garage_id = garages.id
+        WHERE garages.city = ?1 COLLATE NOCASE
+          AND garages.deleted_at IS NULL
+        ORDER BY garages.id, revenue.revenue_cents DESC, revenue.service_type
+        "#,
+        city,
+        start,
+        end
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(err) => {
+            error!("Failed to generate city revenue report: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to generate city revenue report",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let mut report: Vec<GarageRevenueDTO> = Vec::new();
+    for row in rows {
+        let garage = match report.last_mut() {
+            Some(garage) if garage.garage_id == row.garage_id => garage,
+            _ => {
+                report.push(GarageRevenueDTO {
+                    garage_id: row.garage_id,
+                    garage_name: row.garage_name,
+                    invoices: 0,
+                    revenue_cents: 0,
+                    average_ticket_cents: 0,
+                    service_mix: Vec::new(),
+                });
+                report.last_mut().expect("garage was just pushed")
+            }
+        };
+        if let Some(service_type) = row.service_type {
+            garage.invoices += row.invoices;
+            garage.revenue_cents += row.revenue_cents;
+            garage.service_mix.push(ServiceMixDTO {
+                service_type,
+                invoices: row.invoices,
+                revenue_cents: row.revenue_cents,
+            });
+        }
+    }
+    for garage in &mut report {
+        garage.average_ticket_cents = average_ticket(garage.revenue_cents, garage.invoices);
+    }
+    report.sort_by(|a, b| b.revenue_cents.cmp(&a.revenue_cents).then(a.garage_id.cmp(&b.garage_id)));
+
+    HttpResponse::Ok().json(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+
+    fn query(start: &str, end: &str) -> HashMap<String, String> {
+        HashMap::from([
+            ("startMonth".to_string(), start.to_string()),
+            ("endMonth".to_string(), end.to_string()),
+        ])
+    }
+
+    fn rejected(result: Result<(YearMonth, YearMonth), HttpResponse>) -> bool {
+        matches!(result, Err(response) if response.status() == StatusCode::BAD_REQUEST)
+    }
+
+    #[test]
+    fn accepts_a_range_up_to_the_limit() {
+        let (start, end) = month_range(&query("2015-01", "2024-12"), 120).unwrap();
+        assert_eq!((start.to_string(), end.to_string()), ("2015-01".to_string(), "2024-12".to_string()));
+        assert!(month_range(&query("2025-03", "2025-03"), 1).is_ok());
+    }
+
+    #[test]
+    fn rejects_a_range_over_the_limit() {
+        assert!(rejected(month_range(&query("2014-12", "2024-12"), 120)));
+        assert!(rejected(month_range(&query("2025-01", "2025-02"), 1)));
+    }
+
+    #[test]
+    fn rejects_a_reversed_range() {
+        assert!(rejected(month_range(&query("2025-03", "2025-02"), 120)));
+    }
+
+    #[test]
+    fn rejects_missing_and_malformed_months() {
+        assert!(rejected(month_range(&HashMap::new(), 120)));
+        assert!(rejected(month_range(&query("", "2025-02"), 120)));
+        assert!(rejected(month_range(&query("2025-1", "2025-02"), 120)));
+        assert!(rejected(month_range(&query("2025-01", "garbage"), 120)));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::app_state::AppState;
+use crate::models::search::{
+    CarSearchHitDTO, GarageSearchHitDTO, MaintenanceSearchHitDTO, SearchQueryParams, SearchResultsDTO,
+};
+use actix_web::{web, HttpResponse, Responder};
+use log::{error, info};
+use serde_json::json;
+
+const DEFAULT_SEARCH_LIMIT: i64 = 10;
+const MAX_SEARCH_LIMIT: i64 = 50;
+/// The trigram index cannot match anything shorter.
+const MIN_TERM_CHARS: usize = 3;
+const SEARCH_TYPES: &[&str] = &["cars", "garages", "maintenance"];
+
+/// Turns free text into an FTS5 query: every word of at least three
+/// characters must appear somewhere in the row. Words are quoted so that
+/// FTS5 operators typed by the user are searched for literally.
+fn match_expression(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .filter(|term| term.chars().count() >= MIN_TERM_CHARS)
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+fn search_failed(err: sqlx::Error) -> HttpResponse {
+    error!("Failed to search: {:?}", err);
+    HttpResponse::InternalServerError().json(json!({
+        "error": "Failed to search",
+        "details": err.to_string()
+    }))
+}
+
+/// Searches cars by make, model and plate, garages by name and address, and
+/// bookings by service type and notes. Soft-deleted records are left out.
+pub async fn search(
+    query: web::Query<SearchQueryParams>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let text = query.q.as_deref().map(str::trim).unwrap_or_default().to_string();
+    let Some(expression) = match_expression(&text) else {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid q parameter",
+            "details": format!("q must contain a word of at least {} characters", MIN_TERM_CHARS)
+        }));
+    };
+
+    let types: Vec<String> = match query.types.as_deref() {
+        Some(types) => types
+            .split(',')
+            .map(|value| value.trim().to_ascii_lowercase())
+            .filter(|value| !value.is_empty())
+            .collect(),
+        None => SEARCH_TYPES.iter().map(|value| value.to_string()).collect(),
+    };
+    if let Some(unknown) = types.iter().find(|value| !SEARCH_TYPES.contains(&value.as_str())) {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid types parameter",
+            "details": format!("Unknown type '{}'; expected {}", unknown, SEARCH_TYPES.join(", "))
+        }));
+    }
+    let wants = |group: &str| types.iter().any(|value| value == group);
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT);
+
+    info!("Searching for '{}' in {}", text, types.join(", "));
+
+    let mut results = SearchResultsDTO {
+        query: text,
+        cars: None,
+        garages: None,
+        maintenance: None,
+    };
+
+    // bm25 is lower for better matches; the plate and the garage or service
+    // name weigh more than the other columns.
+    if wants("cars") {
+        match sqlx::query_as!(
+            CarSearchHitDTO,
+            r#"
+            SELECT
+                cars.id AS "id!",
+                cars.make,
+                cars.model,
+                cars.license_plate,
+                -bm25(cars_fts, 1.0, 1.0, 2.0, 2.0) AS "score!: f64"
+            FROM cars_fts
+            JOIN cars ON cars.id = cars_fts.rowid
+            WHERE cars_fts MATCH ?1
+              AND cars.deleted_at IS NULL
+            ORDER BY bm25(cars_fts, 1.0, 1.0, 2.0, 2.0), cars.id
+            LIMIT ?2
+            "#,
+            expression,
+            limit
+        )
+        .fetch_all(&data.pool)
+        .await
+        {
+            Ok(hits) => results.cars = Some(hits),
+            Err(err) => return search_failed(err),
+        }
+    }
+
+    if wants("garages") {
+        match sqlx::query_as!(
+            GarageSearchHitDTO,
+            r#"
+            SELECT
+                garages.id AS "id!",
+                garages.name,
+                garages.location,
+                garages.city,
+                -bm25(garages_fts, 2.0, 1.0, 1.0) AS "score!: f64"
+            FROM garages_fts
+            JOIN garages ON garages.id = garages_fts.rowid
+            WHERE garages_fts MATCH ?1
+              AND garages.deleted_at IS NULL
+            ORDER BY bm25(garages_fts, 2.0, 1.0, 1.0), garages.id
+            LIMIT ?2
+            "#,
+            expression,
+            limit
+        )
+        .fetch_all(&data.pool)
+        .await
+        {
+            Ok(hits) => results.garages = Some(hits),
+            Err(err) => return search_failed(err),
+        }
+    }
+
+    if wants("maintenance") {
+        match sqlx::query_as!(
+            MaintenanceSearchHitDTO,
+            r#"
+            SELECT
+                maintenance.id AS "id!",
+                maintenance.car_id AS "car_id!",
+                cars.make || ' ' || cars.model AS "car_name!: String",
+                maintenance.garage_id AS "garage_id!",
+                garages.name AS garage_name,
+                maintenance.service_type,
+                maintenance.scheduled_date,
+                maintenance.status,
+                maintenance.notes,
+                -bm25(maintenance_fts, 2.0, 1.0) AS "score!: f64"
+            FROM maintenance_fts
+            JOIN maintenance ON maintenance.id = maintenance_fts.rowid
+            JOIN cars ON maintenance.car_id = cars.id
+            JOIN garages ON maintenance.garage_id = garages.id
+            WHERE maintenance_fts MATCH ?1
+              AND maintenance.deleted_at IS NULL
+            ORDER BY bm25(maintenance_fts, 2.0, 1.0), maintenance.scheduled_date DESC
+            LIMIT ?2
+            "#,
+            expression,
+            limit
+        )
+        .fetch_all(&data.pool)
+        .await
+        {
+            Ok(hits) => results.maintenance = Some(hits),
+            Err(err) => return search_failed(err),
+        }
+    }
+
+    HttpResponse::Ok().json(results)
+}
@@ -0,0 +1,190 @@
+use crate::app_state::AppState;
+use crate::models::service_type::{CreateServiceTypeRequest, ServiceType, UpdateServiceTypeRequest};
+use actix_web::{web, HttpResponse, Responder};
+use log::{error, info};
+use serde_json::json;
+
+pub async fn get_all_service_types(data: web::Data<AppState>) -> impl Responder {
+    match sqlx::query_as!(
+        ServiceType,
+        r#"SELECT id AS "id!", name, duration_minutes FROM service_types ORDER BY name"#
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(service_types) => HttpResponse::Ok().json(service_types),
+        Err(err) => {
+            error!("Failed to fetch service types: {:?}", err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch service types",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
+
+pub async fn create_service_type(
+    service_type_req: web::Json<CreateServiceTypeRequest>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    info!("Received request to create service type: {:?}", service_type_req);
+
+    if service_type_req.duration_minutes <= 0 {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid durationMinutes",
+            "details": "durationMinutes must be positive"
+        }));
+    }
+
+    match sqlx::query!(
+        "INSERT INTO service_types (name, duration_minutes) VALUES (?, ?)",
+        service_type_req.name,
+        service_type_req.duration_minutes
+    )
+    .execute(&data.pool)
+    .await
+    {
+        Ok(result) => HttpResponse::Created().json(ServiceType {
+            id: result.last_insert_rowid(),
+            name: service_type_req.name.clone(),
+            duration_minutes: service_type_req.duration_minutes,
+        }),
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+            HttpResponse::Conflict().json(json!({
+                "error": "Service type already exists",
+                "details": err.to_string()
+            }))
+        }
+        Err(err) => {
+            error!("Failed to create service type: {:?}", err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to create service type",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
+
+pub async fn edit_service_type(
+    id: web::Path<i64>,
+    service_type_req: web::Json<UpdateServiceTypeRequest>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let service_type_id = id.into_inner();
+    info!("Received request to update service type {}: {:?}", service_type_id, service_type_req);
+
+    if service_type_req.duration_minutes.is_some_and(|duration| duration <= 0) {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid durationMinutes",
+            "details": "durationMinutes must be positive"
+        }));
+    }
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("Failed to start transaction: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to start transaction",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    let current_name = match sqlx::query_scalar!("SELECT name FROM service_types WHERE id = ?", service_type_id)
+        .fetch_optional(&mut *transaction)
+        .await
+    {
+        Ok(Some(name)) => name,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(json!({
+                "error": "Service type not found"
+            }));
+        }
+        Err(err) => {
+            error!("Failed to fetch service type {}: {:?}", service_type_id, err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update service type",
+                "details": err.to_string()
+            }));
+        }
+    };
+
+    // Bookings and plans refer to their service type by name, which is how
+    // slot lengths and reserved parts are found, so a name in use is fixed.
+    if service_type_req.name.as_deref().is_some_and(|name| name != current_name) {
+        match sqlx::query_scalar!(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM maintenance WHERE service_type = ?1)
+                + (SELECT COUNT(*) FROM maintenance_plans WHERE service_type = ?1) AS "uses!: i64"
+            "#,
+            current_name
+        )
+        .fetch_one(&mut *transaction)
+        .await
+        {
+            Ok(0) => {}
+            Ok(uses) => {
+                return HttpResponse::Conflict().json(json!({
+                    "error": "Service type in use",
+                    "details": format!(
+                        "'{}' cannot be renamed while bookings or maintenance plans use it ({} found)",
+                        current_name, uses
+                    )
+                }));
+            }
+            Err(err) => {
+                error!("Failed to check uses of service type {}: {:?}", service_type_id, err);
+                return HttpResponse::InternalServerError().json(json!({
+                    "error": "Failed to update service type",
+                    "details": err.to_string()
+                }));
+            }
+        }
+    }
+
+    let updated = sqlx::query!(
+        r#"
+        UPDATE service_types
+        SET name = COALESCE(?, name), duration_minutes = COALESCE(?, duration_minutes)
+        WHERE id = ?
+        "#,
+        service_type_req.name,
+        service_type_req.duration_minutes,
+        service_type_id
+    )
+    .execute(&mut *transaction)
+    .await;
+
+    match updated {
+        Ok(_) => {}
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+            return HttpResponse::Conflict().json(json!({
+                "error": "Service type already exists",
+                "details": err.to_string()
+            }));
+        }
+        Err(err) => {
+            error!("Failed to update service type: {:?}", err);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update service type",
+                "details": err.to_string()
+            }));
+        }
+    }
+
+    match transaction.commit().await {
+        Ok(_) => HttpResponse::Ok().json(json!({
+            "id": service_type_id,
+            "updated": true,
+        })),
+        Err(err) => {
+            error!("Failed to commit transaction: {:?}", err);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update service type",
+                "details": err.to_string()
+            }))
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use crate::app_state::AppState;
+use crate::events::EventType;
+use crate::models::webhook::{
+    CreateWebhookRequest, DeliveryQueryParams, UpdateWebhookRequest, WebhookDTO, WebhookDeliveryDTO,
+};
+use crate::webhooks;
+use actix_web::{web, HttpResponse, Responder};
+use log::{error, info};
+use serde_json::json;
+
+const DEFAULT_DELIVERY_LIMIT: i64 = 100;
+const MAX_DELIVERY_LIMIT: i64 = 1000;
+const DELIVERY_STATUSES: &[&str] = &["PENDING", "DELIVERED", "FAILED"];
+
+fn url_error(url: &str) -> Option<String> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => None,
+        Ok(_) => Some("url must use http or https".to_string()),
+        Err(err) => Some(format!("url is not valid: {}", err)),
+    }
+}
+
+fn join_events(events: &[EventType]) -> String {
+    let mut names: Vec<&str> = events.iter().map(EventType::as_str).collect();
+    names.sort_unstable();
+    names.dedup();
+    names.join(",")
+}
+
+fn split_events(events: &str) -> Vec<String> {
+    events
+        .split(',')
+        .filter(|event| !event.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn webhook_failed(message: &str, err: sqlx::Error) -> HttpResponse {
+    error!("{}: {:?}", message, err);
+    HttpResponse::InternalServerError().json(json!({
+        "error": message,
+        "details": err.to_string()
+    }))
+}
+
+fn webhook_not_found(id: i64) -> HttpResponse {
+    HttpResponse::NotFound().json(json!({
+        "error": "Webhook not found",
+        "details": format!("No webhook found with id {}", id)
+    }))
+}
+
+async fn fetch_webhook(pool: &sqlx::SqlitePool, id: i64) -> Result<Option<WebhookDTO>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT id, url, events, active AS "active: bool", created_at FROM webhooks WHERE id = ?"#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|row| WebhookDTO {
+        id: row.id,
+        url: row.url,
+        events: split_events(&row.events),
+        active: row.active,
+        created_at: row.created_at,
+        secret: None,
+    }))
+}
+
+pub async fn get_all_webhooks(data: web::Data<AppState>) -> impl Responder {
+    match sqlx::query!(
+        r#"SELECT id, url, events, active AS "active: bool", created_at FROM webhooks ORDER BY id"#
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(rows) => HttpResponse::Ok().json(
+            rows.into_iter()
+                .map(|row| WebhookDTO {
+                    id: row.id,
+                    url: row.url,
+                    events: split_events(&row.events),
+                    active: row.active,
+                    created_at: row.created_at,
+                    secret: None,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Err(err) => webhook_failed("Failed to fetch webhooks", err),
+    }
+}
+
+pub async fn get_webhook(
+    id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let id = id.into_inner();
+    match fetch_webhook(&data.pool, id).await {
+        Ok(Some(webhook)) => HttpResponse::Ok().json(webhook),
+        Ok(None) => webhook_not_found(id),
+        Err(err) => webhook_failed("Failed to fetch webhook", err),
+    }
+}
+
+/// The signing secret is only shown in this response; store it on the
+/// receiving side to verify `X-Webhook-Signature`.
+pub async fn create_webhook(
+    webhook_req: web::Json<CreateWebhookRequest>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let webhook_req = webhook_req.into_inner();
+    let url = webhook_req.url.trim().to_string();
+    if let Some(details) = url_error(&url) {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid webhook",
+            "details": details
+        }));
+    }
+
+    let secret = match webhook_req.secret.map(|secret| secret.trim().to_string()) {
+        Some(secret) if secret.is_empty() => {
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Invalid webhook",
+                "details": "secret must not be blank"
+            }));
+        }
+        Some(secret) => secret,
+        None => webhooks::generate_secret(),
+    };
+    let events = join_events(&webhook_req.events);
+
+    let id = match sqlx::query!(
+        "INSERT INTO webhooks (url, secret, events) VALUES (?, ?, ?)",
+        url,
+        secret,
+        events
+    )
+    .execute(&data.pool)
+    .await
+    {
+        Ok(result) => result.last_insert_rowid(),
+        Err(err) => return webhook_failed("Failed to create webhook", err),
+    };
+    info!("Registered webhook {} for {}", id, url);
+
+    match fetch_webhook(&data.pool, id).await {
+        Ok(Some(webhook)) => HttpResponse::Created().json(WebhookDTO {
+            secret: Some(secret),
+            ..webhook
+        }),
+        Ok(None) => webhook_not_found(id),
+        Err(err) => webhook_failed("Failed to create webhook", err),
+    }
+}
+
+pub async fn edit_webhook(
+    id: web::Path<i64>,
+    webhook_req: web::Json<UpdateWebhookRequest>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let id = id.into_inner();
+    let url = webhook_req.url.as_deref().map(str::trim);
+    if let Some(details) = url.and_then(url_error) {
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Invalid webhook",
+            "details": details
+        }));
+    }
+    let events = webhook_req.events.as_deref().map(join_events);
+
+    match sqlx::query!(
+        r#"
+        UPDATE webhooks
+        SET url = COALESCE(?, url),
+            events = COALESCE(?, events),
+            active = COALESCE(?, active)
+        WHERE id = ?
+        "#,
+        url,
+        events,
+        webhook_req.active,
+        id
+    )
+    .execute(&data.pool)
+    .await
+    {
+        Ok(result) if result.rows_affected() == 0 => return webhook_not_found(id),
+        Ok(_) => {}
+        Err(err) => return webhook_failed("Failed to update webhook", err),
+    }
+
+    match fetch_webhook(&data.pool, id).await {
+        Ok(Some(webhook)) => HttpResponse::Ok().json(webhook),
+        Ok(None) => webhook_not_found(id),
+        Err(err) => webhook_failed("Failed to update webhook", err),
+    }
+}
+
+/// Removes the webhook together with its delivery log.
+pub async fn delete_webhook(
+    id: web::Path<i64>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let id = id.into_inner();
+
+    let mut transaction = match data.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => return webhook_failed("Failed to delete webhook", err),
+    };
+
+    if let Err(err) = sqlx::query!("DELETE FROM webhook_deliveries WHERE webhook_id = ?", id)
+        .execute(&mut *transaction)
+        .await
+    {
+        return webhook_failed("Failed to delete webhook", err);
+    }
+
+    match sqlx::query!("DELETE FROM webhooks WHERE id = ?", id)
+        .execute(&mut *transaction)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => return webhook_not_found(id),
+        Ok(_) => {}
+        Err(err) => return webhook_failed("Failed to delete webhook", err),
+    }
+
+    match transaction.commit().await {
+        Ok(_) => HttpResponse::Ok().json(json!({
+            "id": id,
+            "deleted": true,
+        })),
+        Err(err) => webhook_failed("Failed to delete webhook", err),
+    }
+}
+
+pub async fn get_webhook_deliveries(
+    id: web::Path<i64>,
+    query: web::Query<DeliveryQueryParams>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let id = id.into_inner();
+
+    let status = query.status.as_deref().map(str::to_ascii_uppercase);
+    if let Some(status) = status.as_deref() {
+        if !DELIVERY_STATUSES.contains(&status) {
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Invalid status parameter",
+                "details": format!("status must be one of {}", DELIVERY_STATUSES.join(", "))
+            }));
+        }
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_DELIVERY_LIMIT).clamp(1, MAX_DELIVERY_LIMIT);
+
+    match fetch_webhook(&data.pool, id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return webhook_not_found(id),
+        Err(err) => return webhook_failed("Failed to fetch webhook deliveries", err),
+    }
+
+    match sqlx::query!(
+        r#"
+        SELECT
+            id AS "id!",
+            webhook_id,
+            event_id,
+            event_type,
+            payload,
+            status,
+            attempts,
+            next_attempt_at,
+            last_attempt_at,
+            response_status,
+            last_error,
+            created_at,
+            delivered_at
+        FROM webhook_deliveries
+        WHERE webhook_id = ?1
+          AND (?2 IS NULL OR status = ?2)
+        ORDER BY id DESC
+        LIMIT ?3
+        "#,
+        id,
+        status,
+        limit
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(rows) => HttpResponse::Ok().json(
+            rows.into_iter()
+                .map(|row| WebhookDeliveryDTO {
+                    id: row.id,
+                    webhook_id: row.webhook_id,
+                    event_id: row.event_id,
+                    event_type: row.event_type,
+                    payload: serde_json::from_str(&row.payload).unwrap_or_default(),
+                    status: row.status,
+                    attempts: row.attempts,
+                    next_attempt_at: row.next_attempt_at,
+                    last_attempt_at: row.last_attempt_at,
+                    response_status: row.response_status,
+                    last_error: row.last_error,
+                    created_at: row.created_at,
+                    delivered_at: row.delivered_at,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Err(err) => webhook_failed("Failed to fetch webhook deliveries", err),
+    }
+}
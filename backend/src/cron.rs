@@ -0,0 +1,313 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, TimeZone, Timelike};
+use std::str::FromStr;
+
+/// Far enough ahead to reach the next 29 February.
+const MAX_SEARCH_DAYS: usize = 4 * 366 + 1;
+
+const ALIASES: &[(&str, &str)] = &[
+    ("@hourly", "0 * * * *"),
+    ("@daily", "0 0 * * *"),
+    ("@midnight", "0 0 * * *"),
+    ("@weekly", "0 0 * * 0"),
+    ("@monthly", "0 0 1 * *"),
+    ("@yearly", "0 0 1 1 *"),
+    ("@annually", "0 0 1 1 *"),
+];
+
+/// A five-field cron expression: minute, hour, day of month, month and day
+/// of week. Fields take `*`, numbers, ranges `a-b`, steps `*/n` or `a-b/n`
+/// and comma-separated lists; Sunday is 0 or 7.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// As in classic cron, when both day fields are restricted a day
+    /// matching either of them fires.
+    any_day: bool,
+    any_weekday: bool,
+}
+
+fn field(spec: &str, name: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, Some(step)),
+                _ => return Err(format!("{} step '{}' must be a positive number", name, step)),
+            },
+            None => (part, None),
+        };
+        let number = |value: &str| {
+            value
+                .parse::<u32>()
+                .ok()
+                .filter(|value| (min..=max).contains(value))
+                .ok_or_else(|| format!("{} value '{}' must be between {} and {}", name, value, min, max))
+        };
+        let (low, high) = if range == "*" {
+            (min, max)
+        } else if let Some((low, high)) = range.split_once('-') {
+            (number(low)?, number(high)?)
+        } else {
+            let value = number(range)?;
+            (value, if step.is_some() { max } else { value })
+        };
+        if low > high {
+            return Err(format!("{} range '{}' is reversed", name, range));
+        }
+        for value in (low..=high).step_by(step.unwrap_or(1) as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn has(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+impl FromStr for Cron {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let expanded = ALIASES
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(value))
+            .map_or(value, |(_, expression)| *expression);
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(format!(
+                "'{}' has {} fields; expected minute, hour, day of month, month and day of week",
+                value,
+                fields.len()
+            ));
+        };
+
+        let mut weekday_mask = field(weekdays, "day of week", 0, 7)?;
+        if has(weekday_mask, 7) {
+            weekday_mask = (weekday_mask & !(1 << 7)) | 1;
+        }
+
+        Ok(Cron {
+            minutes: field(minutes, "minute", 0, 59)?,
+            hours: field(hours, "hour", 0, 23)?,
+            days: field(days, "day of month", 1, 31)?,
+            months: field(months, "month", 1, 12)?,
+            weekdays: weekday_mask,
+            any_day: days.starts_with('*'),
+            any_weekday: weekdays.starts_with('*'),
+        })
+    }
+}
+
+impl Cron {
+    fn day_matches(&self, date: chrono::NaiveDate) -> bool {
+        if !has(self.months, date.month()) {
+            return false;
+        }
+        let day = has(self.days, date.day());
+        let weekday = has(self.weekdays, date.weekday().num_days_from_sunday());
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+
+    /// The first matching minute strictly after `after`, or `None` if the
+    /// expression never fires (e.g. 31 February). Times are matched in
+    /// `after`'s zone; local times skipped by a daylight saving change are
+    /// skipped too, and repeated ones fire once.
+    pub fn next_after<Tz: TimeZone>(&self, after: DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let zone = after.timezone();
+        let after = after.naive_local();
+        let start: NaiveDateTime = after.date().and_hms_opt(after.hour(), after.minute(), 0)? + Duration::minutes(1);
+
+        let mut date = start.date();
+        for _ in 0..MAX_SEARCH_DAYS {
+            if self.day_matches(date) {
+                let first_day = date == start.date();
+                for hour in (0..24).filter(|hour| has(self.hours, *hour)) {
+                    if first_day && hour < start.hour() {
+                        continue;
+                    }
+                    for minute in (0..60).filter(|minute| has(self.minutes, *minute)) {
+                        if first_day && hour == start.hour() && minute < start.minute() {
+                            continue;
+                        }
+                        if let Some(at) = zone.from_local_datetime(&date.and_hms_opt(hour, minute, 0)?).earliest() {
+                            return Some(at);
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, LocalResult, NaiveDate, Utc};
+
+    /// Central European time in 2025: UTC+1, and UTC+2 from 30 March
+    /// 01:00 UTC to 26 October 01:00 UTC.
+    #[derive(Debug, Clone, Copy)]
+    struct Cet2025;
+
+    fn offset(hours: i32) -> FixedOffset {
+        FixedOffset::east_opt(hours * 3600).unwrap()
+    }
+
+    impl TimeZone for Cet2025 {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            Cet2025
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            // The summer offset comes first: it is the earlier instant.
+            let valid: Vec<FixedOffset> = [offset(2), offset(1)]
+                .into_iter()
+                .filter(|candidate| self.offset_from_utc_datetime(&(*local - *candidate)) == *candidate)
+                .collect();
+            match valid[..] {
+                [] => LocalResult::None,
+                [single] => LocalResult::Single(single),
+                [earlier, later] => LocalResult::Ambiguous(earlier, later),
+                _ => unreachable!(),
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            let summer = naive("2025-03-30 01:00")..naive("2025-10-26 01:00");
+            if summer.contains(utc) {
+                offset(2)
+            } else {
+                offset(1)
+            }
+        }
+    }
+
+    fn naive(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        Utc.from_utc_datetime(&naive(value))
+    }
+
+    fn next(expression: &str, after: &str) -> Option<String> {
+        let cron: Cron = expression.parse().unwrap();
+        cron.next_after(utc(after)).map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+    }
+
+    #[test]
+    fn parses_aliases() {
+        assert_eq!("@daily".parse::<Cron>(), "0 0 * * *".parse::<Cron>());
+        assert_eq!("@HOURLY".parse::<Cron>(), "0 * * * *".parse::<Cron>());
+    }
+
+    #[test]
+    fn treats_seven_as_sunday() {
+        assert_eq!("0 0 * * 7".parse::<Cron>(), "0 0 * * 0".parse::<Cron>());
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        let malformed = [
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "5-1 * * * *",
+            "*/0 * * * *",
+            "a * * * *",
+        ];
+        for expression in malformed {
+            assert!(expression.parse::<Cron>().is_err(), "'{}' was accepted", expression);
+        }
+    }
+
+    #[test]
+    fn fires_strictly_after_the_given_time() {
+        assert_eq!(next("*/15 * * * *", "2025-06-02 10:07").as_deref(), Some("2025-06-02 10:15"));
+        assert_eq!(next("*/15 * * * *", "2025-06-02 10:15").as_deref(), Some("2025-06-02 10:30"));
+        assert_eq!(next("0 3 * * *", "2025-06-02 03:00").as_deref(), Some("2025-06-03 03:00"));
+    }
+
+    #[test]
+    fn steps_from_the_start_of_a_range() {
+        assert_eq!(next("5/20 * * * *", "2025-06-02 10:06").as_deref(), Some("2025-06-02 10:25"));
+        assert_eq!(next("0 9-17/4 * * *", "2025-06-02 13:30").as_deref(), Some("2025-06-02 17:00"));
+    }
+
+    #[test]
+    fn rolls_over_months_and_years() {
+        assert_eq!(next("@monthly", "2025-01-31 12:00").as_deref(), Some("2025-02-01 00:00"));
+        assert_eq!(next("@yearly", "2025-12-31 23:59").as_deref(), Some("2026-01-01 00:00"));
+    }
+
+    #[test]
+    fn matches_either_day_field_when_both_are_restricted() {
+        // 13 July 2025 is a Sunday; the next Friday is the 18th.
+        assert_eq!(next("0 0 13 * 5", "2025-07-12 00:00").as_deref(), Some("2025-07-13 00:00"));
+        assert_eq!(next("0 0 13 * 5", "2025-07-13 00:00").as_deref(), Some("2025-07-18 00:00"));
+    }
+
+    #[test]
+    fn matches_both_day_fields_when_one_is_a_wildcard() {
+        assert_eq!(next("0 0 * * 5", "2025-07-12 00:00").as_deref(), Some("2025-07-18 00:00"));
+        assert_eq!(next("0 0 */2 * 1", "2025-07-01 00:00").as_deref(), Some("2025-07-07 00:00"));
+    }
+
+    #[test]
+    fn finds_the_next_29_february() {
+        assert_eq!(next("0 0 29 2 *", "2025-03-01 00:00").as_deref(), Some("2028-02-29 00:00"));
+        // The longest possible wait: one leap day to the next.
+        assert_eq!(next("0 0 29 2 *", "2024-02-29 00:00").as_deref(), Some("2028-02-29 00:00"));
+    }
+
+    #[test]
+    fn never_fires_on_a_day_that_does_not_exist() {
+        assert_eq!(next("0 0 31 2 *", "2025-01-01 00:00"), None);
+        assert_eq!(next("0 0 31 4,6,9,11 *", "2025-01-01 00:00"), None);
+    }
+
+    #[test]
+    fn skips_times_lost_to_daylight_saving() {
+        let cron: Cron = "30 2 * * *".parse().unwrap();
+        let after = Cet2025.from_local_datetime(&naive("2025-03-29 12:00")).unwrap();
+        let at = cron.next_after(after).unwrap();
+        assert_eq!(at.naive_local(), naive("2025-03-31 02:30"));
+    }
+
+    #[test]
+    fn fires_once_when_daylight_saving_repeats_an_hour() {
+        let cron: Cron = "30 2 * * *".parse().unwrap();
+        let after = Cet2025.from_local_datetime(&naive("2025-10-25 12:00")).unwrap();
+        let first = cron.next_after(after).unwrap();
+        assert_eq!(first.naive_local(), naive("2025-10-26 02:30"));
+        assert_eq!(first.offset(), &offset(2));
+
+        let second = cron.next_after(first).unwrap();
+        assert_eq!(second.naive_local(), naive("2025-10-27 02:30"));
+    }
+}
@@ -0,0 +1,297 @@
+use crate::audit::{AuditAction, AuditResource};
+use crate::{jobs, notifications};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use log::error;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::{broadcast, Mutex};
+
+/// Events kept in `event_log` for subscribers that reconnect.
+const EVENT_LOG_RETENTION_HOURS: i64 = 24;
+/// How often events written outside a request (background jobs) are relayed.
+const RELAY_EVERY: std::time::Duration = std::time::Duration::from_secs(1);
+const RELAY_BATCH_SIZE: i64 = 500;
+/// Live subscribers that fall further behind than this catch up from the log.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Garage fields that change how many bookings it can take.
+const CAPACITY_FIELDS: &[&str] = &["capacity", "openingTime", "closingTime", "slotMinutes"];
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventType {
+    #[serde(rename = "maintenance.created")]
+    MaintenanceCreated,
+    #[serde(rename = "maintenance.updated")]
+    MaintenanceUpdated,
+    #[serde(rename = "maintenance.deleted")]
+    MaintenanceDeleted,
+    #[serde(rename = "garage.capacity_changed")]
+    GarageCapacityChanged,
+}
+
+impl EventType {
+    pub const ALL: [EventType; 4] = [
+        EventType::MaintenanceCreated,
+        EventType::MaintenanceUpdated,
+        EventType::MaintenanceDeleted,
+        EventType::GarageCapacityChanged,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventType::MaintenanceCreated => "maintenance.created",
+            EventType::MaintenanceUpdated => "maintenance.updated",
+            EventType::MaintenanceDeleted => "maintenance.deleted",
+            EventType::GarageCapacityChanged => "garage.capacity_changed",
+        }
+    }
+}
+
+impl fmt::Display for EventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EventType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        EventType::ALL
+            .into_iter()
+            .find(|event| event.as_str() == value.trim())
+            .ok_or_else(|| format!("Unknown event type '{}'", value))
+    }
+}
+
+/// Something that happened to a booking or garage, as sent to subscribers.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub event_type: EventType,
+    pub garage_id: Option<i64>,
+    pub occurred_at: String,
+    pub data: Value,
+    pub previous: Option<Value>,
+}
+
+impl Event {
+    pub fn new(event_type: EventType, garage_id: Option<i64>, data: Value, previous: Option<Value>) -> Self {
+        Event {
+            id: uuid::Uuid::new_v4().to_string(),
+            event_type,
+            garage_id,
+            occurred_at: Utc::now().to_rfc3339(),
+            data,
+            previous,
+        }
+    }
+}
+
+fn garage_id_of(snapshot: &Value) -> Option<i64> {
+    match snapshot.get("garageId")? {
+        Value::Number(id) => id.as_i64(),
+        Value::String(id) => id.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Maps an audited change to the event subscribers care about, if any.
+pub fn from_change(
+    resource: AuditResource,
+    action: AuditAction,
+    before: Option<&Value>,
+    after: Option<&Value>,
+) -> Option<Event> {
+    match resource {
+        AuditResource::Maintenance => {
+            let event_type = match action {
+                AuditAction::Create => EventType::MaintenanceCreated,
+                AuditAction::Delete => EventType::MaintenanceDeleted,
+                AuditAction::Update | AuditAction::Restore | AuditAction::StatusChange => {
+                    EventType::MaintenanceUpdated
+                }
+            };
+            let data = after.or(before)?.clone();
+            Some(Event::new(event_type, garage_id_of(&data), data, before.cloned()))
+        }
+        AuditResource::Garage => {
+            let (before, after) = (before?, after?);
+            let changed = action == AuditAction::Update
+                && CAPACITY_FIELDS.iter().any(|field| before.get(field) != after.get(field));
+            changed.then(|| {
+                Event::new(
+                    EventType::GarageCapacityChanged,
+                    after.get("id").and_then(Value::as_i64),
+                    after.clone(),
+                    Some(before.clone()),
+                )
+            })
+        }
+        AuditResource::Car | AuditResource::Mechanic => None,
+    }
+}
+
+/// Capacity event for changes that are not audited, such as bays opening
+/// or closing.
+pub fn capacity_changed(garage_id: i64, data: Value) -> Event {
+    Event::new(EventType::GarageCapacityChanged, Some(garage_id), data, None)
+}
+
+/// Logs the event, queues it for every active webhook subscribed to its
+/// type and queues the owner notification it calls for. Runs inside the
+/// caller's transaction so a rolled back change sends nothing; live
+/// subscribers get it once `EventBus::relay` runs after commit.
+pub async fn publish(conn: &mut SqliteConnection, event: &Event) -> Result<(), sqlx::Error> {
+    let event_type = event.event_type.as_str();
+    let body = json!(event);
+    let payload = body.to_string();
+
+    sqlx::query!(
+        "INSERT INTO event_log (event_id, event_type, garage_id, payload) VALUES (?, ?, ?, ?)",
+        event.id,
+        event_type,
+        event.garage_id,
+        payload
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let deliveries = sqlx::query!(
+        r#"
+        INSERT INTO webhook_deliveries (webhook_id, event_id, event_type, payload)
+        SELECT id, ?1, ?2, ?3
+        FROM webhooks
+        WHERE active = 1
+          AND (events = '' OR instr(',' || events || ',', ',' || ?2 || ',') > 0)
+        "#,
+        event.id,
+        event_type,
+        payload
+    )
+    .execute(&mut *conn)
+    .await?;
+    if deliveries.rows_affected() > 0 {
+        jobs::enqueue_webhook_dispatch(conn).await?;
+    }
+
+    if let Some((maintenance_id, kind)) = notifications::kind_for_event(event_type, &body) {
+        jobs::enqueue_notification(conn, maintenance_id, kind).await?;
+    }
+
+    Ok(())
+}
+
+/// A committed event as relayed to live subscribers. `sequence` is its
+/// `event_log` id, usable as an SSE event id.
+#[derive(Debug, Clone)]
+pub struct LoggedEvent {
+    pub sequence: i64,
+    pub event_type: String,
+    pub garage_id: Option<i64>,
+    pub payload: String,
+}
+
+/// Committed events after `sequence`, oldest first, optionally for one garage.
+pub async fn logged_since(
+    pool: &SqlitePool,
+    sequence: i64,
+    garage_id: Option<i64>,
+    limit: i64,
+) -> Result<Vec<LoggedEvent>, sqlx::Error> {
+    sqlx::query_as!(
+        LoggedEvent,
+        r#"
+        SELECT id AS "sequence!", event_type, garage_id, payload
+        FROM event_log
+        WHERE id > ?1
+          AND (?2 IS NULL OR garage_id = ?2)
+        ORDER BY id
+        LIMIT ?3
+        "#,
+        sequence,
+        garage_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// In-process fan-out of committed events to live subscribers such as the
+/// SSE stream. Events are read back from `event_log`, so only committed
+/// changes are broadcast and each one exactly once.
+pub struct EventBus {
+    sender: broadcast::Sender<LoggedEvent>,
+    last_sequence: Mutex<i64>,
+}
+
+impl EventBus {
+    /// Starts after the newest logged event; older ones were sent by a
+    /// previous run.
+    pub async fn new(pool: &SqlitePool) -> Result<Self, sqlx::Error> {
+        let last_sequence = sqlx::query_scalar!(r#"SELECT COALESCE(MAX(id), 0) AS "id!: i64" FROM event_log"#)
+            .fetch_one(pool)
+            .await?;
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+        Ok(EventBus {
+            sender,
+            last_sequence: Mutex::new(last_sequence),
+        })
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LoggedEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Broadcasts events committed since the last relay. Controllers call
+    /// this after committing a change so subscribers hear about it at once.
+    pub async fn relay(&self, pool: &SqlitePool) {
+        let mut last_sequence = self.last_sequence.lock().await;
+        loop {
+            let batch = match logged_since(pool, *last_sequence, None, RELAY_BATCH_SIZE).await {
+                Ok(batch) => batch,
+                Err(err) => {
+                    error!("Failed to relay events: {:?}", err);
+                    return;
+                }
+            };
+            let complete = (batch.len() as i64) < RELAY_BATCH_SIZE;
+            for event in batch {
+                *last_sequence = event.sequence;
+                // Nobody listening is not an error.
+                let _ = self.sender.send(event);
+            }
+            if complete {
+                return;
+            }
+        }
+    }
+}
+
+/// Deletes events older than the retention window; subscribers that were
+/// away longer than that start from live events again.
+pub async fn prune(pool: &SqlitePool) -> Result<u64, sqlx::Error> {
+    let cutoff = format!("-{} hours", EVENT_LOG_RETENTION_HOURS);
+    let result = sqlx::query!("DELETE FROM event_log WHERE created_at < datetime('now', ?)", cutoff)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// Relays events written outside requests, such as by background jobs,
+/// for as long as the server runs.
+pub async fn run(bus: Arc<EventBus>, pool: SqlitePool) {
+    let mut ticker = tokio::time::interval(RELAY_EVERY);
+
+    loop {
+        ticker.tick().await;
+        bus.relay(&pool).await;
+    }
+}
@@ -0,0 +1,424 @@
+use crate::models::car::CarExportRow;
+use crate::models::garage::{Garage, GarageDailyAvailabilityReportDTO};
+use crate::models::maintenance::ResponseMaintenanceDTO;
+use crate::models::report::MonthlyRequestsReportDTO;
+use actix_web::http::header::{self, ContentDisposition, DispositionParam, DispositionType};
+use actix_web::web::Bytes;
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures_util::{Stream, StreamExt, TryStreamExt};
+use log::error;
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const CSV_MIME: &str = "text/csv";
+const XLSX_MIME: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+/// Rows are encoded and sent in batches of this size when streaming CSV.
+const CSV_BATCH_ROWS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Xlsx,
+}
+
+#[derive(Deserialize)]
+struct FormatQuery {
+    format: Option<String>,
+}
+
+/// Picks the response format: `?format=json|csv|xlsx` wins over the
+/// `Accept` header, and JSON is the default.
+pub fn negotiate(req: &HttpRequest) -> Result<ExportFormat, HttpResponse> {
+    let requested = web::Query::<FormatQuery>::from_query(req.query_string())
+        .ok()
+        .and_then(|query| query.into_inner().format);
+
+    if let Some(format) = requested {
+        return match format.trim().to_ascii_lowercase().as_str() {
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            "xlsx" => Ok(ExportFormat::Xlsx),
+            other => Err(HttpResponse::BadRequest().json(json!({
+                "error": "Invalid format parameter",
+                "details": format!("Unknown format '{}'; expected json, csv or xlsx", other)
+            }))),
+        };
+    }
+
+    let accept = req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    Ok(if accept.contains(CSV_MIME) {
+        ExportFormat::Csv
+    } else if accept.contains(XLSX_MIME) {
+        ExportFormat::Xlsx
+    } else {
+        ExportFormat::Json
+    })
+}
+
+pub enum Cell {
+    Text(String),
+    Number(f64),
+    Empty,
+}
+
+impl From<String> for Cell {
+    fn from(value: String) -> Self {
+        Cell::Text(value)
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(value: &str) -> Self {
+        Cell::Text(value.to_string())
+    }
+}
+
+impl From<i64> for Cell {
+    fn from(value: i64) -> Self {
+        Cell::Number(value as f64)
+    }
+}
+
+impl From<i32> for Cell {
+    fn from(value: i32) -> Self {
+        Cell::Number(f64::from(value))
+    }
+}
+
+impl<T: Into<Cell>> From<Option<T>> for Cell {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Cell::Empty, Into::into)
+    }
+}
+
+/// Characters a spreadsheet reads as the start of a formula.
+const FORMULA_PREFIXES: &[char] = &['=', '+', '-', '@', '\t', '\r'];
+
+impl Cell {
+    /// Text that would be run as a formula when the file is opened in a
+    /// spreadsheet is quoted with a leading `'`.
+    fn to_csv(&self) -> String {
+        match self {
+            Cell::Text(value) if value.starts_with(FORMULA_PREFIXES) => format!("'{}", value),
+            Cell::Text(value) => value.clone(),
+            Cell::Number(value) => value.to_string(),
+            Cell::Empty => String::new(),
+        }
+    }
+}
+
+/// A type that can be written as one spreadsheet row.
+pub trait Tabular {
+    const HEADERS: &'static [&'static str];
+
+    fn cells(&self) -> Vec<Cell>;
+}
+
+/// Sends `rows` in the negotiated format. CSV is streamed as rows arrive;
+/// JSON and XLSX need the whole table first.
+pub async fn respond<T, S>(format: ExportFormat, name: &str, rows: S) -> HttpResponse
+where
+    T: Tabular + Serialize + 'static,
+    S: Stream<Item = Result<T, sqlx::Error>> + 'static,
+{
+    match format {
+        ExportFormat::Csv => csv_response(name, rows),
+        ExportFormat::Json => match rows.try_collect::<Vec<T>>().await {
+            Ok(rows) => HttpResponse::Ok().json(rows),
+            Err(err) => export_failed(name, &err),
+        },
+        ExportFormat::Xlsx => match rows.try_collect::<Vec<T>>().await {
+            Ok(rows) => xlsx_response(name, &rows),
+            Err(err) => export_failed(name, &err),
+        },
+    }
+}
+
+/// Sends rows that are already in memory, e.g. a computed report.
+pub async fn respond_with<T>(format: ExportFormat, name: &str, rows: Vec<T>) -> HttpResponse
+where
+    T: Tabular + Serialize + 'static,
+{
+    respond(format, name, futures_util::stream::iter(rows.into_iter().map(Ok))).await
+}
+
+fn export_failed(name: &str, err: &dyn std::fmt::Display) -> HttpResponse {
+    error!("Failed to fetch {}: {}", name, err);
+    HttpResponse::InternalServerError().json(json!({
+        "error": format!("Failed to fetch {}", name),
+        "details": err.to_string()
+    }))
+}
+
+fn attachment(name: &str, extension: &str) -> ContentDisposition {
+    ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters: vec![DispositionParam::Filename(format!("{}.{}", name, extension))],
+    }
+}
+
+fn encode_csv(headers: Option<&[&str]>, rows: &[Vec<Cell>]) -> Result<Bytes, csv::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    if let Some(headers) = headers {
+        writer.write_record(headers)?;
+    }
+    for row in rows {
+        writer.write_record(row.iter().map(Cell::to_csv))?;
+    }
+    writer
+        .into_inner()
+        .map(Bytes::from)
+        .map_err(|err| csv::Error::from(err.into_error()))
+}
+
+fn csv_response<T, S>(name: &str, rows: S) -> HttpResponse
+where
+    T: Tabular + 'static,
+    S: Stream<Item = Result<T, sqlx::Error>> + 'static,
+{
+    let disposition = attachment(name, "csv");
+    let name = name.to_string();
+    let body = async_stream::stream! {
+        yield encode_csv(Some(T::HEADERS), &[]).map_err(actix_web::error::ErrorInternalServerError);
+
+        let mut rows = Box::pin(rows.chunks(CSV_BATCH_ROWS));
+        while let Some(batch) = rows.next().await {
+            let batch: Result<Vec<Vec<Cell>>, sqlx::Error> =
+                batch.into_iter().map(|row| row.map(|row| row.cells())).collect();
+            match batch {
+                Ok(batch) => {
+                    yield encode_csv(None, &batch).map_err(actix_web::error::ErrorInternalServerError);
+                }
+                Err(err) => {
+                    // Headers are already sent; aborting the body is the only
+                    // way to tell the client the file is incomplete.
+                    error!("Failed to stream {} export: {:?}", name, err);
+                    yield Err(actix_web::error::ErrorInternalServerError(err));
+                    break;
+                }
+            }
+        }
+    };
+
+    HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header(disposition)
+        .streaming(body)
+}
+
+fn xlsx_workbook<T: Tabular>(name: &str, rows: &[T]) -> Result<Vec<u8>, XlsxError> {
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+    let sheet = workbook.add_worksheet();
+    sheet.set_name(name)?;
+
+    for (column, header) in T::HEADERS.iter().enumerate() {
+        sheet.write_string_with_format(0, column as u16, *header, &bold)?;
+    }
+    for (index, row) in rows.iter().enumerate() {
+        let line = index as u32 + 1;
+        for (column, cell) in row.cells().into_iter().enumerate() {
+            match cell {
+                Cell::Text(value) => {
+                    sheet.write_string(line, column as u16, value)?;
+                }
+                Cell::Number(value) => {
+                    sheet.write_number(line, column as u16, value)?;
+                }
+                Cell::Empty => {}
+            }
+        }
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    sheet.autofit();
+
+    workbook.save_to_buffer()
+}
+
+fn xlsx_response<T: Tabular>(name: &str, rows: &[T]) -> HttpResponse {
+    match xlsx_workbook(name, rows) {
+        Ok(buffer) => HttpResponse::Ok()
+            .content_type(XLSX_MIME)
+            .insert_header(attachment(name, "xlsx"))
+            .body(buffer),
+        Err(err) => export_failed(name, &err),
+    }
+}
+
+impl Tabular for Garage {
+    const HEADERS: &'static [&'static str] = &[
+        "id", "name", "location", "city", "capacity", "openingTime", "closingTime", "slotMinutes", "deletedAt",
+    ];
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.id.into(),
+            self.name.as_str().into(),
+            self.location.as_str().into(),
+            self.city.as_str().into(),
+            self.capacity.into(),
+            self.opening_time.as_str().into(),
+            self.closing_time.as_str().into(),
+            self.slot_minutes.into(),
+            self.deleted_at.clone().into(),
+        ]
+    }
+}
+
+impl Tabular for CarExportRow {
+    const HEADERS: &'static [&'static str] =
+        &["id", "make", "model", "productionYear", "licensePlate", "vin", "garageIds", "deletedAt"];
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.id.into(),
+            self.make.as_str().into(),
+            self.model.as_str().into(),
+            self.production_year.into(),
+            self.license_plate.as_str().into(),
+            self.vin.clone().into(),
+            self.garage_ids.as_str().into(),
+            self.deleted_at.clone().into(),
+        ]
+    }
+}
+
+impl Tabular for ResponseMaintenanceDTO {
+    const HEADERS: &'static [&'static str] = &[
+        "id",
+        "carId",
+        "carName",
+        "garageId",
+        "garageName",
+        "serviceType",
+        "scheduledDate",
+        "startTime",
+        "durationMinutes",
+        "bayId",
+        "mechanicId",
+        "status",
+        "notes",
+        "quotedTotalCents",
+        "subtotalCents",
+        "taxCents",
+        "totalCents",
+        "deletedAt",
+    ];
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.id.into(),
+            self.car_id.as_str().into(),
+            self.car_name.as_str().into(),
+            self.garage_id.as_str().into(),
+            self.garage_name.as_str().into(),
+            self.service_type.as_str().into(),
+            self.scheduled_date.as_str().into(),
+            self.start_time.clone().into(),
+            self.duration_minutes.into(),
+            self.bay_id.into(),
+            self.mechanic_id.into(),
+            self.status.as_str().into(),
+            self.notes.clone().into(),
+            self.quoted_total_cents.into(),
+            self.subtotal_cents.into(),
+            self.tax_cents.into(),
+            self.total_cents.into(),
+            self.deleted_at.clone().into(),
+        ]
+    }
+}
+
+impl Tabular for GarageDailyAvailabilityReportDTO {
+    const HEADERS: &'static [&'static str] = &["date", "requests", "availableCapacity"];
+
+    fn cells(&self) -> Vec<Cell> {
+        vec![
+            self.date.as_str().into(),
+            self.requests.into(),
+            self.available_capacity.into(),
+        ]
+    }
+}
+
+impl Tabular for MonthlyRequestsReportDTO {
+    /// Status columns follow `MaintenanceStatus::ALL`, the order `by_status` is built in.
+    const HEADERS: &'static [&'static str] = &[
+        "yearMonth",
+        "requests",
+        "SCHEDULED",
+        "QUOTED",
+        "CONFIRMED",
+        "IN_PROGRESS",
+        "COMPLETED",
+        "CANCELLED",
+        "NO_SHOW",
+    ];
+
+    fn cells(&self) -> Vec<Cell> {
+        let mut cells = vec![self.year_month.to_string().into(), self.requests.into()];
+        cells.extend(self.by_status.iter().map(|count| count.requests.into()));
+        cells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn csv(cell: impl Into<Cell>) -> String {
+        cell.into().to_csv()
+    }
+
+    #[test]
+    fn quotes_text_that_starts_a_formula() {
+        assert_eq!(csv("=SUM(A1:A9)"), "'=SUM(A1:A9)");
+        assert_eq!(csv("+385 91 123 4567"), "'+385 91 123 4567");
+        assert_eq!(csv("-2+3"), "'-2+3");
+        assert_eq!(csv("@cmd"), "'@cmd");
+        assert_eq!(csv("\t=1+1"), "'\t=1+1");
+        assert_eq!(csv("\r=1+1"), "'\r=1+1");
+    }
+
+    #[test]
+    fn leaves_formula_characters_later_in_the_text() {
+        assert_eq!(csv("Oil change = 1h"), "Oil change = 1h");
+        assert_eq!(csv("a@b.c"), "a@b.c");
+        assert_eq!(csv(" =1"), " =1");
+    }
+
+    #[test]
+    fn leaves_plain_text_alone() {
+        assert_eq!(csv("AB-123-CD"), "AB-123-CD");
+        assert_eq!(csv(String::new()), "");
+    }
+
+    #[test]
+    fn writes_numbers_unquoted() {
+        assert_eq!(csv(-42_i64), "-42");
+        assert_eq!(csv(7_i32), "7");
+        assert_eq!(Cell::Number(1.5).to_csv(), "1.5");
+    }
+
+    #[test]
+    fn writes_empty_cells_as_nothing() {
+        assert_eq!(Cell::Empty.to_csv(), "");
+        assert_eq!(csv(None::<i64>), "");
+        assert_eq!(csv(Some("=1")), "'=1");
+    }
+
+    #[test]
+    fn encodes_escaped_cells_as_csv() {
+        let rows = vec![vec![Cell::from("=HYPERLINK(\"x\")"), Cell::from(3_i64), Cell::Empty]];
+        let bytes = encode_csv(Some(&["a", "b", "c"]), &rows).unwrap();
+        assert_eq!(std::str::from_utf8(&bytes).unwrap(), "a,b,c\n\"'=HYPERLINK(\"\"x\"\")\",3,\n");
+    }
+}
@@ -0,0 +1,220 @@
+use crate::models::import::ImportRowErrorDTO;
+use std::collections::HashMap;
+
+/// Header names are matched ignoring case, spaces, `_` and `-`, so
+/// `licensePlate`, `license_plate` and `License Plate` are the same column.
+pub fn column_key(header: &str) -> String {
+    header
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '_' | '-'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+pub struct CsvRow {
+    pub line: u64,
+    fields: HashMap<String, String>,
+}
+
+impl CsvRow {
+    /// The trimmed value of a column, or `None` when it is missing or blank.
+    pub fn get(&self, column: &str) -> Option<&str> {
+        self.fields
+            .get(&column_key(column))
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+pub fn row_error(line: u64, field: Option<&str>, message: impl Into<String>) -> ImportRowErrorDTO {
+    ImportRowErrorDTO {
+        line,
+        field: field.map(str::to_string),
+        message: message.into(),
+    }
+}
+
+/// Splits an upload into rows keyed by `column_key`. Fails as a whole when
+/// the header is unreadable or lacks a required column; rows that cannot be
+/// decoded are reported individually.
+pub fn read_rows(
+    body: &[u8],
+    required: &[&str],
+) -> Result<(Vec<CsvRow>, Vec<ImportRowErrorDTO>), String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(body);
+
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|err| format!("Could not read the CSV header: {}", err))?
+        .iter()
+        .map(column_key)
+        .collect();
+
+    let missing: Vec<&str> = required
+        .iter()
+        .copied()
+        .filter(|column| !headers.contains(&column_key(column)))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Missing required columns: {}", missing.join(", ")));
+    }
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for record in reader.records() {
+        match record {
+            Ok(record) => {
+                if record.iter().all(|value| value.is_empty()) {
+                    continue;
+                }
+                let line = record.position().map_or(0, |position| position.line());
+                let fields = headers
+                    .iter()
+                    .cloned()
+                    .zip(record.iter().map(str::to_string))
+                    .collect();
+                rows.push(CsvRow { line, fields });
+            }
+            Err(err) => {
+                let line = err.position().map_or(0, |position| position.line());
+                errors.push(row_error(line, None, format!("Unreadable row: {}", err)));
+            }
+        }
+    }
+
+    Ok((rows, errors))
+}
+
+/// Reads a required text column, recording an error when it is blank.
+pub fn required_text(row: &CsvRow, column: &str, errors: &mut Vec<ImportRowErrorDTO>) -> Option<String> {
+    let value = row.get(column).map(str::to_string);
+    if value.is_none() {
+        errors.push(row_error(row.line, Some(column), format!("{} is required", column)));
+    }
+    value
+}
+
+/// Reads an integer column; blank optional columns give `Ok(None)`.
+pub fn integer(
+    row: &CsvRow,
+    column: &str,
+    required: bool,
+    errors: &mut Vec<ImportRowErrorDTO>,
+) -> Option<i64> {
+    match row.get(column) {
+        Some(value) => match value.parse::<i64>() {
+            Ok(value) => Some(value),
+            Err(_) => {
+                errors.push(row_error(
+                    row.line,
+                    Some(column),
+                    format!("{} must be a whole number, got '{}'", column, value),
+                ));
+                None
+            }
+        },
+        None => {
+            if required {
+                errors.push(row_error(row.line, Some(column), format!("{} is required", column)));
+            }
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(line: u64, fields: &[(&str, &str)]) -> CsvRow {
+        CsvRow {
+            line,
+            fields: fields
+                .iter()
+                .map(|(column, value)| (column_key(column), value.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn matches_headers_ignoring_case_and_separators() {
+        for header in ["licensePlate", "license_plate", "License Plate", "LICENSE-PLATE", "licenseplate"] {
+            assert_eq!(column_key(header), "licenseplate", "{}", header);
+        }
+    }
+
+    #[test]
+    fn reads_rows_by_column_with_their_lines() {
+        let body = b"Make,License Plate,productionYear\nVW, AB-123-CD ,2015\nFiat,ZG-1,\n";
+        let (rows, errors) = read_rows(body, &["make", "licensePlate"]).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].line, 2);
+        assert_eq!(rows[0].get("license_plate"), Some("AB-123-CD"));
+        assert_eq!(rows[1].line, 3);
+        assert_eq!(rows[1].get("productionYear"), None);
+        assert_eq!(rows[1].get("vin"), None);
+    }
+
+    #[test]
+    fn fails_when_required_columns_are_missing() {
+        let err = read_rows(b"make,model\nVW,Golf\n", &["make", "licensePlate", "garageId"]).err();
+        assert_eq!(err.as_deref(), Some("Missing required columns: licensePlate, garageId"));
+    }
+
+    #[test]
+    fn skips_blank_rows_but_keeps_line_numbers() {
+        let body = b"make,model\nVW,Golf\n,\n  ,  \nFiat,Panda\n";
+        let (rows, errors) = read_rows(body, &["make"]).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(rows.iter().map(|row| row.line).collect::<Vec<_>>(), [2, 5]);
+    }
+
+    #[test]
+    fn reports_unreadable_rows_with_their_line() {
+        let body = b"make,model\nVW,Golf\nFiat,\xff\xfe\nOpel,Astra\n";
+        let (rows, errors) = read_rows(body, &["make"]).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 3);
+        assert_eq!(errors[0].field, None);
+        assert!(errors[0].message.starts_with("Unreadable row"), "{}", errors[0].message);
+    }
+
+    #[test]
+    fn reads_optional_and_required_integers() {
+        let row = row(4, &[("year", " 2015 "), ("km", "")]);
+        let mut errors = Vec::new();
+        assert_eq!(integer(&row, "year", true, &mut errors), Some(2015));
+        assert_eq!(integer(&row, "km", false, &mut errors), None);
+        assert!(errors.is_empty());
+
+        assert_eq!(integer(&row, "km", true, &mut errors), None);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 4);
+        assert_eq!(errors[0].field.as_deref(), Some("km"));
+        assert_eq!(errors[0].message, "km is required");
+    }
+
+    #[test]
+    fn rejects_integers_that_are_not_whole_numbers() {
+        for value in ["2015.5", "twenty", "1e3"] {
+            let mut errors = Vec::new();
+            assert_eq!(integer(&row(2, &[("year", value)]), "year", false, &mut errors), None);
+            assert_eq!(errors[0].message, format!("year must be a whole number, got '{}'", value));
+        }
+    }
+
+    #[test]
+    fn requires_text_columns() {
+        let row = row(5, &[("make", "VW"), ("model", " ")]);
+        let mut errors = Vec::new();
+        assert_eq!(required_text(&row, "make", &mut errors).as_deref(), Some("VW"));
+        assert_eq!(required_text(&row, "model", &mut errors), None);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "model is required");
+    }
+}
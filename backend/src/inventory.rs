@@ -0,0 +1,91 @@
+use sqlx::SqliteConnection;
+
+/// Brings the parts held for a booking in line with its current garage,
+/// service type and status: open bookings reserve what their service type
+/// consumes, deleted and closed ones hold nothing.
+pub async fn sync_reservations(
+    conn: &mut SqliteConnection,
+    maintenance_id: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM maintenance_part_reservations WHERE maintenance_id = ? AND consumed_at IS NULL",
+        maintenance_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO maintenance_part_reservations (maintenance_id, garage_id, part_id, quantity)
+        SELECT maintenance.id, CAST(maintenance.garage_id AS INTEGER), service_type_parts.part_id, service_type_parts.quantity
+        FROM maintenance
+        JOIN service_types ON service_types.name = maintenance.service_type
+        JOIN service_type_parts ON service_type_parts.service_type_id = service_types.id
+        WHERE maintenance.id = ?
+          AND maintenance.deleted_at IS NULL
+          AND maintenance.status NOT IN ('COMPLETED', 'CANCELLED', 'NO_SHOW')
+        "#,
+        maintenance_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Takes the reserved parts out of the garage's stock once the work is done.
+pub async fn consume(conn: &mut SqliteConnection, maintenance_id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO garage_parts (garage_id, part_id, on_hand)
+        SELECT garage_id, part_id, -SUM(quantity)
+        FROM maintenance_part_reservations
+        WHERE maintenance_id = ? AND consumed_at IS NULL
+        GROUP BY garage_id, part_id
+        ON CONFLICT (garage_id, part_id) DO UPDATE SET on_hand = on_hand + excluded.on_hand
+        "#,
+        maintenance_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE maintenance_part_reservations
+        SET consumed_at = CURRENT_TIMESTAMP
+        WHERE maintenance_id = ? AND consumed_at IS NULL
+        "#,
+        maintenance_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Re-reserves every open booking of a service type after its parts list
+/// changed.
+pub async fn sync_service_type(
+    conn: &mut SqliteConnection,
+    service_type_id: i64,
+) -> Result<(), sqlx::Error> {
+    let bookings = sqlx::query_scalar!(
+        r#"
+        SELECT maintenance.id AS "id!"
+        FROM maintenance
+        JOIN service_types ON service_types.name = maintenance.service_type
+        WHERE service_types.id = ?
+          AND maintenance.deleted_at IS NULL
+          AND maintenance.status NOT IN ('COMPLETED', 'CANCELLED', 'NO_SHOW')
+        "#,
+        service_type_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for maintenance_id in bookings {
+        sync_reservations(&mut *conn, maintenance_id).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,506 @@
+use crate::cron::Cron;
+use crate::notifications::{NotificationKind, Notifier};
+use crate::{scheduler, webhooks};
+use chrono::{Duration, Local, Utc};
+use log::{error, info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+pub const DEFAULT_WORKERS: usize = 2;
+pub const DEFAULT_MAX_ATTEMPTS: i64 = 5;
+pub const SQLITE_TIMESTAMP: &str = "%Y-%m-%d %H:%M:%S";
+
+/// How long an idle worker waits before looking for due jobs again.
+const POLL_EVERY: std::time::Duration = std::time::Duration::from_secs(1);
+/// How often schedules are checked; cron has minute resolution.
+const CLOCK_EVERY: std::time::Duration = std::time::Duration::from_secs(15);
+const FIRST_RETRY_SECONDS: i64 = 30;
+const MAX_RETRY_SECONDS: i64 = 60 * 60;
+/// Finished jobs are kept this long for inspection.
+const JOB_RETENTION_DAYS: i64 = 14;
+/// Enqueued by every change that queues webhook deliveries.
+const WEBHOOK_DISPATCH_KEY: &str = "webhooks.dispatch";
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    #[serde(rename = "plans.materialize")]
+    MaterializePlans,
+    #[serde(rename = "notifications.send")]
+    SendNotification,
+    #[serde(rename = "notifications.reminders")]
+    SendReminders,
+    #[serde(rename = "webhooks.dispatch")]
+    DispatchWebhooks,
+    #[serde(rename = "events.prune")]
+    PruneEventLog,
+    #[serde(rename = "jobs.prune")]
+    PruneJobs,
+}
+
+impl JobKind {
+    pub const ALL: [JobKind; 6] = [
+        JobKind::MaterializePlans,
+        JobKind::SendNotification,
+        JobKind::SendReminders,
+        JobKind::DispatchWebhooks,
+        JobKind::PruneEventLog,
+        JobKind::PruneJobs,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::MaterializePlans => "plans.materialize",
+            JobKind::SendNotification => "notifications.send",
+            JobKind::SendReminders => "notifications.reminders",
+            JobKind::DispatchWebhooks => "webhooks.dispatch",
+            JobKind::PruneEventLog => "events.prune",
+            JobKind::PruneJobs => "jobs.prune",
+        }
+    }
+}
+
+impl fmt::Display for JobKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for JobKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        JobKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == value.trim())
+            .ok_or_else(|| format!("Unknown job kind '{}'", value))
+    }
+}
+
+/// A recurring job registered at startup. Its cron expression and whether
+/// it is active can be changed through the admin API and survive restarts.
+struct DefaultSchedule {
+    name: &'static str,
+    kind: JobKind,
+    cron: &'static str,
+}
+
+const DEFAULT_SCHEDULES: &[DefaultSchedule] = &[
+    DefaultSchedule { name: "materialize-plans", kind: JobKind::MaterializePlans, cron: "0 3 * * *" },
+    DefaultSchedule { name: "booking-reminders", kind: JobKind::SendReminders, cron: "*/15 * * * *" },
+    // Picks up webhook deliveries waiting for a retry.
+    DefaultSchedule { name: "webhook-retries", kind: JobKind::DispatchWebhooks, cron: "* * * * *" },
+    DefaultSchedule { name: "prune-event-log", kind: JobKind::PruneEventLog, cron: "0 * * * *" },
+    DefaultSchedule { name: "prune-jobs", kind: JobKind::PruneJobs, cron: "30 4 * * *" },
+];
+
+/// Wait before retry number `attempts`: 30s doubling up to `max_seconds`.
+/// Jobs cap it at an hour; webhook deliveries pass their own cap.
+pub fn retry_delay(attempts: i64, max_seconds: i64) -> Duration {
+    let exponent = attempts.clamp(1, 20) - 1;
+    Duration::seconds((FIRST_RETRY_SECONDS << exponent).min(max_seconds))
+}
+
+/// When a schedule fires next, as an SQLite UTC timestamp.
+pub fn next_run_at(cron: &Cron) -> Option<String> {
+    cron.next_after(Local::now())
+        .map(|at| at.with_timezone(&Utc).format(SQLITE_TIMESTAMP).to_string())
+}
+
+/// Queues a job to run as soon as a worker is free. Runs inside the
+/// caller's transaction when given one. With a `unique_key`, nothing is
+/// queued while a job with the same key is still pending; returns the new
+/// job's id otherwise.
+pub async fn enqueue(
+    conn: &mut SqliteConnection,
+    kind: JobKind,
+    payload: Value,
+    unique_key: Option<&str>,
+) -> Result<Option<i64>, sqlx::Error> {
+    let kind = kind.as_str();
+    let payload = payload.to_string();
+    let result = sqlx::query!(
+        "INSERT OR IGNORE INTO jobs (kind, payload, unique_key, max_attempts) VALUES (?, ?, ?, ?)",
+        kind,
+        payload,
+        unique_key,
+        DEFAULT_MAX_ATTEMPTS
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok((result.rows_affected() > 0).then(|| result.last_insert_rowid()))
+}
+
+/// Wakes the webhook dispatcher for deliveries queued in this transaction.
+pub async fn enqueue_webhook_dispatch(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    enqueue(conn, JobKind::DispatchWebhooks, json!({}), Some(WEBHOOK_DISPATCH_KEY)).await?;
+    Ok(())
+}
+
+pub async fn enqueue_notification(
+    conn: &mut SqliteConnection,
+    maintenance_id: i64,
+    kind: NotificationKind,
+) -> Result<(), sqlx::Error> {
+    let payload = json!({ "maintenanceId": maintenance_id, "kind": kind });
+    enqueue(conn, JobKind::SendNotification, payload, None).await?;
+    Ok(())
+}
+
+/// Registers the built-in schedules. Existing ones keep their cron
+/// expression and active flag; kind and payload follow the code.
+async fn register_schedules(pool: &SqlitePool, horizon_days: i64) -> Result<(), sqlx::Error> {
+    for schedule in DEFAULT_SCHEDULES {
+        let cron = schedule.cron.parse::<Cron>().expect("built-in schedules are valid");
+        let next_run_at = next_run_at(&cron).expect("built-in schedules fire");
+        let kind = schedule.kind.as_str();
+        let payload = match schedule.kind {
+            JobKind::MaterializePlans => json!({ "horizonDays": horizon_days }),
+            _ => json!({}),
+        }
+        .to_string();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO job_schedules (name, kind, payload, cron, next_run_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT (name) DO UPDATE SET kind = excluded.kind, payload = excluded.payload
+            "#,
+            schedule.name,
+            kind,
+            payload,
+            schedule.cron,
+            next_run_at
+        )
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Queues a run of the named schedule unless one is already pending, and
+/// records when it fired. Returns the job id, if one was queued.
+pub async fn fire_schedule(conn: &mut SqliteConnection, name: &str) -> Result<Option<i64>, sqlx::Error> {
+    let Some(schedule) = sqlx::query!("SELECT kind, payload FROM job_schedules WHERE name = ?", name)
+        .fetch_optional(&mut *conn)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let unique_key = format!("schedule:{}", name);
+    let result = sqlx::query!(
+        r#"
+        INSERT OR IGNORE INTO jobs (kind, payload, unique_key, schedule, max_attempts)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+        schedule.kind,
+        schedule.payload,
+        unique_key,
+        name,
+        DEFAULT_MAX_ATTEMPTS
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!("UPDATE job_schedules SET last_run_at = datetime('now') WHERE name = ?", name)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok((result.rows_affected() > 0).then(|| result.last_insert_rowid()))
+}
+
+/// Queues every active schedule that is due and moves it to its next run.
+async fn fire_due_schedules(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let due = sqlx::query!(
+        r#"
+        SELECT name AS "name!", cron FROM job_schedules
+        WHERE active = 1 AND next_run_at <= datetime('now')
+        ORDER BY next_run_at
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for schedule in due {
+        let next = match schedule.cron.parse::<Cron>() {
+            Ok(cron) => next_run_at(&cron),
+            Err(err) => {
+                error!("Schedule '{}' has an invalid cron expression: {}", schedule.name, err);
+                None
+            }
+        };
+
+        let mut tx = pool.begin().await?;
+        fire_schedule(&mut tx, &schedule.name).await?;
+        match next {
+            Some(next) => {
+                sqlx::query!("UPDATE job_schedules SET next_run_at = ? WHERE name = ?", next, schedule.name)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            None => {
+                warn!("Schedule '{}' never fires again; deactivating it", schedule.name);
+                sqlx::query!("UPDATE job_schedules SET active = 0 WHERE name = ?", schedule.name)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+        tx.commit().await?;
+    }
+    Ok(())
+}
+
+/// Everything a job may need besides its payload.
+pub struct JobContext {
+    pub pool: SqlitePool,
+    pub notifier: Arc<Notifier>,
+    pub http: reqwest::Client,
+}
+
+struct ClaimedJob {
+    id: i64,
+    kind: String,
+    payload: String,
+    attempts: i64,
+    max_attempts: i64,
+}
+
+/// Marks the oldest due job as running and hands it to the caller. SQLite
+/// runs one writer at a time, so two workers never claim the same job.
+async fn claim(pool: &SqlitePool) -> Result<Option<ClaimedJob>, sqlx::Error> {
+    sqlx::query_as!(
+        ClaimedJob,
+        r#"
+        UPDATE jobs
+        SET status = 'RUNNING', attempts = attempts + 1, started_at = datetime('now'), finished_at = NULL
+        WHERE id = (
+            SELECT id FROM jobs
+            WHERE status = 'PENDING' AND run_at <= datetime('now')
+            ORDER BY run_at, id
+            LIMIT 1
+        )
+        RETURNING id AS "id!", kind, payload, attempts, max_attempts
+        "#
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MaterializePayload {
+    horizon_days: Option<i64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NotificationPayload {
+    maintenance_id: i64,
+    kind: NotificationKind,
+}
+
+fn payload<T: for<'de> Deserialize<'de>>(payload: &str) -> Result<T, String> {
+    serde_json::from_str(payload).map_err(|err| format!("Invalid job payload: {}", err))
+}
+
+/// Runs one job, returning a short summary for the job record.
+async fn perform(context: &JobContext, kind: JobKind, raw_payload: &str) -> Result<Value, String> {
+    let pool = &context.pool;
+    match kind {
+        JobKind::MaterializePlans => {
+            let request: MaterializePayload = payload(raw_payload)?;
+            let horizon_days = request.horizon_days.unwrap_or(scheduler::DEFAULT_HORIZON_DAYS);
+            let report = scheduler::materialize_plans(pool, Local::now().date_naive(), horizon_days)
+                .await
+                .map_err(|err| err.to_string())?;
+            Ok(json!({ "created": report.created.len(), "skipped": report.skipped.len() }))
+        }
+        JobKind::SendNotification => {
+            let request: NotificationPayload = payload(raw_payload)?;
+            let sent = context
+                .notifier
+                .notify(pool, request.maintenance_id, request.kind)
+                .await
+                .map_err(|err| err.to_string())?;
+            Ok(json!({ "sent": sent }))
+        }
+        JobKind::SendReminders => {
+            let sent = context
+                .notifier
+                .send_reminders(pool, Local::now().date_naive())
+                .await
+                .map_err(|err| err.to_string())?;
+            Ok(json!({ "sent": sent }))
+        }
+        JobKind::DispatchWebhooks => {
+            let mut attempted = 0;
+            loop {
+                let batch = webhooks::dispatch_due(pool, &context.http)
+                    .await
+                    .map_err(|err| err.to_string())?;
+                attempted += batch;
+                if batch < webhooks::BATCH_SIZE as usize {
+                    break;
+                }
+            }
+            Ok(json!({ "attempted": attempted }))
+        }
+        JobKind::PruneEventLog => {
+            let pruned = crate::events::prune(pool).await.map_err(|err| err.to_string())?;
+            Ok(json!({ "pruned": pruned }))
+        }
+        JobKind::PruneJobs => {
+            let cutoff = format!("-{} days", JOB_RETENTION_DAYS);
+            let pruned = sqlx::query!(
+                "DELETE FROM jobs WHERE status IN ('SUCCEEDED', 'FAILED') AND finished_at < datetime('now', ?)",
+                cutoff
+            )
+            .execute(pool)
+            .await
+            .map_err(|err| err.to_string())?
+            .rows_affected();
+            Ok(json!({ "pruned": pruned }))
+        }
+    }
+}
+
+/// Records how a claimed job went: done, due for a retry, or failed for
+/// good once its attempts are used up.
+async fn finish(pool: &SqlitePool, job: &ClaimedJob, outcome: Result<Value, String>) -> Result<(), sqlx::Error> {
+    match outcome {
+        Ok(result) => {
+            let result = result.to_string();
+            sqlx::query!(
+                r#"
+                UPDATE jobs
+                SET status = 'SUCCEEDED', result = ?, last_error = NULL, finished_at = datetime('now')
+                WHERE id = ?
+                "#,
+                result,
+                job.id
+            )
+            .execute(pool)
+            .await?;
+        }
+        Err(message) if job.attempts >= job.max_attempts => {
+            warn!("Job {} ({}) failed for good after {} attempts: {}", job.id, job.kind, job.attempts, message);
+            sqlx::query!(
+                "UPDATE jobs SET status = 'FAILED', last_error = ?, finished_at = datetime('now') WHERE id = ?",
+                message,
+                job.id
+            )
+            .execute(pool)
+            .await?;
+        }
+        Err(message) => {
+            warn!("Job {} ({}) failed on attempt {}: {}", job.id, job.kind, job.attempts, message);
+            let run_at = (Utc::now() + retry_delay(job.attempts, MAX_RETRY_SECONDS)).format(SQLITE_TIMESTAMP).to_string();
+            // A newer pending job with the same key already covers the
+            // retry, so this one is dropped instead.
+            sqlx::query!(
+                r#"
+                UPDATE OR IGNORE jobs
+                SET status = 'PENDING', last_error = ?1, run_at = ?2
+                WHERE id = ?3
+                "#,
+                message,
+                run_at,
+                job.id
+            )
+            .execute(pool)
+            .await?;
+            sqlx::query!(
+                r#"
+                UPDATE jobs
+                SET status = 'FAILED', last_error = ?1, finished_at = datetime('now')
+                WHERE id = ?2 AND status = 'RUNNING'
+                "#,
+                message,
+                job.id
+            )
+            .execute(pool)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Runs due jobs one after another for as long as the server runs.
+async fn work(context: Arc<JobContext>, worker: usize) {
+    loop {
+        let job = match claim(&context.pool).await {
+            Ok(Some(job)) => job,
+            Ok(None) => {
+                tokio::time::sleep(POLL_EVERY).await;
+                continue;
+            }
+            Err(err) => {
+                error!("Job worker {} could not claim a job: {:?}", worker, err);
+                tokio::time::sleep(POLL_EVERY).await;
+                continue;
+            }
+        };
+
+        let outcome = match job.kind.parse::<JobKind>() {
+            Ok(kind) => perform(&context, kind, &job.payload).await,
+            Err(err) => Err(err),
+        };
+        if outcome.is_ok() {
+            info!("Job {} ({}) succeeded", job.id, job.kind);
+        }
+        if let Err(err) = finish(&context.pool, &job, outcome).await {
+            error!("Failed to record the outcome of job {}: {:?}", job.id, err);
+        }
+    }
+}
+
+/// Puts jobs a previous process was running back in the queue. Those with
+/// an equivalent job already pending are closed instead.
+async fn requeue_interrupted(pool: &SqlitePool) -> Result<u64, sqlx::Error> {
+    let requeued = sqlx::query!("UPDATE OR IGNORE jobs SET status = 'PENDING' WHERE status = 'RUNNING'")
+        .execute(pool)
+        .await?
+        .rows_affected();
+    sqlx::query!(
+        r#"
+        UPDATE jobs
+        SET status = 'FAILED', last_error = 'Interrupted by a restart', finished_at = datetime('now')
+        WHERE status = 'RUNNING'
+        "#
+    )
+    .execute(pool)
+    .await?;
+    Ok(requeued)
+}
+
+/// Starts `workers` job workers and the clock that queues scheduled jobs.
+/// Jobs left running by a previous process are queued again first.
+pub async fn run(context: Arc<JobContext>, workers: usize, horizon_days: i64) {
+    let pool = &context.pool;
+    match requeue_interrupted(pool).await {
+        Ok(0) => {}
+        Ok(requeued) => warn!("Requeued {} jobs interrupted by a restart", requeued),
+        Err(err) => error!("Failed to requeue interrupted jobs: {:?}", err),
+    }
+    if let Err(err) = register_schedules(pool, horizon_days).await {
+        error!("Failed to register job schedules: {:?}", err);
+    }
+
+    for worker in 1..=workers.max(1) {
+        actix_web::rt::spawn(work(context.clone(), worker));
+    }
+    info!("Started {} job workers", workers.max(1));
+
+    let mut clock = tokio::time::interval(CLOCK_EVERY);
+    loop {
+        clock.tick().await;
+        if let Err(err) = fire_due_schedules(pool).await {
+            error!("Failed to queue scheduled jobs: {:?}", err);
+        }
+    }
+}
@@ -1,19 +1,68 @@
 mod controllers;
 mod models;
 mod app_state;
+mod audit;
+mod billing;
+mod calendar;
+mod cron;
+mod events;
+mod export;
+mod import;
+mod inventory;
+mod jobs;
+mod mechanics;
+mod notifications;
+mod odometer;
+mod plates;
+mod scheduler;
+mod slots;
+mod vin;
+mod webhooks;
 
 use actix_web::{web, App, HttpServer};
 use actix_cors::Cors;
 use app_state::AppState;
+use events::EventBus;
+use jobs::JobContext;
+use notifications::Notifier;
 use controllers::{
-    car_controller::{create_car, get_all_cars, delete_car, edit_car},
-    garage_controller::{create_garage, get_all_garages, edit_garage, delete_garage, get_single_garage, get_garage_report},
-    maintenance_controller::{create_maintenance, get_all_maintenances, get_maintenance_by_id,  delete_maintenance, edit_maintenance, monthly_requests_report},
+    audit_controller::get_audit_log,
+    bay_controller::{get_garage_bays, create_bay, delete_bay, get_free_slots},
+    billing_controller::{get_line_items, add_line_item, delete_line_item, get_invoice},
+    calendar_controller::{get_garage_calendar, get_car_calendar},
+    car_controller::{create_car, decode_vin, get_all_cars, get_car_by_plate, delete_car, edit_car, restore_car},
+    event_controller::get_event_stream,
+    garage_controller::{create_garage, get_all_garages, edit_garage, delete_garage, get_single_garage, get_garage_report, restore_garage, get_garage_schedule, reschedule_garage_day},
+    import_controller::{import_cars, import_garages},
+    job_controller::{get_all_jobs, get_job, retry_job, get_job_schedules, update_job_schedule, run_job_schedule},
+    maintenance_controller::{
+        create_maintenance, get_all_maintenances, get_maintenance_by_id,  delete_maintenance, edit_maintenance,
+        quote_maintenance, confirm_maintenance, start_maintenance, complete_maintenance, cancel_maintenance, mark_maintenance_no_show, get_maintenance_history,
+        restore_maintenance, get_car_maintenance_history,
+    },
+    maintenance_plan_controller::{get_car_plans, create_car_plan, update_plan, delete_plan, materialize_plans},
+    mechanic_controller::{get_garage_mechanics, create_mechanic, delete_mechanic, assign_mechanic, get_mechanic_workload_report},
+    notification_controller::get_maintenance_notifications,
+    odometer_controller::{get_car_odometer, add_odometer_reading, get_due_for_service},
+    part_controller::{
+        get_all_parts, create_part, edit_part, get_garage_stock, update_garage_stock, get_service_type_parts, set_service_type_parts,
+        low_stock_report,
+    },
+    report_controller::{monthly_requests_report, monthly_revenue_report, city_revenue_report},
+    search_controller::search,
+    service_type_controller::{get_all_service_types, create_service_type, edit_service_type},
+    webhook_controller::{get_all_webhooks, get_webhook, create_webhook, edit_webhook, delete_webhook, get_webhook_deliveries},
 };
 use sqlx::SqlitePool;
 use env_logger::Env;
 use dotenv::dotenv;
 use std::env;
+use std::sync::Arc;
+
+/// Default longest range of the per-day reports; a year plus a leap day.
+const DEFAULT_REPORT_MAX_DAYS: i64 = 366;
+/// Default longest range of the per-month reports; ten years.
+const DEFAULT_REPORT_MAX_MONTHS: i64 = 120;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -31,8 +80,84 @@ async fn main() -> std::io::Result<()> {
         .expect("Failed to create pool.");
 
     sqlx::migrate!().run(&pool).await.expect("Failed to run migrations");
+    plates::rekey_cars(&pool).await.expect("Failed to re-key license plates");
+
+    let horizon_days = match env::var("SCHEDULER_HORIZON_DAYS") {
+        Ok(value) => value
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .filter(|days| (0..=scheduler::MAX_HORIZON_DAYS).contains(days))
+            .unwrap_or_else(|| {
+                panic!(
+                    "SCHEDULER_HORIZON_DAYS '{}' is not a number of days between 0 and {}",
+                    value,
+                    scheduler::MAX_HORIZON_DAYS
+                )
+            }),
+        Err(_) => scheduler::DEFAULT_HORIZON_DAYS,
+    };
+
+    // A rate that is set but unreadable would bill every invoice without tax.
+    let tax_rate_bps = match env::var("TAX_RATE_PERCENT") {
+        Ok(value) => billing::parse_tax_rate(&value)
+            .unwrap_or_else(|| panic!("TAX_RATE_PERCENT '{}' is not a valid percentage", value)),
+        Err(_) => 0,
+    };
+
+    let report_max_days = match env::var("REPORT_MAX_RANGE_DAYS") {
+        Ok(value) => value
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .filter(|days| *days > 0)
+            .unwrap_or_else(|| panic!("REPORT_MAX_RANGE_DAYS '{}' is not a positive number of days", value)),
+        Err(_) => DEFAULT_REPORT_MAX_DAYS,
+    };
+
+    let report_max_months = match env::var("REPORT_MAX_RANGE_MONTHS") {
+        Ok(value) => value
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .filter(|months| *months > 0)
+            .unwrap_or_else(|| panic!("REPORT_MAX_RANGE_MONTHS '{}' is not a positive number of months", value)),
+        Err(_) => DEFAULT_REPORT_MAX_MONTHS,
+    };
+
+    let plate_country = env::var("PLATE_COUNTRY").ok().map(|value| {
+        value
+            .parse::<plates::PlateCountry>()
+            .unwrap_or_else(|details| panic!("PLATE_COUNTRY: {}", details))
+    });
+
+    let events = Arc::new(EventBus::new(&pool).await.expect("Failed to read the event log"));
+    actix_web::rt::spawn(events::run(events.clone(), pool.clone()));
+
+    let job_workers = match env::var("JOB_WORKERS") {
+        Ok(value) => value
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|workers| *workers > 0)
+            .unwrap_or_else(|| panic!("JOB_WORKERS '{}' is not a positive number of workers", value)),
+        Err(_) => jobs::DEFAULT_WORKERS,
+    };
+    let job_context = Arc::new(JobContext {
+        pool: pool.clone(),
+        notifier: Arc::new(Notifier::from_env()),
+        http: webhooks::client().expect("Failed to create the webhook HTTP client"),
+    });
+    actix_web::rt::spawn(jobs::run(job_context, job_workers, horizon_days));
 
-    let app_data = web::Data::new(AppState { pool });
+    let app_data = web::Data::new(AppState {
+        pool,
+        tax_rate_bps,
+        report_max_days,
+        report_max_months,
+        events,
+        plate_country,
+    });
 
     HttpServer::new(move || {
         App::new()
@@ -41,25 +166,97 @@ async fn main() -> std::io::Result<()> {
                 Cors::default()
                     .allowed_origin("http://localhost:3000")
                     .allowed_methods(vec!["GET", "POST", "PUT", "DELETE"])
-                    .allowed_headers(vec![actix_web::http::header::CONTENT_TYPE])
+                    .allowed_headers(vec![
+                        actix_web::http::header::CONTENT_TYPE,
+                        actix_web::http::header::HeaderName::from_static("x-actor"),
+                    ])
                     .max_age(3600),
             )
+            .route("/audit", web::get().to(get_audit_log))
+            .route("/events", web::get().to(get_event_stream))
+            .route("/search", web::get().to(search))
+            .route("/jobs", web::get().to(get_all_jobs))
+            .route("/jobs/schedules", web::get().to(get_job_schedules))
+            .route("/jobs/schedules/{name}", web::put().to(update_job_schedule))
+            .route("/jobs/schedules/{name}/run", web::post().to(run_job_schedule))
+            .route("/jobs/{id}", web::get().to(get_job))
+            .route("/jobs/{id}/retry", web::post().to(retry_job))
             .route("/garages/dailyAvailabilityReport", web::get().to(get_garage_report))
+            .route("/garages/mechanicWorkloadReport", web::get().to(get_mechanic_workload_report))
             .route("/maintenance/monthlyRequestsReport", web::get().to(monthly_requests_report)) 
+            .route("/maintenance/monthlyRevenueReport", web::get().to(monthly_revenue_report))
+            .route("/garages/cityRevenueReport", web::get().to(city_revenue_report))
+            .route("/garages/lowStockReport", web::get().to(low_stock_report))
             .route("/garages", web::get().to(get_all_garages))
             .route("/garages", web::post().to(create_garage))
+            .route("/garages/import", web::post().to(import_garages))
             .route("/garages/{id}", web::delete().to(delete_garage)) 
             .route("/garages/{id}", web::put().to(edit_garage))
             .route("/garages/{id}", web::get().to(get_single_garage))
+            .route("/garages/{id}/restore", web::post().to(restore_garage))
+            .route("/garages/{id}/schedule", web::get().to(get_garage_schedule))
+            .route("/garages/{id}/reschedule", web::post().to(reschedule_garage_day))
+            .route("/garages/{id}/bays", web::get().to(get_garage_bays))
+            .route("/garages/{id}/bays", web::post().to(create_bay))
+            .route("/garages/{id}/slots", web::get().to(get_free_slots))
+            .route("/garages/{id}/calendar.ics", web::get().to(get_garage_calendar))
+            .route("/bays/{id}", web::delete().to(delete_bay))
+            .route("/garages/{id}/mechanics", web::get().to(get_garage_mechanics))
+            .route("/garages/{id}/mechanics", web::post().to(create_mechanic))
+            .route("/mechanics/{id}", web::delete().to(delete_mechanic))
+            .route("/service-types", web::get().to(get_all_service_types))
+            .route("/service-types", web::post().to(create_service_type))
+            .route("/service-types/{id}", web::put().to(edit_service_type))
+            .route("/service-types/{id}/parts", web::get().to(get_service_type_parts))
+            .route("/service-types/{id}/parts", web::put().to(set_service_type_parts))
+            .route("/parts", web::get().to(get_all_parts))
+            .route("/parts", web::post().to(create_part))
+            .route("/parts/{id}", web::put().to(edit_part))
+            .route("/webhooks", web::get().to(get_all_webhooks))
+            .route("/webhooks", web::post().to(create_webhook))
+            .route("/webhooks/{id}", web::get().to(get_webhook))
+            .route("/webhooks/{id}", web::put().to(edit_webhook))
+            .route("/webhooks/{id}", web::delete().to(delete_webhook))
+            .route("/webhooks/{id}/deliveries", web::get().to(get_webhook_deliveries))
+            .route("/garages/{id}/stock", web::get().to(get_garage_stock))
+            .route("/garages/{id}/stock/{part_id}", web::put().to(update_garage_stock))
+            .route("/cars/due-for-service", web::get().to(get_due_for_service))
             .route("/cars", web::get().to(get_all_cars))
             .route("/cars", web::post().to(create_car))
+            .route("/cars/import", web::post().to(import_cars))
+            .route("/cars/by-plate/{plate}", web::get().to(get_car_by_plate))
+            .route("/vins/{vin}", web::get().to(decode_vin))
             .route("/cars/{id}", web::put().to(edit_car))
             .route("/cars/{id}", web::delete().to(delete_car))
+            .route("/cars/{id}/restore", web::post().to(restore_car))
+            .route("/cars/{id}/maintenance", web::get().to(get_car_maintenance_history))
+            .route("/cars/{id}/calendar.ics", web::get().to(get_car_calendar))
+            .route("/cars/{id}/odometer", web::get().to(get_car_odometer))
+            .route("/cars/{id}/odometer", web::post().to(add_odometer_reading))
+            .route("/cars/{id}/plans", web::get().to(get_car_plans))
+            .route("/cars/{id}/plans", web::post().to(create_car_plan))
+            .route("/maintenance-plans/materialize", web::post().to(materialize_plans))
+            .route("/maintenance-plans/{id}", web::put().to(update_plan))
+            .route("/maintenance-plans/{id}", web::delete().to(delete_plan))
             .route("/maintenance", web::get().to(get_all_maintenances))
             .route("/maintenance", web::post().to(create_maintenance)) 
             .route("/maintenance/{id}", web::get().to(get_maintenance_by_id))
             .route("/maintenance/{id}", web::put().to(edit_maintenance))
             .route("/maintenance/{id}", web::delete().to(delete_maintenance)) 
+            .route("/maintenance/{id}/quote", web::post().to(quote_maintenance))
+            .route("/maintenance/{id}/confirm", web::post().to(confirm_maintenance))
+            .route("/maintenance/{id}/start", web::post().to(start_maintenance))
+            .route("/maintenance/{id}/complete", web::post().to(complete_maintenance))
+            .route("/maintenance/{id}/cancel", web::post().to(cancel_maintenance))
+            .route("/maintenance/{id}/no-show", web::post().to(mark_maintenance_no_show))
+            .route("/maintenance/{id}/history", web::get().to(get_maintenance_history))
+            .route("/maintenance/{id}/mechanic", web::put().to(assign_mechanic))
+            .route("/maintenance/{id}/line-items", web::get().to(get_line_items))
+            .route("/maintenance/{id}/line-items", web::post().to(add_line_item))
+            .route("/maintenance/{id}/line-items/{item_id}", web::delete().to(delete_line_item))
+            .route("/maintenance/{id}/invoice", web::get().to(get_invoice))
+            .route("/maintenance/{id}/notifications", web::get().to(get_maintenance_notifications))
+            .route("/maintenance/{id}/restore", web::post().to(restore_maintenance))
         })
     .bind("127.0.0.1:8088")?
     .run()
@@ -0,0 +1,65 @@
+use crate::slots;
+use sqlx::SqliteConnection;
+
+/// Finds a booking that keeps the mechanic busy when the given one would
+/// need them. Bookings with a start time clash when their windows overlap;
+/// a booking without one takes the mechanic for the whole day. Returns a
+/// human-readable reason on conflict.
+pub async fn find_conflict(
+    conn: &mut SqliteConnection,
+    mechanic_id: i64,
+    scheduled_date: &str,
+    start_time: Option<&str>,
+    duration_minutes: Option<i64>,
+    exclude_maintenance_id: Option<i64>,
+) -> Result<Option<String>, sqlx::Error> {
+    let others = sqlx::query!(
+        r#"
+        SELECT id AS "id!", start_time, duration_minutes
+        FROM maintenance
+        WHERE mechanic_id = ?
+          AND date(scheduled_date) = date(?)
+          AND status != 'CANCELLED'
+          AND deleted_at IS NULL
+          AND (?3 IS NULL OR id != ?3)
+        ORDER BY start_time, id
+        "#,
+        mechanic_id,
+        scheduled_date,
+        exclude_maintenance_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let window = start_time
+        .and_then(slots::parse_time)
+        .map(|start| (start, start + duration_minutes.unwrap_or(1).max(1)));
+
+    for other in others {
+        let other_window = other
+            .start_time
+            .as_deref()
+            .and_then(slots::parse_time)
+            .map(|start| (start, start + other.duration_minutes.unwrap_or(1).max(1)));
+
+        let overlaps = match (window, other_window) {
+            (Some((start, end)), Some((other_start, other_end))) => start < other_end && other_start < end,
+            _ => true,
+        };
+
+        if overlaps {
+            return Ok(Some(match other.start_time {
+                Some(other_start) => format!(
+                    "Mechanic {} is already assigned to maintenance {} on {} at {}",
+                    mechanic_id, other.id, scheduled_date, other_start
+                ),
+                None => format!(
+                    "Mechanic {} is already assigned to maintenance {} on {}",
+                    mechanic_id, other.id, scheduled_date
+                ),
+            }));
+        }
+    }
+
+    Ok(None)
+}
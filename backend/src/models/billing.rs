@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[sqlx(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LineItemKind {
+    Labour,
+    Part,
+    Fee,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LineItemDTO {
+    pub id: i64,
+    pub maintenance_id: i64,
+    pub kind: LineItemKind,
+    pub description: String,
+    /// Hours for labour, pieces for parts.
+    pub quantity: f64,
+    pub unit_price_cents: i64,
+    pub total_cents: i64,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateLineItemDTO {
+    pub kind: LineItemKind,
+    pub description: String,
+    pub quantity: f64,
+    pub unit_price_cents: i64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InvoiceDTO {
+    pub invoice_number: String,
+    pub maintenance_id: i64,
+    pub car_id: String,
+    pub garage_id: String,
+    pub issued_at: String,
+    pub lines: Vec<LineItemDTO>,
+    pub subtotal_cents: i64,
+    pub tax_rate_percent: f64,
+    pub tax_cents: i64,
+    pub total_cents: i64,
+}
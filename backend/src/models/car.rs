@@ -10,17 +10,48 @@ pub struct Car {
     pub model: Option<String>,
     pub production_year: Option<i64>,
     pub license_plate: Option<String>,
+    pub vin: Option<String>,
     pub garage_ids: Option<Value>,
     pub garages: Option<Value>, 
+    pub owner_name: Option<String>,
+    pub owner_email: Option<String>,
+    pub owner_phone: Option<String>,
+    pub deleted_at: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateCarRequest {
+    /// Taken from the VIN when left out.
+    #[serde(default)]
     pub make: String,
     pub model: String,
-    pub production_year: i64,
+    /// Taken from the VIN's model year when left out.
+    pub production_year: Option<i64>,
     pub license_plate: String,
+    /// Checked against `make` and `productionYear` when both are given. On
+    /// update, left out keeps the stored VIN and empty removes it.
+    pub vin: Option<String>,
+    /// ISO country code whose plate layout the plate must follow, e.g.
+    /// `HR`. Defaults to the server's `PLATE_COUNTRY` when the plate fits it.
+    pub plate_country: Option<String>,
     pub garage_ids: Option<Vec<i64>>, 
+    pub owner_name: Option<String>,
+    pub owner_email: Option<String>,
+    pub owner_phone: Option<String>,
 }
 
+
+/// Flat car row for CSV/XLSX exports; garage ids are joined with `;`.
+#[derive(Serialize, FromRow, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CarExportRow {
+    pub id: i64,
+    pub make: String,
+    pub model: String,
+    pub production_year: i64,
+    pub license_plate: String,
+    pub vin: Option<String>,
+    pub garage_ids: String,
+    pub deleted_at: Option<String>,
+}
@@ -1,20 +1,30 @@
+use crate::models::maintenance::MaintenanceStatus;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct Garage {
     pub id: i64,
     pub name: String,
     pub location: String,
     pub city: String,
     pub capacity: i64,
+    pub opening_time: String,
+    pub closing_time: String,
+    pub slot_minutes: i64,
+    pub deleted_at: Option<String>,
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct CreateGarageRequest {
     pub name: String,
     pub location: String,
     pub city: String,
     pub capacity: i64,
+    pub opening_time: Option<String>,
+    pub closing_time: Option<String>,
+    pub slot_minutes: Option<i64>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -32,4 +42,116 @@ pub struct GarageDailyAvailabilityReportDTO {
     pub date: String,
     pub requests: i32,
     pub available_capacity: i32,
-}
\ No newline at end of file
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GarageScheduleQueryParams {
+    pub start_date: String,
+    pub end_date: String,
+    #[serde(default)]
+    pub include_cancelled: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GarageBookingDTO {
+    pub maintenance_id: i64,
+    pub car_id: String,
+    pub car_name: String,
+    pub license_plate: String,
+    pub service_type: String,
+    pub status: MaintenanceStatus,
+    pub bay_id: Option<i64>,
+    pub start_time: Option<String>,
+    pub duration_minutes: Option<i64>,
+    pub mechanic_id: Option<i64>,
+    pub notes: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GarageScheduleDayDTO {
+    pub date: String,
+    pub bookings: Vec<GarageBookingDTO>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RescheduleStrategy {
+    /// Keep the garage and move each booking to its next day with capacity.
+    NextAvailableDate,
+    /// Move each booking to another garage the car is registered with.
+    AlternativeGarage,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RescheduleGarageDayDTO {
+    pub date: String,
+    pub strategy: RescheduleStrategy,
+    pub max_days_ahead: Option<u64>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MovedBookingDTO {
+    pub maintenance_id: i64,
+    pub car_id: String,
+    pub from_garage_id: i64,
+    pub from_date: String,
+    pub to_garage_id: i64,
+    pub to_garage_name: String,
+    pub to_date: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UnmovedBookingDTO {
+    pub maintenance_id: i64,
+    pub car_id: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RescheduleReportDTO {
+    pub garage_id: i64,
+    pub date: String,
+    pub strategy: RescheduleStrategy,
+    pub dry_run: bool,
+    pub moved: Vec<MovedBookingDTO>,
+    pub unmoved: Vec<UnmovedBookingDTO>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BayDTO {
+    pub id: i64,
+    pub garage_id: i64,
+    pub name: String,
+    pub active: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateBayRequest {
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FreeSlotsQueryParams {
+    pub date: String,
+    pub service_type: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FreeSlotDTO {
+    pub start_time: String,
+    pub end_time: String,
+    pub bay_ids: Vec<i64>,
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportQueryParams {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRowErrorDTO {
+    /// Line in the uploaded file; the header is line 1.
+    pub line: u64,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReportDTO {
+    pub dry_run: bool,
+    pub total_rows: usize,
+    pub valid_rows: usize,
+    /// Ids of the created records; empty for dry runs and failed imports.
+    pub created_ids: Vec<i64>,
+    pub errors: Vec<ImportRowErrorDTO>,
+}
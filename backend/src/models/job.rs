@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JobQueryParams {
+    pub status: Option<String>,
+    pub kind: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JobDTO {
+    pub id: i64,
+    pub kind: String,
+    pub payload: Value,
+    pub status: String,
+    pub attempts: i64,
+    pub max_attempts: i64,
+    pub run_at: String,
+    /// Schedule that queued the job, if any.
+    pub schedule: Option<String>,
+    /// Summary of a successful run.
+    pub result: Option<Value>,
+    pub last_error: Option<String>,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JobScheduleDTO {
+    pub name: String,
+    pub kind: String,
+    pub payload: Value,
+    pub cron: String,
+    pub active: bool,
+    pub next_run_at: String,
+    pub last_run_at: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateJobScheduleRequest {
+    pub cron: Option<String>,
+    pub active: Option<bool>,
+}
@@ -1,4 +1,96 @@
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[sqlx(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MaintenanceStatus {
+    Scheduled,
+    Quoted,
+    Confirmed,
+    InProgress,
+    Completed,
+    Cancelled,
+    NoShow,
+}
+
+impl MaintenanceStatus {
+    pub const ALL: [MaintenanceStatus; 7] = [
+        MaintenanceStatus::Scheduled,
+        MaintenanceStatus::Quoted,
+        MaintenanceStatus::Confirmed,
+        MaintenanceStatus::InProgress,
+        MaintenanceStatus::Completed,
+        MaintenanceStatus::Cancelled,
+        MaintenanceStatus::NoShow,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MaintenanceStatus::Scheduled => "SCHEDULED",
+            MaintenanceStatus::Quoted => "QUOTED",
+            MaintenanceStatus::Confirmed => "CONFIRMED",
+            MaintenanceStatus::InProgress => "IN_PROGRESS",
+            MaintenanceStatus::Completed => "COMPLETED",
+            MaintenanceStatus::Cancelled => "CANCELLED",
+            MaintenanceStatus::NoShow => "NO_SHOW",
+        }
+    }
+
+    /// Allowed moves of the booking state machine. Completed, cancelled and
+    /// no-show bookings are final.
+    pub fn can_transition_to(&self, next: MaintenanceStatus) -> bool {
+        use MaintenanceStatus::*;
+
+        matches!(
+            (self, next),
+            (Scheduled, Quoted)
+                | (Scheduled, Confirmed)
+                | (Scheduled, InProgress)
+                | (Scheduled, Cancelled)
+                | (Scheduled, NoShow)
+                | (Quoted, Confirmed)
+                | (Quoted, Cancelled)
+                | (Quoted, NoShow)
+                | (Confirmed, InProgress)
+                | (Confirmed, Cancelled)
+                | (Confirmed, NoShow)
+                | (InProgress, Completed)
+                | (InProgress, Cancelled)
+        )
+    }
+
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            MaintenanceStatus::Completed | MaintenanceStatus::Cancelled | MaintenanceStatus::NoShow
+        )
+    }
+}
+
+impl fmt::Display for MaintenanceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for MaintenanceStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "SCHEDULED" => Ok(MaintenanceStatus::Scheduled),
+            "QUOTED" => Ok(MaintenanceStatus::Quoted),
+            "CONFIRMED" => Ok(MaintenanceStatus::Confirmed),
+            "IN_PROGRESS" => Ok(MaintenanceStatus::InProgress),
+            "COMPLETED" => Ok(MaintenanceStatus::Completed),
+            "CANCELLED" => Ok(MaintenanceStatus::Cancelled),
+            "NO_SHOW" => Ok(MaintenanceStatus::NoShow),
+            other => Err(format!("Unknown maintenance status: {}", other)),
+        }
+    }
+}
 
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")] 
@@ -7,6 +99,9 @@ pub struct CreateMaintenanceDTO {
     pub garage_id: String,
     pub service_type: String,
     pub scheduled_date: String,
+    pub start_time: Option<String>,
+    pub bay_id: Option<i64>,
+    pub notes: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug)] 
@@ -16,6 +111,9 @@ pub struct UpdateMaintenanceDTO {
     pub garage_id: String,
     pub service_type: Option<String>,
     pub scheduled_date: Option<String>,
+    pub start_time: Option<String>,
+    pub bay_id: Option<i64>,
+    pub notes: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -28,6 +126,18 @@ pub struct ResponseMaintenanceDTO {
     pub scheduled_date: String,
     pub garage_id: String,
     pub garage_name: String,
+    pub status: MaintenanceStatus,
+    pub bay_id: Option<i64>,
+    pub start_time: Option<String>,
+    pub duration_minutes: Option<i64>,
+    pub mechanic_id: Option<i64>,
+    pub notes: Option<String>,
+    pub deleted_at: Option<String>,
+    pub quoted_total_cents: Option<i64>,
+    /// Invoiced amounts once completed, otherwise the current estimate.
+    pub subtotal_cents: i64,
+    pub tax_cents: i64,
+    pub total_cents: i64,
 }
 
 #[derive(Deserialize, Serialize, Debug)] 
@@ -38,14 +148,69 @@ pub struct Maintenance {
     pub garage_id: String,
     pub service_type: String,
     pub scheduled_date: String,
+    pub status: MaintenanceStatus,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TransitionMaintenanceDTO {
+    pub note: Option<String>,
+    /// Odometer reading taken when the car is handed back; only used when
+    /// completing a booking.
+    pub odometer_km: Option<i64>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
-pub struct EditMaintenanceDTO {
-    pub id: String,
-    pub car_id: String,
+pub struct MaintenanceStatusHistoryDTO {
+    pub id: i64,
+    pub maintenance_id: i64,
+    pub from_status: Option<MaintenanceStatus>,
+    pub to_status: MaintenanceStatus,
+    pub note: Option<String>,
+    pub changed_at: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CarMaintenanceEntryDTO {
+    pub id: i64,
+    pub scheduled_date: String,
+    pub service_type: String,
+    pub status: MaintenanceStatus,
+    pub notes: Option<String>,
     pub garage_id: String,
+    pub garage_name: String,
+    pub odometer_km: Option<i64>,
+    pub completed_at: Option<String>,
+    pub invoice_total_cents: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LastServiceDTO {
     pub service_type: String,
-    pub scheduled_date: String,
-}
\ No newline at end of file
+    pub maintenance_id: i64,
+    pub date: String,
+    pub garage_name: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CarMaintenanceSummaryDTO {
+    pub total_bookings: usize,
+    pub completed: usize,
+    pub last_service_per_type: Vec<LastServiceDTO>,
+    /// Sum of the invoices of completed bookings, in cents.
+    pub total_spend: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CarMaintenanceHistoryDTO {
+    pub car_id: i64,
+    pub car_name: String,
+    pub license_plate: String,
+    pub history: Vec<CarMaintenanceEntryDTO>,
+    pub summary: CarMaintenanceSummaryDTO,
+}
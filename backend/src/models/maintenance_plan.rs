@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMaintenancePlanDTO {
+    pub garage_id: i64,
+    pub service_type: String,
+    pub interval_days: Option<i64>,
+    pub interval_months: Option<i64>,
+    pub interval_km: Option<i64>,
+    pub start_date: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateMaintenancePlanDTO {
+    pub garage_id: Option<i64>,
+    pub service_type: Option<String>,
+    pub active: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenancePlanDTO {
+    pub id: i64,
+    pub car_id: i64,
+    pub garage_id: i64,
+    pub garage_name: String,
+    pub service_type: String,
+    pub interval_days: Option<i64>,
+    pub interval_months: Option<i64>,
+    pub interval_km: Option<i64>,
+    pub start_date: String,
+    pub next_due_date: String,
+    pub active: bool,
+    pub created_at: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MaterializeQueryParams {
+    pub horizon_days: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MaterializedMaintenanceDTO {
+    pub maintenance_id: i64,
+    pub plan_id: i64,
+    pub car_id: i64,
+    pub garage_id: i64,
+    pub service_type: String,
+    pub due_date: String,
+    pub scheduled_date: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedOccurrenceDTO {
+    pub plan_id: i64,
+    pub due_date: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MaterializeReportDTO {
+    pub created: Vec<MaterializedMaintenanceDTO>,
+    pub skipped: Vec<SkippedOccurrenceDTO>,
+}
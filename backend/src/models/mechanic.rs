@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MechanicDTO {
+    pub id: i64,
+    pub garage_id: i64,
+    pub name: String,
+    pub active: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMechanicRequest {
+    pub name: String,
+}
+
+/// Body of `PUT /maintenance/{id}/mechanic`; `null` unassigns the booking.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignMechanicDTO {
+    pub mechanic_id: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MechanicWorkloadDTO {
+    pub date: String,
+    pub mechanic_id: i64,
+    pub mechanic_name: String,
+    pub bookings: i64,
+    pub booked_minutes: i64,
+}
@@ -1,3 +1,18 @@
+pub mod audit;
+pub mod billing;
 pub mod car;
+pub mod common;
+pub mod event;
 pub mod garage;
+pub mod import;
+pub mod job;
 pub mod maintenance;
+pub mod maintenance_plan;
+pub mod mechanic;
+pub mod notification;
+pub mod odometer;
+pub mod part;
+pub mod report;
+pub mod search;
+pub mod service_type;
+pub mod webhook;
@@ -0,0 +1,17 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationDTO {
+    pub id: i64,
+    pub maintenance_id: i64,
+    pub kind: String,
+    pub channel: String,
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    pub scheduled_date: String,
+    pub status: String,
+    pub error: Option<String>,
+    pub created_at: String,
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OdometerReadingDTO {
+    pub id: i64,
+    pub car_id: i64,
+    pub reading_km: i64,
+    pub recorded_on: String,
+    pub maintenance_id: Option<i64>,
+    pub created_at: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateOdometerReadingDTO {
+    pub reading_km: i64,
+    pub recorded_on: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DueForServiceQueryParams {
+    #[serde(default)]
+    pub within_days: i64,
+    #[serde(default)]
+    pub within_km: i64,
+    pub car_id: Option<i64>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DueReason {
+    Time,
+    Mileage,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DueForServiceDTO {
+    pub car_id: i64,
+    pub car_name: String,
+    pub license_plate: String,
+    pub plan_id: i64,
+    pub service_type: String,
+    pub last_service_date: Option<String>,
+    pub last_service_km: Option<i64>,
+    pub current_km: Option<i64>,
+    pub due_date: Option<String>,
+    pub due_km: Option<i64>,
+    pub overdue: bool,
+    pub reasons: Vec<DueReason>,
+}
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PartDTO {
+    pub id: i64,
+    pub sku: String,
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePartRequest {
+    pub sku: String,
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePartRequest {
+    pub sku: Option<String>,
+    pub name: Option<String>,
+}
+
+/// Stock of one part in a garage. `available` is what is left once every
+/// open booking has taken its reserved parts.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StockLevelDTO {
+    pub part_id: i64,
+    pub sku: String,
+    pub name: String,
+    pub on_hand: i64,
+    pub reserved: i64,
+    pub available: i64,
+    pub reorder_level: i64,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateStockRequest {
+    pub on_hand: Option<i64>,
+    pub reorder_level: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceTypePartDTO {
+    pub part_id: i64,
+    pub quantity: i64,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LowStockQueryParams {
+    pub garage_id: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LowStockDTO {
+    pub garage_id: i64,
+    pub garage_name: String,
+    pub part_id: i64,
+    pub sku: String,
+    pub name: String,
+    pub on_hand: i64,
+    pub reserved: i64,
+    pub available: i64,
+    pub reorder_level: i64,
+    /// Parts to order to get back to the reorder level.
+    pub shortfall: i64,
+}
@@ -0,0 +1,222 @@
+use crate::models::maintenance::MaintenanceStatus;
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Month {
+    January,
+    February,
+    March,
+    April,
+    May,
+    June,
+    July,
+    August,
+    September,
+    October,
+    November,
+    December,
+}
+
+impl Month {
+    const ALL: [Month; 12] = [
+        Month::January,
+        Month::February,
+        Month::March,
+        Month::April,
+        Month::May,
+        Month::June,
+        Month::July,
+        Month::August,
+        Month::September,
+        Month::October,
+        Month::November,
+        Month::December,
+    ];
+
+    pub fn from_value(value: u32) -> Option<Self> {
+        Self::ALL.get(value.checked_sub(1)? as usize).copied()
+    }
+
+    pub fn value(&self) -> u32 {
+        *self as u32 + 1
+    }
+}
+
+/// Month of a year in the shape the OpenAPI spec gives `yearMonth`.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub struct YearMonth {
+    pub year: i32,
+    pub month: Month,
+    pub leap_year: bool,
+    pub month_value: u32,
+}
+
+impl YearMonth {
+    pub fn new(year: i32, month: Month) -> Self {
+        YearMonth {
+            year,
+            month,
+            leap_year: (year % 4 == 0 && year % 100 != 0) || year % 400 == 0,
+            month_value: month.value(),
+        }
+    }
+
+    /// Parses a strict `YYYY-MM` value.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (year, month) = value.trim().split_once('-')?;
+        if year.len() != 4 || month.len() != 2 || !year.bytes().chain(month.bytes()).all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        Some(YearMonth::new(year.parse().ok()?, Month::from_value(month.parse().ok()?)?))
+    }
+
+    pub fn next(&self) -> Self {
+        match Month::from_value(self.month_value + 1) {
+            Some(month) => YearMonth::new(self.year, month),
+            None => YearMonth::new(self.year + 1, Month::January),
+        }
+    }
+
+    /// How many months `range(start, end)` yields.
+    pub fn months_between(start: YearMonth, end: YearMonth) -> i64 {
+        (i64::from(end.year) - i64::from(start.year)) * 12 + i64::from(end.month_value) - i64::from(start.month_value) + 1
+    }
+
+    /// Every month from `start` to `end`, both included.
+    pub fn range(start: YearMonth, end: YearMonth) -> impl Iterator<Item = YearMonth> {
+        std::iter::successors(Some(start), |month| Some(month.next())).take_while(move |month| *month <= end)
+    }
+}
+
+/// `YYYY-MM`, the form months are stored and queried in.
+impl fmt::Display for YearMonth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}", self.year, self.month_value)
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthlyRequestsReportDTO {
+    pub year_month: YearMonth,
+    pub requests: i32,
+    /// One entry per status, in `MaintenanceStatus::ALL` order, summing to `requests`.
+    pub by_status: Vec<StatusCountDTO>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusCountDTO {
+    pub status: MaintenanceStatus,
+    pub requests: i32,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceMixDTO {
+    pub service_type: String,
+    pub invoices: i64,
+    pub revenue_cents: i64,
+}
+
+/// Revenue is counted net of tax and attributed to the month the service was
+/// booked for, like the monthly requests report.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthlyRevenueDTO {
+    pub year_month: YearMonth,
+    pub invoices: i64,
+    pub revenue_cents: i64,
+    pub tax_cents: i64,
+    pub average_ticket_cents: i64,
+    pub service_mix: Vec<ServiceMixDTO>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GarageRevenueDTO {
+    pub garage_id: i64,
+    pub garage_name: String,
+    pub invoices: i64,
+    pub revenue_cents: i64,
+    pub average_ticket_cents: i64,
+    pub service_mix: Vec<ServiceMixDTO>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn month(value: &str) -> YearMonth {
+        YearMonth::parse(value).unwrap()
+    }
+
+    #[test]
+    fn parses_strict_year_months() {
+        assert_eq!(month("2025-03"), YearMonth::new(2025, Month::March));
+        assert_eq!(month(" 2025-12 "), YearMonth::new(2025, Month::December));
+    }
+
+    #[test]
+    fn rejects_malformed_year_months() {
+        let malformed = [
+            "", "2025", "2025-3", "25-03", "2025-13", "2025-00", "2025/03", "2025-03-01", "+025-03", "2025-0a",
+        ];
+        for value in malformed {
+            assert_eq!(YearMonth::parse(value), None, "'{}' was accepted", value);
+        }
+    }
+
+    #[test]
+    fn knows_leap_years() {
+        assert!(YearMonth::new(2024, Month::February).leap_year);
+        assert!(!YearMonth::new(2025, Month::February).leap_year);
+        assert!(!YearMonth::new(1900, Month::February).leap_year);
+        assert!(YearMonth::new(2000, Month::February).leap_year);
+    }
+
+    #[test]
+    fn serializes_in_the_spec_shape() {
+        assert_eq!(
+            serde_json::to_value(month("2024-02")).unwrap(),
+            json!({ "year": 2024, "month": "FEBRUARY", "leapYear": true, "monthValue": 2 })
+        );
+    }
+
+    #[test]
+    fn displays_as_year_dash_month() {
+        assert_eq!(month("2025-03").to_string(), "2025-03");
+        assert_eq!(YearMonth::new(825, Month::January).to_string(), "0825-01");
+    }
+
+    #[test]
+    fn lists_every_month_of_a_range() {
+        let months: Vec<String> = YearMonth::range(month("2024-11"), month("2025-02"))
+            .map(|month| month.to_string())
+            .collect();
+        assert_eq!(months, ["2024-11", "2024-12", "2025-01", "2025-02"]);
+    }
+
+    #[test]
+    fn lists_a_single_month() {
+        assert_eq!(YearMonth::range(month("2025-03"), month("2025-03")).count(), 1);
+    }
+
+    #[test]
+    fn lists_nothing_for_a_reversed_range() {
+        assert_eq!(YearMonth::range(month("2025-03"), month("2025-02")).count(), 0);
+    }
+
+    #[test]
+    fn counts_months_like_the_range() {
+        for (start, end) in [("2025-03", "2025-03"), ("2024-11", "2025-02"), ("2015-01", "2024-12")] {
+            let (start, end) = (month(start), month(end));
+            assert_eq!(YearMonth::months_between(start, end), YearMonth::range(start, end).count() as i64);
+        }
+        assert_eq!(YearMonth::months_between(month("2015-01"), month("2024-12")), 120);
+    }
+}
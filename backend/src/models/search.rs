@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchQueryParams {
+    pub q: Option<String>,
+    /// Comma-separated groups to search: cars, garages, maintenance.
+    pub types: Option<String>,
+    /// Most hits returned per group.
+    pub limit: Option<i64>,
+}
+
+/// Hits are ordered best first; a higher `score` is a better match.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CarSearchHitDTO {
+    pub id: i64,
+    pub make: String,
+    pub model: String,
+    pub license_plate: String,
+    pub score: f64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GarageSearchHitDTO {
+    pub id: i64,
+    pub name: String,
+    pub location: String,
+    pub city: String,
+    pub score: f64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceSearchHitDTO {
+    pub id: i64,
+    pub car_id: String,
+    pub car_name: String,
+    pub garage_id: String,
+    pub garage_name: String,
+    pub service_type: String,
+    pub scheduled_date: String,
+    pub status: String,
+    pub notes: Option<String>,
+    pub score: f64,
+}
+
+/// Groups left out through `types` are omitted.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResultsDTO {
+    pub query: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cars: Option<Vec<CarSearchHitDTO>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub garages: Option<Vec<GarageSearchHitDTO>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maintenance: Option<Vec<MaintenanceSearchHitDTO>>,
+}
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceType {
+    pub id: i64,
+    pub name: String,
+    pub duration_minutes: i64,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateServiceTypeRequest {
+    pub name: String,
+    pub duration_minutes: i64,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateServiceTypeRequest {
+    pub name: Option<String>,
+    pub duration_minutes: Option<i64>,
+}
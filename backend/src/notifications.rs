@@ -0,0 +1,507 @@
+use crate::events::EventType;
+use crate::scheduler;
+use async_trait::async_trait;
+use chrono::{Duration, Local, NaiveDate};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use std::env;
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+
+const DEFAULT_SMTP_PORT: u16 = 587;
+const SMS_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+const CONFIRMATION_SUBJECT: &str = "Your {service} booking on {date}";
+const CONFIRMATION_BODY: &str = "Hello {owner},\n\nYour {car} ({plate}) is booked for {service} at {garage}, {address} on {date}{time}.\n\nSee you then!";
+const RESCHEDULE_SUBJECT: &str = "Your {service} booking has moved to {date}";
+const RESCHEDULE_BODY: &str = "Hello {owner},\n\nThe {service} booking for your {car} ({plate}) has changed. It is now at {garage}, {address} on {date}{time}.\n\nReply to this message if the new time does not suit you.";
+const REMINDER_SUBJECT: &str = "Reminder: {service} tomorrow";
+const REMINDER_BODY: &str = "Hello {owner},\n\nA reminder that your {car} ({plate}) is booked for {service} at {garage}, {address} tomorrow, {date}{time}.";
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum NotificationKind {
+    Confirmation,
+    Reschedule,
+    Reminder,
+}
+
+impl NotificationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::Confirmation => "CONFIRMATION",
+            NotificationKind::Reschedule => "RESCHEDULE",
+            NotificationKind::Reminder => "REMINDER",
+        }
+    }
+
+    fn templates(&self) -> (&'static str, &'static str) {
+        match self {
+            NotificationKind::Confirmation => (CONFIRMATION_SUBJECT, CONFIRMATION_BODY),
+            NotificationKind::Reschedule => (RESCHEDULE_SUBJECT, RESCHEDULE_BODY),
+            NotificationKind::Reminder => (REMINDER_SUBJECT, REMINDER_BODY),
+        }
+    }
+}
+
+/// Contact details of a car's owner.
+pub struct Recipient {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+}
+
+pub struct Message {
+    pub subject: String,
+    pub body: String,
+}
+
+/// A way of reaching car owners. Channels pick the address they need from
+/// the recipient and skip owners they cannot reach.
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn address(&self, recipient: &Recipient) -> Option<String>;
+
+    async fn send(&self, address: &str, message: &Message) -> Result<(), String>;
+}
+
+/// Rejects owner contact details no channel could use.
+pub fn contact_error(email: Option<&str>, phone: Option<&str>) -> Option<String> {
+    if let Some(email) = email {
+        if email.parse::<Mailbox>().is_err() {
+            return Some(format!("ownerEmail '{}' is not a valid email address", email));
+        }
+    }
+    if let Some(phone) = phone {
+        let digits = phone.chars().filter(char::is_ascii_digit).count();
+        let allowed = phone
+            .chars()
+            .enumerate()
+            .all(|(index, c)| c.is_ascii_digit() || matches!(c, ' ' | '-' | '(' | ')') || (c == '+' && index == 0));
+        if !allowed || !(6..=15).contains(&digits) {
+            return Some(format!("ownerPhone '{}' is not a valid phone number", phone));
+        }
+    }
+    None
+}
+
+/// Fills `{name}` placeholders; unknown ones are left as they are.
+pub fn render(template: &str, values: &[(&str, String)]) -> String {
+    values.iter().fold(template.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+pub struct SmtpChannel {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpChannel {
+    /// Configured by `SMTP_HOST`, `SMTP_PORT`, `SMTP_USERNAME`,
+    /// `SMTP_PASSWORD` and `SMTP_FROM`. `SMTP_TLS=off` talks plain SMTP,
+    /// e.g. to a local mail catcher.
+    pub fn from_env() -> Option<Result<Self, String>> {
+        let host = env::var("SMTP_HOST").ok()?;
+        Some(Self::build(&host))
+    }
+
+    fn build(host: &str) -> Result<Self, String> {
+        let from = env::var("SMTP_FROM")
+            .map_err(|_| "SMTP_FROM is required when SMTP_HOST is set".to_string())?
+            .parse::<Mailbox>()
+            .map_err(|err| format!("SMTP_FROM is not a valid address: {}", err))?;
+        let port = env::var("SMTP_PORT")
+            .ok()
+            .and_then(|port| port.parse::<u16>().ok())
+            .unwrap_or(DEFAULT_SMTP_PORT);
+
+        let mut builder = if env::var("SMTP_TLS").is_ok_and(|tls| tls.eq_ignore_ascii_case("off")) {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host).map_err(|err| err.to_string())?
+        };
+        builder = builder.port(port);
+        if let (Ok(username), Ok(password)) = (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD")) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(SmtpChannel {
+            transport: builder.build(),
+            from,
+        })
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for SmtpChannel {
+    fn name(&self) -> &'static str {
+        "EMAIL"
+    }
+
+    fn address(&self, recipient: &Recipient) -> Option<String> {
+        recipient.email.clone()
+    }
+
+    async fn send(&self, address: &str, message: &Message) -> Result<(), String> {
+        let to = address.parse::<Mailbox>().map_err(|err| err.to_string())?;
+        let email = lettre::Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(message.subject.clone())
+            .body(message.body.clone())
+            .map_err(|err| err.to_string())?;
+
+        self.transport.send(email).await.map(|_| ()).map_err(|err| err.to_string())
+    }
+}
+
+/// Sends texts through an HTTP gateway that accepts
+/// `{"to", "from", "text"}` as JSON with a bearer token.
+pub struct SmsGatewayChannel {
+    client: reqwest::Client,
+    url: String,
+    token: Option<String>,
+    from: Option<String>,
+}
+
+impl SmsGatewayChannel {
+    /// Configured by `SMS_GATEWAY_URL`, `SMS_GATEWAY_TOKEN` and `SMS_FROM`.
+    pub fn from_env() -> Option<Result<Self, String>> {
+        let url = env::var("SMS_GATEWAY_URL").ok()?;
+        Some(
+            reqwest::Client::builder()
+                .timeout(SMS_TIMEOUT)
+                .build()
+                .map(|client| SmsGatewayChannel {
+                    client,
+                    url,
+                    token: env::var("SMS_GATEWAY_TOKEN").ok(),
+                    from: env::var("SMS_FROM").ok(),
+                })
+                .map_err(|err| err.to_string()),
+        )
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for SmsGatewayChannel {
+    fn name(&self) -> &'static str {
+        "SMS"
+    }
+
+    fn address(&self, recipient: &Recipient) -> Option<String> {
+        recipient.phone.clone()
+    }
+
+    async fn send(&self, address: &str, message: &Message) -> Result<(), String> {
+        let mut request = self.client.post(&self.url).json(&json!({
+            "to": address,
+            "from": self.from,
+            "text": message.body,
+        }));
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await.map_err(|err| err.to_string())?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("SMS gateway answered {}", response.status()))
+        }
+    }
+}
+
+/// Writes messages to a file, or to the server log, instead of sending
+/// them. Meant for development and tests.
+pub struct LogChannel {
+    path: Option<PathBuf>,
+}
+
+#[async_trait]
+impl NotificationChannel for LogChannel {
+    fn name(&self) -> &'static str {
+        "LOG"
+    }
+
+    fn address(&self, recipient: &Recipient) -> Option<String> {
+        recipient.email.clone().or_else(|| recipient.phone.clone())
+    }
+
+    async fn send(&self, address: &str, message: &Message) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            info!("Notification to {}: {}\n{}", address, message.subject, message.body);
+            return Ok(());
+        };
+
+        let entry = json!({
+            "to": address,
+            "subject": message.subject,
+            "body": message.body,
+            "sentAt": Local::now().to_rfc3339(),
+        });
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", entry))
+            .map_err(|err| err.to_string())
+    }
+}
+
+/// Why notifying an owner failed.
+#[derive(Debug)]
+pub enum NotifyError {
+    Database(sqlx::Error),
+    /// Every channel that could reach the owner failed to send.
+    Undelivered(String),
+}
+
+impl From<sqlx::Error> for NotifyError {
+    fn from(err: sqlx::Error) -> Self {
+        NotifyError::Database(err)
+    }
+}
+
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotifyError::Database(err) => write!(f, "{}", err),
+            NotifyError::Undelivered(details) => write!(f, "Notification not delivered: {}", details),
+        }
+    }
+}
+
+pub struct Notifier {
+    channels: Vec<Box<dyn NotificationChannel>>,
+}
+
+impl Notifier {
+    pub fn new(channels: Vec<Box<dyn NotificationChannel>>) -> Self {
+        Notifier { channels }
+    }
+
+    /// Email and SMS are enabled by their settings; `NOTIFICATIONS_LOG_FILE`
+    /// adds the file sink. Without any of them messages go to the log.
+    pub fn from_env() -> Self {
+        let mut channels: Vec<Box<dyn NotificationChannel>> = Vec::new();
+
+        match SmtpChannel::from_env() {
+            Some(Ok(channel)) => channels.push(Box::new(channel)),
+            Some(Err(err)) => error!("Email notifications disabled: {}", err),
+            None => {}
+        }
+        match SmsGatewayChannel::from_env() {
+            Some(Ok(channel)) => channels.push(Box::new(channel)),
+            Some(Err(err)) => error!("SMS notifications disabled: {}", err),
+            None => {}
+        }
+        if let Ok(path) = env::var("NOTIFICATIONS_LOG_FILE") {
+            channels.push(Box::new(LogChannel { path: Some(PathBuf::from(path)) }));
+        }
+        if channels.is_empty() {
+            channels.push(Box::new(LogChannel { path: None }));
+        }
+
+        info!(
+            "Notification channels: {}",
+            channels.iter().map(|channel| channel.name()).collect::<Vec<_>>().join(", ")
+        );
+        Notifier::new(channels)
+    }
+
+    /// Tells the car's owner about a booking on every channel that can
+    /// reach them and logs each attempt. Deleted and closed bookings, and
+    /// reminders already sent for the booking's date, are skipped.
+    /// Returns how many messages went out, or `Undelivered` when every
+    /// channel tried failed so the job is retried.
+    pub async fn notify(
+        &self,
+        pool: &SqlitePool,
+        maintenance_id: i64,
+        kind: NotificationKind,
+    ) -> Result<usize, NotifyError> {
+        let Some(booking) = sqlx::query!(
+            r#"
+            SELECT
+                date(maintenance.scheduled_date) AS "scheduled_date!: String",
+                maintenance.start_time,
+                maintenance.service_type,
+                cars.make || ' ' || cars.model AS "car_name!: String",
+                cars.license_plate,
+                cars.owner_name,
+                cars.owner_email,
+                cars.owner_phone,
+                garages.name AS garage_name,
+                garages.location AS garage_location,
+                garages.city AS garage_city
+            FROM maintenance
+            JOIN cars ON maintenance.car_id = cars.id
+            JOIN garages ON maintenance.garage_id = garages.id
+            WHERE maintenance.id = ?
+              AND maintenance.deleted_at IS NULL
+              AND maintenance.status NOT IN ('COMPLETED', 'CANCELLED', 'NO_SHOW')
+            "#,
+            maintenance_id
+        )
+        .fetch_optional(pool)
+        .await?
+        else {
+            return Ok(0);
+        };
+
+        let recipient = Recipient {
+            name: booking.owner_name,
+            email: booking.owner_email,
+            phone: booking.owner_phone,
+        };
+        let date = NaiveDate::parse_from_str(&booking.scheduled_date, scheduler::DATE_FORMAT)
+            .map(|date| date.format("%A %-d %B %Y").to_string())
+            .unwrap_or_else(|_| booking.scheduled_date.clone());
+        let values = [
+            ("owner", recipient.name.clone().unwrap_or_else(|| "there".to_string())),
+            ("car", booking.car_name),
+            ("plate", booking.license_plate),
+            ("service", booking.service_type),
+            ("garage", booking.garage_name),
+            ("address", format!("{}, {}", booking.garage_location, booking.garage_city)),
+            ("date", date),
+            ("time", booking.start_time.map(|time| format!(" at {}", time)).unwrap_or_default()),
+        ];
+        let (subject, body) = kind.templates();
+        let message = Message {
+            subject: render(subject, &values),
+            body: render(body, &values),
+        };
+
+        let kind_name = kind.as_str();
+        let mut sent = 0;
+        let mut failures = Vec::new();
+        for channel in &self.channels {
+            let Some(address) = channel.address(&recipient) else {
+                continue;
+            };
+            let channel_name = channel.name();
+
+            if kind == NotificationKind::Reminder {
+                let already_sent = sqlx::query_scalar!(
+                    r#"
+                    SELECT COUNT(*) AS "count!: i64" FROM notifications
+                    WHERE maintenance_id = ? AND kind = ? AND channel = ? AND scheduled_date = ? AND status = 'SENT'
+                    "#,
+                    maintenance_id,
+                    kind_name,
+                    channel_name,
+                    booking.scheduled_date
+                )
+                .fetch_one(pool)
+                .await?;
+                if already_sent > 0 {
+                    continue;
+                }
+            }
+
+            let outcome = channel.send(&address, &message).await;
+            let (status, failure) = match &outcome {
+                Ok(()) => ("SENT", None),
+                Err(err) => {
+                    warn!("{} notification for maintenance {} failed: {}", channel_name, maintenance_id, err);
+                    ("FAILED", Some(err.clone()))
+                }
+            };
+            sqlx::query!(
+                r#"
+                INSERT INTO notifications (maintenance_id, kind, channel, recipient, subject, body, scheduled_date, status, error)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+                maintenance_id,
+                kind_name,
+                channel_name,
+                address,
+                message.subject,
+                message.body,
+                booking.scheduled_date,
+                status,
+                failure
+            )
+            .execute(pool)
+            .await?;
+
+            match outcome {
+                Ok(()) => sent += 1,
+                Err(err) => failures.push(format!("{}: {}", channel_name, err)),
+            }
+        }
+
+        if sent == 0 && !failures.is_empty() {
+            return Err(NotifyError::Undelivered(failures.join("; ")));
+        }
+        Ok(sent)
+    }
+
+    /// Sends the day-before reminder for every open booking tomorrow.
+    /// A booking whose reminder could not be delivered does not stop the
+    /// others; the run fails afterwards so a retry sends the missing ones.
+    pub async fn send_reminders(&self, pool: &SqlitePool, today: NaiveDate) -> Result<usize, NotifyError> {
+        let tomorrow = (today + Duration::days(1)).format(scheduler::DATE_FORMAT).to_string();
+        let due = sqlx::query_scalar!(
+            r#"
+            SELECT id AS "id!" FROM maintenance
+            WHERE date(scheduled_date) = ?
+              AND deleted_at IS NULL
+              AND status IN ('SCHEDULED', 'QUOTED', 'CONFIRMED')
+            ORDER BY id
+            "#,
+            tomorrow
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let mut sent = 0;
+        let mut undelivered = Vec::new();
+        for maintenance_id in due {
+            match self.notify(pool, maintenance_id, NotificationKind::Reminder).await {
+                Ok(count) => sent += count,
+                Err(NotifyError::Undelivered(details)) => {
+                    undelivered.push(format!("maintenance {} ({})", maintenance_id, details))
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        if !undelivered.is_empty() {
+            return Err(NotifyError::Undelivered(undelivered.join(", ")));
+        }
+        Ok(sent)
+    }
+}
+
+/// Which message, if any, a booking event calls for. Moves of date, time
+/// or garage are reschedules; other edits are not worth a message.
+pub fn kind_for_event(event_type: &str, payload: &Value) -> Option<(i64, NotificationKind)> {
+    let data = payload.get("data")?;
+    let maintenance_id = data.get("id")?.as_i64()?;
+
+    if event_type == EventType::MaintenanceCreated.as_str() {
+        return Some((maintenance_id, NotificationKind::Confirmation));
+    }
+    if event_type != EventType::MaintenanceUpdated.as_str() {
+        return None;
+    }
+
+    let previous = payload.get("previous")?;
+    let moved = ["scheduledDate", "startTime", "garageId"]
+        .iter()
+        .any(|field| data.get(field) != previous.get(field));
+    let restored = previous.get("deletedAt").is_some_and(|deleted_at| !deleted_at.is_null());
+    (moved && !restored).then_some((maintenance_id, NotificationKind::Reschedule))
+}
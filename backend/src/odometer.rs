@@ -0,0 +1,266 @@
+use crate::models::odometer::{DueForServiceDTO, DueReason};
+use crate::scheduler::{self, PlanInterval, DATE_FORMAT};
+use chrono::{Days, NaiveDate};
+use sqlx::SqliteConnection;
+
+/// Checks that a reading fits between the readings taken before and after
+/// it, so the odometer never appears to run backwards. Returns a
+/// human-readable reason when it does not.
+pub async fn check_monotonic(
+    conn: &mut SqliteConnection,
+    car_id: i64,
+    recorded_on: NaiveDate,
+    reading_km: i64,
+) -> Result<Option<String>, sqlx::Error> {
+    if reading_km < 0 {
+        return Ok(Some("Odometer reading cannot be negative".to_string()));
+    }
+
+    let date = recorded_on.format(DATE_FORMAT).to_string();
+
+    let previous = sqlx::query_scalar!(
+        r#"
+        SELECT MAX(reading_km) AS "reading_km: i64"
+        FROM odometer_readings
+        WHERE car_id = ? AND recorded_on <= ?
+        "#,
+        car_id,
+        date
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if let Some(previous) = previous.filter(|previous| *previous > reading_km) {
+        return Ok(Some(format!(
+            "Reading of {} km on {} is lower than an earlier reading of {} km",
+            reading_km, date, previous
+        )));
+    }
+
+    let next = sqlx::query_scalar!(
+        r#"
+        SELECT MIN(reading_km) AS "reading_km: i64"
+        FROM odometer_readings
+        WHERE car_id = ? AND recorded_on > ?
+        "#,
+        car_id,
+        date
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if let Some(next) = next.filter(|next| *next < reading_km) {
+        return Ok(Some(format!(
+            "Reading of {} km on {} is higher than a later reading of {} km",
+            reading_km, date, next
+        )));
+    }
+
+    Ok(None)
+}
+
+pub async fn insert_reading(
+    conn: &mut SqliteConnection,
+    car_id: i64,
+    recorded_on: NaiveDate,
+    reading_km: i64,
+    maintenance_id: Option<i64>,
+) -> Result<i64, sqlx::Error> {
+    let date = recorded_on.format(DATE_FORMAT).to_string();
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO odometer_readings (car_id, reading_km, recorded_on, maintenance_id)
+        VALUES (?, ?, ?, ?)
+        "#,
+        car_id,
+        reading_km,
+        date,
+        maintenance_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(result.last_insert_rowid())
+}
+
+/// Highest reading known on or before the given date.
+async fn reading_on(
+    conn: &mut SqliteConnection,
+    car_id: i64,
+    date: &str,
+) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT MAX(reading_km) AS "reading_km: i64"
+        FROM odometer_readings
+        WHERE car_id = ? AND recorded_on <= ?
+        "#,
+        car_id,
+        date
+    )
+    .fetch_one(&mut *conn)
+    .await
+}
+
+/// Lowest, and so earliest, reading recorded after the given date.
+async fn first_reading_after(
+    conn: &mut SqliteConnection,
+    car_id: i64,
+    date: &str,
+) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT MIN(reading_km) AS "reading_km: i64"
+        FROM odometer_readings
+        WHERE car_id = ? AND recorded_on > ?
+        "#,
+        car_id,
+        date
+    )
+    .fetch_one(&mut *conn)
+    .await
+}
+
+/// Combines each active plan of a car with the last completed maintenance
+/// of the same service type and the car's odometer to decide whether the
+/// service is due within `within_days` days or `within_km` kilometres.
+pub async fn due_for_service(
+    conn: &mut SqliteConnection,
+    today: NaiveDate,
+    within_days: i64,
+    within_km: i64,
+    car_id: Option<i64>,
+) -> Result<Vec<DueForServiceDTO>, sqlx::Error> {
+    let plans = sqlx::query!(
+        r#"
+        SELECT
+            maintenance_plans.id AS "plan_id!",
+            cars.id AS "car_id!",
+            cars.make || ' ' || cars.model AS "car_name!: String",
+            cars.license_plate,
+            maintenance_plans.service_type,
+            maintenance_plans.interval_days,
+            maintenance_plans.interval_months,
+            maintenance_plans.interval_km,
+            maintenance_plans.start_date
+        FROM maintenance_plans
+        JOIN cars ON cars.id = maintenance_plans.car_id
+        WHERE maintenance_plans.active = 1
+          AND cars.deleted_at IS NULL
+          AND (?1 IS NULL OR cars.id = ?1)
+        ORDER BY cars.id, maintenance_plans.id
+        "#,
+        car_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let horizon = today
+        .checked_add_days(Days::new(within_days.max(0) as u64))
+        .unwrap_or(today);
+    let mut due = Vec::new();
+
+    for plan in plans {
+        let last_service = sqlx::query!(
+            r#"
+            SELECT
+                maintenance.id AS "id!",
+                date(maintenance.scheduled_date) AS "service_date!: String",
+                (
+                    SELECT MAX(reading_km)
+                    FROM odometer_readings
+                    WHERE odometer_readings.maintenance_id = maintenance.id
+                ) AS "reading_km: i64"
+            FROM maintenance
+            WHERE maintenance.car_id = ?
+              AND maintenance.service_type = ?
+              AND maintenance.status = 'COMPLETED'
+              AND maintenance.deleted_at IS NULL
+            ORDER BY date(maintenance.scheduled_date) DESC, maintenance.id DESC
+            LIMIT 1
+            "#,
+            plan.car_id,
+            plan.service_type
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        let last_service_date = last_service.as_ref().map(|service| service.service_date.clone());
+        let last_service_km = match &last_service {
+            Some(service) => match service.reading_km {
+                Some(km) => Some(km),
+                None => reading_on(&mut *conn, plan.car_id, &service.service_date).await?,
+            },
+            None => None,
+        };
+
+        let current_km = sqlx::query_scalar!(
+            r#"SELECT MAX(reading_km) AS "reading_km: i64" FROM odometer_readings WHERE car_id = ?"#,
+            plan.car_id
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+
+        let interval = PlanInterval {
+            days: plan.interval_days,
+            months: plan.interval_months,
+        };
+        let due_date = match last_service_date.as_deref().and_then(scheduler::parse_date) {
+            Some(last) => interval.advance(last),
+            None if plan.interval_days.is_some() || plan.interval_months.is_some() => {
+                scheduler::parse_date(&plan.start_date)
+            }
+            None => None,
+        };
+
+        // Without a completed service the mileage baseline is the odometer
+        // when the plan started, or its first reading after that. A car with
+        // no readings has no mileage due yet.
+        let baseline_km = match last_service_km {
+            Some(km) => Some(km),
+            None if last_service.is_none() => match reading_on(&mut *conn, plan.car_id, &plan.start_date).await? {
+                Some(km) => Some(km),
+                None => first_reading_after(&mut *conn, plan.car_id, &plan.start_date).await?,
+            },
+            None => None,
+        };
+        let due_km = plan.interval_km.zip(baseline_km).map(|(interval, base)| base + interval);
+
+        let mut reasons = Vec::new();
+        let mut overdue = false;
+        if let Some(due_date) = due_date {
+            if due_date <= horizon {
+                reasons.push(DueReason::Time);
+            }
+            overdue |= due_date < today;
+        }
+        if let (Some(due_km), Some(current_km)) = (due_km, current_km) {
+            if current_km + within_km.max(0) >= due_km {
+                reasons.push(DueReason::Mileage);
+            }
+            overdue |= current_km > due_km;
+        }
+
+        if reasons.is_empty() {
+            continue;
+        }
+
+        due.push(DueForServiceDTO {
+            car_id: plan.car_id,
+            car_name: plan.car_name,
+            license_plate: plan.license_plate,
+            plan_id: plan.plan_id,
+            service_type: plan.service_type,
+            last_service_date,
+            last_service_km,
+            current_km,
+            due_date: due_date.map(|date| date.format(DATE_FORMAT).to_string()),
+            due_km,
+            overdue,
+            reasons,
+        });
+    }
+
+    Ok(due)
+}
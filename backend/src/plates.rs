@@ -0,0 +1,287 @@
+use log::info;
+use sqlx::SqlitePool;
+use std::fmt;
+use std::str::FromStr;
+
+/// Shortest and longest plate, counting letters and digits only.
+const MIN_PLATE_CHARS: usize = 2;
+const MAX_PLATE_CHARS: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Letter,
+    Digit,
+}
+
+/// One run of letters or digits in a plate, `min..=max` long.
+struct Segment(CharClass, usize, usize);
+
+/// Countries whose plate layout is known. A plate is matched on its
+/// letters and digits alone, so `zg1234ab` and `ZG 1234 AB` are both
+/// written in the national style, `ZG 1234-AB`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlateCountry {
+    /// `ZG 1234-AB`
+    Croatia,
+    /// `AB-123-CD`
+    France,
+    /// `AB 123CD`
+    Italy,
+    /// `1234 BCD`
+    Spain,
+    /// `AB12 CDE`
+    UnitedKingdom,
+}
+
+impl PlateCountry {
+    pub const ALL: [PlateCountry; 5] = [
+        PlateCountry::Croatia,
+        PlateCountry::France,
+        PlateCountry::Italy,
+        PlateCountry::Spain,
+        PlateCountry::UnitedKingdom,
+    ];
+
+    /// ISO 3166-1 alpha-2 code.
+    pub fn code(&self) -> &'static str {
+        match self {
+            PlateCountry::Croatia => "HR",
+            PlateCountry::France => "FR",
+            PlateCountry::Italy => "IT",
+            PlateCountry::Spain => "ES",
+            PlateCountry::UnitedKingdom => "GB",
+        }
+    }
+
+    /// The runs a plate is made of and what goes between consecutive runs.
+    fn layout(&self) -> (&'static [Segment], &'static [&'static str]) {
+        use CharClass::{Digit, Letter};
+        match self {
+            PlateCountry::Croatia => (
+                &[Segment(Letter, 2, 2), Segment(Digit, 3, 4), Segment(Letter, 1, 2)],
+                &[" ", "-"],
+            ),
+            PlateCountry::France => (
+                &[Segment(Letter, 2, 2), Segment(Digit, 3, 3), Segment(Letter, 2, 2)],
+                &["-", "-"],
+            ),
+            PlateCountry::Italy => (
+                &[Segment(Letter, 2, 2), Segment(Digit, 3, 3), Segment(Letter, 2, 2)],
+                &[" ", ""],
+            ),
+            PlateCountry::Spain => (
+                &[Segment(Digit, 4, 4), Segment(Letter, 3, 3)],
+                &[" "],
+            ),
+            PlateCountry::UnitedKingdom => (
+                &[Segment(Letter, 2, 2), Segment(Digit, 2, 2), Segment(Letter, 3, 3)],
+                &["", " "],
+            ),
+        }
+    }
+
+    /// Writes the plate in this country's style, or `None` if it does not
+    /// fit the country's layout.
+    fn format(&self, key: &str) -> Option<String> {
+        let (segments, separators) = self.layout();
+        let runs = runs(key);
+        if runs.len() != segments.len() {
+            return None;
+        }
+
+        let mut plate = String::with_capacity(key.len() + separators.len());
+        for (index, ((class, run), Segment(expected, min, max))) in runs.iter().zip(segments).enumerate() {
+            if class != expected || !(*min..=*max).contains(&run.chars().count()) {
+                return None;
+            }
+            if index > 0 {
+                plate.push_str(separators[index - 1]);
+            }
+            plate.push_str(run);
+        }
+        Some(plate)
+    }
+}
+
+impl fmt::Display for PlateCountry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl FromStr for PlateCountry {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        // UK is the common name for GB plates.
+        let code = if value.eq_ignore_ascii_case("UK") { "GB" } else { value };
+        let codes: Vec<&str> = PlateCountry::ALL.iter().map(PlateCountry::code).collect();
+        PlateCountry::ALL
+            .into_iter()
+            .find(|country| country.code().eq_ignore_ascii_case(code))
+            .ok_or_else(|| format!("Unknown plate country '{}'; expected one of {}", value, codes.join(", ")))
+    }
+}
+
+/// Splits a key into runs of letters and runs of digits.
+fn runs(key: &str) -> Vec<(CharClass, String)> {
+    let mut runs: Vec<(CharClass, String)> = Vec::new();
+    for c in key.chars() {
+        let class = if c.is_numeric() { CharClass::Digit } else { CharClass::Letter };
+        match runs.last_mut() {
+            Some((last, run)) if *last == class => run.push(c),
+            _ => runs.push((class, c.to_string())),
+        }
+    }
+    runs
+}
+
+/// What plates are compared by: letters and digits only, in upper case.
+/// `AB-123-CD`, `ab 123 cd` and `AB123CD` share the key `AB123CD`.
+pub fn plate_key(plate: &str) -> String {
+    plate
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_uppercase)
+        .collect()
+}
+
+/// Upper-cases the plate and rewrites its separators. With `country` the
+/// plate must follow that country's layout. Otherwise `default_country`'s
+/// layout is used when the plate fits it, and anything else keeps the
+/// groups it was typed with, joined by hyphens.
+pub fn normalize(
+    plate: &str,
+    country: Option<PlateCountry>,
+    default_country: Option<PlateCountry>,
+) -> Result<String, String> {
+    let key = plate_key(plate);
+    let length = key.chars().count();
+    if !(MIN_PLATE_CHARS..=MAX_PLATE_CHARS).contains(&length) {
+        return Err(format!(
+            "licensePlate '{}' must have between {} and {} letters and digits",
+            plate.trim(),
+            MIN_PLATE_CHARS,
+            MAX_PLATE_CHARS
+        ));
+    }
+
+    if let Some(country) = country {
+        return country
+            .format(&key)
+            .ok_or_else(|| format!("licensePlate '{}' is not a valid {} plate", plate.trim(), country));
+    }
+    if let Some(formatted) = default_country.and_then(|country| country.format(&key)) {
+        return Ok(formatted);
+    }
+
+    Ok(plate
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|group| !group.is_empty())
+        .map(str::to_uppercase)
+        .collect::<Vec<_>>()
+        .join("-"))
+}
+
+/// Brings every stored `plate_key` in line with `plate_key`. The migration
+/// that added the column could only approximate it in SQL, missing other
+/// separators and non-ASCII letters. As there, a car whose key is already
+/// taken by an older car gets its id appended.
+pub async fn rekey_cars(pool: &SqlitePool) -> Result<usize, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    let cars = sqlx::query!(r#"SELECT id AS "id!", license_plate, plate_key FROM cars ORDER BY id"#)
+        .fetch_all(&mut *transaction)
+        .await?;
+
+    let mut rekeyed = 0;
+    for car in cars {
+        let key = plate_key(&car.license_plate);
+        let duplicate = format!("{}#{}", key, car.id);
+        let stored = car.plate_key.as_deref();
+        if stored == Some(key.as_str()) || stored == Some(duplicate.as_str()) {
+            continue;
+        }
+
+        let taken = sqlx::query_scalar!(
+            r#"SELECT id AS "id!" FROM cars WHERE plate_key = ? AND id != ?"#,
+            key,
+            car.id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .is_some();
+        let new_key = if taken { duplicate } else { key };
+        sqlx::query!("UPDATE cars SET plate_key = ? WHERE id = ?", new_key, car.id)
+            .execute(&mut *transaction)
+            .await?;
+        rekeyed += 1;
+    }
+
+    transaction.commit().await?;
+    if rekeyed > 0 {
+        info!("Re-keyed {} license plates", rekeyed);
+    }
+    Ok(rekeyed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_each_country_layout() {
+        let cases = [
+            (PlateCountry::Croatia, "zg1234ab", "ZG 1234-AB"),
+            (PlateCountry::Croatia, "ZG 123 A", "ZG 123-A"),
+            (PlateCountry::France, "ab 123 cd", "AB-123-CD"),
+            (PlateCountry::Italy, "AB-123-CD", "AB 123CD"),
+            (PlateCountry::Spain, "1234bcd", "1234 BCD"),
+            (PlateCountry::UnitedKingdom, "ab12cde", "AB12 CDE"),
+        ];
+        for (country, plate, expected) in cases {
+            assert_eq!(normalize(plate, Some(country), None).unwrap(), expected, "{} {}", country, plate);
+        }
+    }
+
+    #[test]
+    fn rejects_plates_that_do_not_fit_the_country() {
+        assert!(normalize("AB-123-CD", Some(PlateCountry::Spain), None).is_err());
+        assert!(normalize("AB1234CD", Some(PlateCountry::France), None).is_err());
+        assert!(normalize("1234BC", Some(PlateCountry::Spain), None).is_err());
+    }
+
+    #[test]
+    fn uses_the_default_country_when_the_plate_fits() {
+        assert_eq!(normalize("ab123cd", None, Some(PlateCountry::France)).unwrap(), "AB-123-CD");
+    }
+
+    #[test]
+    fn keeps_typed_groups_when_the_default_country_does_not_fit() {
+        assert_eq!(normalize("b 1234 xy", None, Some(PlateCountry::France)).unwrap(), "B-1234-XY");
+        assert_eq!(normalize("m.ab 123", None, None).unwrap(), "M-AB-123");
+    }
+
+    #[test]
+    fn checks_the_plate_length() {
+        assert!(normalize("A", None, None).is_err());
+        assert!(normalize("ABCDEFGHIJKLM", None, None).is_err());
+        assert!(normalize("- -", None, None).is_err());
+    }
+
+    #[test]
+    fn keys_ignore_separators_and_case() {
+        assert_eq!(plate_key("AB-123-CD"), "AB123CD");
+        assert_eq!(plate_key("ab 123 cd"), "AB123CD");
+        assert_eq!(plate_key(" ab.123_cd "), "AB123CD");
+        assert_eq!(plate_key("zg 1234-šb"), "ZG1234ŠB");
+    }
+
+    #[test]
+    fn parses_country_codes() {
+        assert_eq!("hr".parse::<PlateCountry>(), Ok(PlateCountry::Croatia));
+        assert_eq!(" GB ".parse::<PlateCountry>(), Ok(PlateCountry::UnitedKingdom));
+        assert_eq!("uk".parse::<PlateCountry>(), Ok(PlateCountry::UnitedKingdom));
+        assert!("DE".parse::<PlateCountry>().is_err());
+    }
+}
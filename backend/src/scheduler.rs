@@ -0,0 +1,600 @@
+use crate::audit::{self, AuditAction, AuditResource};
+use crate::models::garage::{MovedBookingDTO, RescheduleStrategy, UnmovedBookingDTO};
+use crate::models::maintenance::MaintenanceStatus;
+use crate::models::odometer::DueReason;
+use crate::models::maintenance_plan::{
+    MaterializeReportDTO, MaterializedMaintenanceDTO, SkippedOccurrenceDTO,
+};
+use crate::{inventory, odometer, slots};
+use chrono::{Days, Months, NaiveDate};
+use log::warn;
+use sqlx::{SqliteConnection, SqlitePool};
+
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+pub const DEFAULT_HORIZON_DAYS: i64 = 60;
+pub const MAX_HORIZON_DAYS: i64 = 366;
+
+/// How far past its due date an occurrence may slip when the preferred
+/// garage is full before the scheduler gives up until the next run.
+pub const MAX_SHIFT_DAYS: u64 = 30;
+const SCHEDULER_ACTOR: &str = "scheduler";
+
+#[derive(Debug, Clone, Copy)]
+pub struct PlanInterval {
+    pub days: Option<i64>,
+    pub months: Option<i64>,
+}
+
+impl PlanInterval {
+    pub fn advance(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match (self.days, self.months) {
+            (Some(days), _) if days > 0 => date.checked_add_days(Days::new(days as u64)),
+            (_, Some(months)) if months > 0 => date.checked_add_months(Months::new(months as u32)),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), DATE_FORMAT).ok()
+}
+
+/// Parses an inclusive `startDate`/`endDate` range of at most `max_days`
+/// days, explaining what is wrong otherwise.
+pub fn parse_date_range(start: &str, end: &str, max_days: i64) -> Result<(NaiveDate, NaiveDate), String> {
+    let (Some(start_date), Some(end_date)) = (parse_date(start), parse_date(end)) else {
+        return Err("startDate and endDate must be formatted as YYYY-MM-DD".to_string());
+    };
+    if end_date < start_date {
+        return Err("endDate must not be before startDate".to_string());
+    }
+    let days = (end_date - start_date).num_days() + 1;
+    if days > max_days {
+        return Err(format!(
+            "Date range covers {} days; at most {} are allowed",
+            days, max_days
+        ));
+    }
+    Ok((start_date, end_date))
+}
+
+/// Every day of an inclusive range as `YYYY-MM-DD`, the axis of the per-day
+/// reports.
+pub fn report_days(start: NaiveDate, end: NaiveDate) -> impl Iterator<Item = String> {
+    start
+        .iter_days()
+        .take_while(move |date| *date <= end)
+        .map(|date| date.format(DATE_FORMAT).to_string())
+}
+
+/// Books every occurrence of the active time-based plans that falls due
+/// before `today + horizon_days`. Occurrences already in the past are
+/// collapsed into a single booking as soon as possible. Mileage plans are
+/// booked once the odometer says they are due.
+pub async fn materialize_plans(
+    pool: &SqlitePool,
+    today: NaiveDate,
+    horizon_days: i64,
+) -> Result<MaterializeReportDTO, sqlx::Error> {
+    let horizon_end = today
+        .checked_add_days(Days::new(horizon_days.clamp(0, MAX_HORIZON_DAYS) as u64))
+        .unwrap_or(today);
+    let horizon = horizon_end.format(DATE_FORMAT).to_string();
+
+    let plans = sqlx::query!(
+        r#"
+        SELECT
+            maintenance_plans.id AS "id!",
+            maintenance_plans.car_id,
+            maintenance_plans.garage_id,
+            maintenance_plans.service_type,
+            maintenance_plans.interval_days,
+            maintenance_plans.interval_months,
+            maintenance_plans.next_due_date
+        FROM maintenance_plans
+        JOIN cars ON cars.id = maintenance_plans.car_id
+        JOIN garages ON garages.id = maintenance_plans.garage_id
+        WHERE maintenance_plans.active = 1
+          AND cars.deleted_at IS NULL
+          AND garages.deleted_at IS NULL
+          AND (maintenance_plans.interval_days IS NOT NULL OR maintenance_plans.interval_months IS NOT NULL)
+          AND maintenance_plans.next_due_date <= ?
+        ORDER BY maintenance_plans.next_due_date, maintenance_plans.id
+        "#,
+        horizon
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut report = MaterializeReportDTO::default();
+
+    for plan in plans {
+        let interval = PlanInterval {
+            days: plan.interval_days,
+            months: plan.interval_months,
+        };
+        let Some(mut due) = parse_date(&plan.next_due_date) else {
+            warn!("Plan {} has an invalid next due date {}", plan.id, plan.next_due_date);
+            continue;
+        };
+
+        let mut transaction = pool.begin().await?;
+
+        // Another run may have booked this plan since it was read above. The
+        // check is written as an update so the transaction takes the write
+        // lock first and waits for that run rather than failing on commit.
+        let unchanged = sqlx::query!(
+            r#"
+            UPDATE maintenance_plans
+            SET next_due_date = next_due_date
+            WHERE id = ? AND active = 1 AND next_due_date = ?
+            "#,
+            plan.id,
+            plan.next_due_date
+        )
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+        if unchanged == 0 {
+            continue;
+        }
+
+        let Some(hours) = slots::garage_hours(&mut transaction, plan.garage_id).await? else {
+            continue;
+        };
+
+        let mut created = Vec::new();
+        let mut skipped = Vec::new();
+
+        while due <= horizon_end {
+            let target = due.max(today);
+            let due_date = due.format(DATE_FORMAT).to_string();
+
+            let Some(placement) =
+                slots::find_placement(&mut transaction, &hours, target, MAX_SHIFT_DAYS, &plan.service_type)
+                    .await?
+            else {
+                skipped.push(SkippedOccurrenceDTO {
+                    plan_id: plan.id,
+                    due_date,
+                    reason: format!("Garage {} is fully booked for {} days", plan.garage_id, MAX_SHIFT_DAYS),
+                });
+                break;
+            };
+
+            let maintenance_id =
+                book_plan(&mut transaction, plan.id, plan.car_id, plan.garage_id, &plan.service_type, &placement)
+                    .await?;
+            let scheduled_date = placement.date.format(DATE_FORMAT).to_string();
+
+            created.push(MaterializedMaintenanceDTO {
+                maintenance_id,
+                plan_id: plan.id,
+                car_id: plan.car_id,
+                garage_id: plan.garage_id,
+                service_type: plan.service_type.clone(),
+                due_date,
+                scheduled_date,
+            });
+
+            // Advance from the nominal due date so a shifted booking does not
+            // push every later occurrence back as well.
+            let mut next = interval.advance(due);
+            while let Some(candidate) = next {
+                if candidate > target {
+                    break;
+                }
+                next = interval.advance(candidate);
+            }
+            match next {
+                Some(next) => due = next,
+                None => break,
+            }
+        }
+
+        // Only moves the plan on from the date this run started from, so a
+        // concurrent run that got there first rolls this one back.
+        let next_due_date = due.format(DATE_FORMAT).to_string();
+        let advanced = sqlx::query!(
+            "UPDATE maintenance_plans SET next_due_date = ? WHERE id = ? AND next_due_date = ?",
+            next_due_date,
+            plan.id,
+            plan.next_due_date
+        )
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+        if advanced == 0 {
+            transaction.rollback().await?;
+            continue;
+        }
+
+        transaction.commit().await?;
+        report.created.extend(created);
+        report.skipped.extend(skipped);
+    }
+
+    materialize_mileage_plans(pool, today, &mut report).await?;
+
+    Ok(report)
+}
+
+/// Creates the booking for one occurrence of a plan where `placement` put
+/// it, reserving its parts and recording who made it.
+async fn book_plan(
+    conn: &mut SqliteConnection,
+    plan_id: i64,
+    car_id: i64,
+    garage_id: i64,
+    service_type: &str,
+    placement: &slots::Placement,
+) -> Result<i64, sqlx::Error> {
+    let car_id = car_id.to_string();
+    let garage_id = garage_id.to_string();
+    let scheduled_date = placement.date.format(DATE_FORMAT).to_string();
+    let status = MaintenanceStatus::Scheduled;
+
+    let maintenance_id = sqlx::query!(
+        r#"
+        INSERT INTO maintenance
+            (car_id, garage_id, service_type, scheduled_date, status, plan_id, bay_id, start_time, duration_minutes)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        car_id,
+        garage_id,
+        service_type,
+        scheduled_date,
+        status,
+        plan_id,
+        placement.bay_id,
+        placement.start_time,
+        placement.duration_minutes
+    )
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO maintenance_status_history (maintenance_id, from_status, to_status, note)
+        VALUES (?, NULL, ?, 'Created from maintenance plan')
+        "#,
+        maintenance_id,
+        status
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    inventory::sync_reservations(&mut *conn, maintenance_id).await?;
+
+    audit::record_change(
+        &mut *conn,
+        SCHEDULER_ACTOR,
+        AuditResource::Maintenance,
+        &maintenance_id.to_string(),
+        AuditAction::Create,
+        None,
+    )
+    .await?;
+
+    Ok(maintenance_id)
+}
+
+/// Books plans with a mileage interval as soon as the odometer shows them
+/// due, unless the plan already has a booking that is still open.
+async fn materialize_mileage_plans(
+    pool: &SqlitePool,
+    today: NaiveDate,
+    report: &mut MaterializeReportDTO,
+) -> Result<(), sqlx::Error> {
+    let plans = sqlx::query!(
+        r#"
+        SELECT
+            maintenance_plans.id AS "id!",
+            maintenance_plans.car_id,
+            maintenance_plans.garage_id,
+            maintenance_plans.service_type
+        FROM maintenance_plans
+        JOIN cars ON cars.id = maintenance_plans.car_id
+        JOIN garages ON garages.id = maintenance_plans.garage_id
+        WHERE maintenance_plans.active = 1
+          AND cars.deleted_at IS NULL
+          AND garages.deleted_at IS NULL
+          AND maintenance_plans.interval_km IS NOT NULL
+        ORDER BY maintenance_plans.id
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let due_date = today.format(DATE_FORMAT).to_string();
+    for plan in plans {
+        let mut transaction = pool.begin().await?;
+
+        // Written as an update so concurrent runs take turns on the plan
+        // and the second one sees the first one's booking.
+        let active = sqlx::query!(
+            "UPDATE maintenance_plans SET active = active WHERE id = ? AND active = 1",
+            plan.id
+        )
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+        if active == 0 {
+            continue;
+        }
+
+        let open = sqlx::query_scalar!(
+            r#"
+            SELECT id AS "id!"
+            FROM maintenance
+            WHERE plan_id = ?
+              AND status NOT IN ('COMPLETED', 'CANCELLED', 'NO_SHOW')
+              AND deleted_at IS NULL
+            LIMIT 1
+            "#,
+            plan.id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?;
+        if open.is_some() {
+            continue;
+        }
+
+        let due = odometer::due_for_service(&mut transaction, today, 0, 0, Some(plan.car_id)).await?;
+        let mileage_due = due
+            .iter()
+            .any(|entry| entry.plan_id == plan.id && entry.reasons.contains(&DueReason::Mileage));
+        if !mileage_due {
+            continue;
+        }
+
+        let Some(hours) = slots::garage_hours(&mut transaction, plan.garage_id).await? else {
+            continue;
+        };
+        let Some(placement) =
+            slots::find_placement(&mut transaction, &hours, today, MAX_SHIFT_DAYS, &plan.service_type).await?
+        else {
+            report.skipped.push(SkippedOccurrenceDTO {
+                plan_id: plan.id,
+                due_date: due_date.clone(),
+                reason: format!("Garage {} is fully booked for {} days", plan.garage_id, MAX_SHIFT_DAYS),
+            });
+            continue;
+        };
+
+        let maintenance_id =
+            book_plan(&mut transaction, plan.id, plan.car_id, plan.garage_id, &plan.service_type, &placement).await?;
+        transaction.commit().await?;
+
+        report.created.push(MaterializedMaintenanceDTO {
+            maintenance_id,
+            plan_id: plan.id,
+            car_id: plan.car_id,
+            garage_id: plan.garage_id,
+            service_type: plan.service_type,
+            due_date: due_date.clone(),
+            scheduled_date: placement.date.format(DATE_FORMAT).to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Moves every open booking of a garage on `date` elsewhere, following
+/// `strategy`. Runs on the caller's connection so the whole move can be
+/// committed or rolled back at once.
+pub async fn reschedule_garage_day(
+    conn: &mut SqliteConnection,
+    actor: &str,
+    garage_id: i64,
+    date: NaiveDate,
+    strategy: RescheduleStrategy,
+    max_days_ahead: u64,
+) -> Result<(Vec<MovedBookingDTO>, Vec<UnmovedBookingDTO>), sqlx::Error> {
+    let from_date = date.format(DATE_FORMAT).to_string();
+
+    let bookings = sqlx::query!(
+        r#"
+        SELECT id AS "id!", car_id, scheduled_date, service_type
+        FROM maintenance
+        WHERE garage_id = ?
+          AND date(scheduled_date) = ?
+          AND status IN ('SCHEDULED', 'QUOTED', 'CONFIRMED')
+          AND deleted_at IS NULL
+        ORDER BY id
+        "#,
+        garage_id,
+        from_date
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut moved = Vec::new();
+    let mut unmoved = Vec::new();
+
+    for booking in bookings {
+        let candidates: Vec<(i64, String)> = match strategy {
+            RescheduleStrategy::NextAvailableDate => sqlx::query!(
+                "SELECT id, name FROM garages WHERE id = ? AND deleted_at IS NULL",
+                garage_id
+            )
+            .fetch_all(&mut *conn)
+            .await?
+            .into_iter()
+            .map(|garage| (garage.id, garage.name))
+            .collect(),
+            RescheduleStrategy::AlternativeGarage => sqlx::query!(
+                r#"
+                SELECT garages.id, garages.name
+                FROM car_garages
+                JOIN garages ON garages.id = car_garages.garage_id
+                WHERE car_garages.car_id = ?
+                  AND garages.id != ?
+                  AND garages.deleted_at IS NULL
+                ORDER BY garages.id
+                "#,
+                booking.car_id,
+                garage_id
+            )
+            .fetch_all(&mut *conn)
+            .await?
+            .into_iter()
+            .map(|garage| (garage.id, garage.name))
+            .collect(),
+        };
+
+        // Staying put means the closed day itself is off the table; another
+        // garage may still take the car on the original date.
+        let earliest = match strategy {
+            RescheduleStrategy::NextAvailableDate => date.checked_add_days(Days::new(1)),
+            RescheduleStrategy::AlternativeGarage => Some(date),
+        };
+        let Some(earliest) = earliest else {
+            continue;
+        };
+
+        let mut best: Option<(slots::Placement, i64, String)> = None;
+        for (candidate_id, candidate_name) in candidates {
+            let Some(hours) = slots::garage_hours(&mut *conn, candidate_id).await? else {
+                continue;
+            };
+            if let Some(placement) =
+                slots::find_placement(&mut *conn, &hours, earliest, max_days_ahead, &booking.service_type).await?
+            {
+                if best.as_ref().is_none_or(|(best, _, _)| placement.date < best.date) {
+                    best = Some((placement, candidate_id, candidate_name));
+                }
+            }
+        }
+
+        let Some((placement, to_garage_id, to_garage_name)) = best else {
+            unmoved.push(UnmovedBookingDTO {
+                maintenance_id: booking.id,
+                car_id: booking.car_id,
+                reason: match strategy {
+                    RescheduleStrategy::NextAvailableDate => format!(
+                        "Garage {} has no capacity in the next {} days",
+                        garage_id, max_days_ahead
+                    ),
+                    RescheduleStrategy::AlternativeGarage => format!(
+                        "No other garage of this car has capacity in the next {} days",
+                        max_days_ahead
+                    ),
+                },
+            });
+            continue;
+        };
+
+        let resource_id = booking.id.to_string();
+        let before = audit::snapshot(&mut *conn, AuditResource::Maintenance, &resource_id).await?;
+        let to_garage = to_garage_id.to_string();
+        let to_date = placement.date.format(DATE_FORMAT).to_string();
+
+        // The assigned mechanic is released: they may not work at the new
+        // garage or be free on the new day.
+        sqlx::query!(
+            r#"
+            UPDATE maintenance
+            SET garage_id = ?, scheduled_date = ?, bay_id = ?, start_time = ?, duration_minutes = ?, mechanic_id = NULL
+            WHERE id = ?
+            "#,
+            to_garage,
+            to_date,
+            placement.bay_id,
+            placement.start_time,
+            placement.duration_minutes,
+            booking.id
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        inventory::sync_reservations(&mut *conn, booking.id).await?;
+
+        audit::record_change(
+            &mut *conn,
+            actor,
+            AuditResource::Maintenance,
+            &resource_id,
+            AuditAction::Update,
+            before,
+        )
+        .await?;
+
+        moved.push(MovedBookingDTO {
+            maintenance_id: booking.id,
+            car_id: booking.car_id,
+            from_garage_id: garage_id,
+            from_date: booking.scheduled_date,
+            to_garage_id,
+            to_garage_name,
+            to_date,
+        });
+    }
+
+    Ok((moved, unmoved))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        parse_date(value).unwrap()
+    }
+
+    #[test]
+    fn parses_a_single_day_range() {
+        assert_eq!(
+            parse_date_range("2025-03-01", "2025-03-01", 1),
+            Ok((date("2025-03-01"), date("2025-03-01")))
+        );
+    }
+
+    #[test]
+    fn accepts_a_range_of_exactly_the_limit() {
+        assert!(parse_date_range("2025-01-01", "2025-01-31", 31).is_ok());
+    }
+
+    #[test]
+    fn rejects_a_range_over_the_limit() {
+        assert_eq!(
+            parse_date_range("2025-01-01", "2025-02-01", 31),
+            Err("Date range covers 32 days; at most 31 are allowed".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_an_inverted_range() {
+        assert_eq!(
+            parse_date_range("2025-03-02", "2025-03-01", 31),
+            Err("endDate must not be before startDate".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_dates() {
+        let malformed = [("01/03/2025", "2025-03-02"), ("2025-03-01", "tomorrow"), ("2025-02-30", "2025-03-01")];
+        for (start, end) in malformed {
+            assert!(parse_date_range(start, end, 31).is_err(), "{} to {} was accepted", start, end);
+        }
+    }
+
+    #[test]
+    fn lists_one_day_for_a_single_day_range() {
+        assert_eq!(report_days(date("2025-03-01"), date("2025-03-01")).collect::<Vec<_>>(), ["2025-03-01"]);
+    }
+
+    #[test]
+    fn lists_every_day_across_month_ends() {
+        assert_eq!(
+            report_days(date("2024-02-28"), date("2024-03-01")).collect::<Vec<_>>(),
+            ["2024-02-28", "2024-02-29", "2024-03-01"]
+        );
+    }
+
+    #[test]
+    fn lists_nothing_for_an_inverted_range() {
+        assert_eq!(report_days(date("2025-03-02"), date("2025-03-01")).count(), 0);
+    }
+}
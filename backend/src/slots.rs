@@ -0,0 +1,559 @@
+use crate::scheduler::DATE_FORMAT;
+use chrono::{Days, NaiveDate, NaiveTime, Timelike};
+use sqlx::SqliteConnection;
+
+pub const TIME_FORMAT: &str = "%H:%M";
+
+/// Booking rules of a garage. Garages with active bays are planned in
+/// bay time slots; the others keep the plain "N bookings per day" capacity.
+#[derive(Debug, Clone)]
+pub struct GarageHours {
+    pub garage_id: i64,
+    pub capacity: i64,
+    pub opening: i64,
+    pub closing: i64,
+    pub slot_minutes: i64,
+    pub bays: Vec<i64>,
+}
+
+/// Where a booking ends up: always a day, plus a bay and start time when the
+/// garage is planned in slots.
+#[derive(Debug, Clone)]
+pub struct Placement {
+    pub date: NaiveDate,
+    pub bay_id: Option<i64>,
+    pub start_time: Option<String>,
+    pub duration_minutes: i64,
+}
+
+/// Why a booking cannot be placed on the requested day.
+#[derive(Debug, Clone)]
+pub enum SlotError {
+    /// The requested start time is malformed or outside the slot grid.
+    Window(String),
+    /// The window is valid but no (requested) bay is free.
+    Taken(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BookedInterval {
+    bay_id: i64,
+    start: i64,
+    end: i64,
+}
+
+/// Minutes since midnight for an `HH:MM` time.
+pub fn parse_time(value: &str) -> Option<i64> {
+    NaiveTime::parse_from_str(value.trim(), TIME_FORMAT)
+        .ok()
+        .map(|time| i64::from(time.hour() * 60 + time.minute()))
+}
+
+pub fn format_time(minutes: i64) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+impl GarageHours {
+    pub fn uses_bays(&self) -> bool {
+        !self.bays.is_empty()
+    }
+
+    pub fn slots_per_day(&self) -> i64 {
+        if self.slot_minutes <= 0 {
+            return 0;
+        }
+        (self.closing - self.opening).max(0) / self.slot_minutes
+    }
+
+    /// Number of slots a booking of the given length occupies.
+    pub fn units_for(&self, duration_minutes: i64) -> i64 {
+        if self.slot_minutes <= 0 {
+            return 1;
+        }
+        ((duration_minutes.max(1) + self.slot_minutes - 1) / self.slot_minutes).max(1)
+    }
+
+    /// Capacity of one day, in slots for bay garages and in bookings otherwise.
+    pub fn daily_capacity(&self) -> i64 {
+        if self.uses_bays() {
+            self.bays.len() as i64 * self.slots_per_day()
+        } else {
+            self.capacity
+        }
+    }
+
+    /// Explains why a booking cannot start at `start` for `duration` minutes,
+    /// or returns `None` when the window fits the opening hours and slot grid.
+    pub fn window_error(&self, start: i64, duration: i64) -> Option<String> {
+        if start < self.opening || start + duration > self.closing {
+            return Some(format!(
+                "Booking from {} for {} minutes falls outside opening hours {}-{}",
+                format_time(start),
+                duration,
+                format_time(self.opening),
+                format_time(self.closing)
+            ));
+        }
+        if self.slot_minutes > 0 && (start - self.opening) % self.slot_minutes != 0 {
+            return Some(format!(
+                "Start time {} is not aligned to {}-minute slots from {}",
+                format_time(start),
+                self.slot_minutes,
+                format_time(self.opening)
+            ));
+        }
+        None
+    }
+}
+
+pub async fn garage_hours(
+    conn: &mut SqliteConnection,
+    garage_id: i64,
+) -> Result<Option<GarageHours>, sqlx::Error> {
+    let Some(garage) = sqlx::query!(
+        r#"
+        SELECT id, capacity, opening_time, closing_time, slot_minutes
+        FROM garages
+        WHERE id = ? AND deleted_at IS NULL
+        "#,
+        garage_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let bays = sqlx::query_scalar!(
+        r#"SELECT id AS "id!" FROM bays WHERE garage_id = ? AND active = 1 ORDER BY id"#,
+        garage_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Some(GarageHours {
+        garage_id: garage.id,
+        capacity: garage.capacity,
+        opening: parse_time(&garage.opening_time).unwrap_or(8 * 60),
+        closing: parse_time(&garage.closing_time).unwrap_or(18 * 60),
+        slot_minutes: garage.slot_minutes,
+        bays,
+    }))
+}
+
+/// How long a service keeps a bay busy. Unknown service types take one slot.
+pub async fn duration_for(
+    conn: &mut SqliteConnection,
+    service_type: &str,
+    slot_minutes: i64,
+) -> Result<i64, sqlx::Error> {
+    let duration = sqlx::query_scalar!(
+        "SELECT duration_minutes FROM service_types WHERE name = ?",
+        service_type
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(duration.unwrap_or(slot_minutes).max(1))
+}
+
+async fn booked_intervals(
+    conn: &mut SqliteConnection,
+    hours: &GarageHours,
+    date: NaiveDate,
+    exclude_maintenance_id: Option<i64>,
+) -> Result<Vec<BookedInterval>, sqlx::Error> {
+    let date = date.format(DATE_FORMAT).to_string();
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT bay_id AS "bay_id!", start_time AS "start_time!", duration_minutes
+        FROM maintenance
+        WHERE garage_id = ?
+          AND date(scheduled_date) = ?
+          AND bay_id IS NOT NULL
+          AND start_time IS NOT NULL
+          AND status != 'CANCELLED'
+          AND deleted_at IS NULL
+          AND (?3 IS NULL OR id != ?3)
+        "#,
+        hours.garage_id,
+        date,
+        exclude_maintenance_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            let start = parse_time(&row.start_time)?;
+            let duration = row.duration_minutes.unwrap_or(hours.slot_minutes);
+            Some(BookedInterval {
+                bay_id: row.bay_id,
+                start,
+                end: start + duration,
+            })
+        })
+        .collect())
+}
+
+fn bay_is_free(booked: &[BookedInterval], bay_id: i64, start: i64, end: i64) -> bool {
+    !booked
+        .iter()
+        .any(|interval| interval.bay_id == bay_id && interval.start < end && start < interval.end)
+}
+
+/// A bay that is free for the whole window, preferring `preferred_bay` when
+/// given. Only active bays of the garage are considered.
+pub async fn find_free_bay(
+    conn: &mut SqliteConnection,
+    hours: &GarageHours,
+    date: NaiveDate,
+    start: i64,
+    duration: i64,
+    preferred_bay: Option<i64>,
+    exclude_maintenance_id: Option<i64>,
+) -> Result<Option<i64>, sqlx::Error> {
+    let booked = booked_intervals(conn, hours, date, exclude_maintenance_id).await?;
+    let end = start + duration;
+
+    if let Some(bay_id) = preferred_bay {
+        let free = hours.bays.contains(&bay_id) && bay_is_free(&booked, bay_id, start, end);
+        return Ok(free.then_some(bay_id));
+    }
+
+    Ok(hours
+        .bays
+        .iter()
+        .copied()
+        .find(|bay_id| bay_is_free(&booked, *bay_id, start, end)))
+}
+
+/// Every start time of the day with the bays that could take a booking of
+/// `duration` minutes at that time.
+pub async fn free_slots(
+    conn: &mut SqliteConnection,
+    hours: &GarageHours,
+    date: NaiveDate,
+    duration: i64,
+    exclude_maintenance_id: Option<i64>,
+) -> Result<Vec<(i64, Vec<i64>)>, sqlx::Error> {
+    let booked = booked_intervals(conn, hours, date, exclude_maintenance_id).await?;
+    Ok(open_slots(hours, &booked, duration))
+}
+
+/// The slot grid of one day with the bays free for `duration` minutes at
+/// each start, leaving out starts where no bay is free.
+fn open_slots(hours: &GarageHours, booked: &[BookedInterval], duration: i64) -> Vec<(i64, Vec<i64>)> {
+    let mut slots = Vec::new();
+
+    if hours.slot_minutes <= 0 {
+        return slots;
+    }
+
+    let mut start = hours.opening;
+    while start + duration <= hours.closing {
+        let bays: Vec<i64> = hours
+            .bays
+            .iter()
+            .copied()
+            .filter(|bay_id| bay_is_free(booked, *bay_id, start, start + duration))
+            .collect();
+        if !bays.is_empty() {
+            slots.push((start, bays));
+        }
+        start += hours.slot_minutes;
+    }
+
+    slots
+}
+
+/// Slot units already taken on a day: slots for bay garages, bookings
+/// otherwise.
+pub async fn used_units(
+    conn: &mut SqliteConnection,
+    hours: &GarageHours,
+    date: NaiveDate,
+) -> Result<i64, sqlx::Error> {
+    let date = date.format(DATE_FORMAT).to_string();
+
+    let durations = sqlx::query_scalar!(
+        r#"
+        SELECT duration_minutes
+        FROM maintenance
+        WHERE garage_id = ?
+          AND date(scheduled_date) = ?
+          AND status != 'CANCELLED'
+          AND deleted_at IS NULL
+        "#,
+        hours.garage_id,
+        date
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    if !hours.uses_bays() {
+        return Ok(durations.len() as i64);
+    }
+
+    Ok(durations
+        .into_iter()
+        .map(|duration| hours.units_for(duration.unwrap_or(hours.slot_minutes)))
+        .sum())
+}
+
+/// Earliest placement for a service on or after `from`, looking at most
+/// `max_days_ahead` days ahead.
+pub async fn find_placement(
+    conn: &mut SqliteConnection,
+    hours: &GarageHours,
+    from: NaiveDate,
+    max_days_ahead: u64,
+    service_type: &str,
+) -> Result<Option<Placement>, sqlx::Error> {
+    let duration = duration_for(&mut *conn, service_type, hours.slot_minutes).await?;
+
+    for shift in 0..=max_days_ahead {
+        let Some(date) = from.checked_add_days(Days::new(shift)) else {
+            break;
+        };
+
+        if hours.uses_bays() {
+            let slots = free_slots(&mut *conn, hours, date, duration, None).await?;
+            if let Some((start, bays)) = slots.into_iter().next() {
+                return Ok(Some(Placement {
+                    date,
+                    bay_id: bays.first().copied(),
+                    start_time: Some(format_time(start)),
+                    duration_minutes: duration,
+                }));
+            }
+        } else if used_units(&mut *conn, hours, date).await? < hours.capacity {
+            return Ok(Some(Placement {
+                date,
+                bay_id: None,
+                start_time: None,
+                duration_minutes: duration,
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Places a booking on a fixed day. Bay garages need a free bay for the
+/// whole duration: the requested start time and bay are honoured when given,
+/// otherwise the earliest free slot is taken. `exclude_maintenance_id` lets a
+/// booking keep its own slot when it is edited.
+pub async fn place_on_day(
+    conn: &mut SqliteConnection,
+    hours: &GarageHours,
+    date: NaiveDate,
+    service_type: &str,
+    start_time: Option<&str>,
+    preferred_bay: Option<i64>,
+    exclude_maintenance_id: Option<i64>,
+) -> Result<Result<Placement, SlotError>, sqlx::Error> {
+    let duration = duration_for(&mut *conn, service_type, hours.slot_minutes).await?;
+
+    let start = match start_time {
+        Some(value) => match parse_time(value) {
+            Some(start) => Some(start),
+            None => {
+                return Ok(Err(SlotError::Window(format!(
+                    "Start time '{}' must be formatted as HH:MM",
+                    value
+                ))));
+            }
+        },
+        None => None,
+    };
+
+    if let Some(message) = start.and_then(|start| hours.window_error(start, duration)) {
+        return Ok(Err(SlotError::Window(message)));
+    }
+
+    if !hours.uses_bays() {
+        return Ok(Ok(Placement {
+            date,
+            bay_id: None,
+            start_time: start.map(format_time),
+            duration_minutes: duration,
+        }));
+    }
+
+    let placed = match start {
+        Some(start) => find_free_bay(
+            &mut *conn,
+            hours,
+            date,
+            start,
+            duration,
+            preferred_bay,
+            exclude_maintenance_id,
+        )
+        .await?
+        .map(|bay_id| (start, bay_id)),
+        None => free_slots(&mut *conn, hours, date, duration, exclude_maintenance_id)
+            .await?
+            .into_iter()
+            .find_map(|(start, bays)| match preferred_bay {
+                Some(bay_id) => bays.contains(&bay_id).then_some((start, bay_id)),
+                None => bays.first().map(|bay_id| (start, *bay_id)),
+            }),
+    };
+
+    Ok(match placed {
+        Some((start, bay_id)) => Ok(Placement {
+            date,
+            bay_id: Some(bay_id),
+            start_time: Some(format_time(start)),
+            duration_minutes: duration,
+        }),
+        None => Err(SlotError::Taken(match (start, preferred_bay) {
+            (Some(start), Some(bay_id)) => format!(
+                "Bay {} is not free on {} at {} for {} minutes",
+                bay_id,
+                date.format(DATE_FORMAT),
+                format_time(start),
+                duration
+            ),
+            (Some(start), None) => format!(
+                "No bay is free on {} at {} for {} minutes",
+                date.format(DATE_FORMAT),
+                format_time(start),
+                duration
+            ),
+            (None, _) => format!(
+                "No free slot on {} for {} minutes",
+                date.format(DATE_FORMAT),
+                duration
+            ),
+        })),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 08:00-12:00 in hour slots with bays 1 and 2.
+    fn hours() -> GarageHours {
+        GarageHours {
+            garage_id: 1,
+            capacity: 3,
+            opening: 8 * 60,
+            closing: 12 * 60,
+            slot_minutes: 60,
+            bays: vec![1, 2],
+        }
+    }
+
+    fn booked(bay_id: i64, start: &str, duration: i64) -> BookedInterval {
+        let start = parse_time(start).unwrap();
+        BookedInterval {
+            bay_id,
+            start,
+            end: start + duration,
+        }
+    }
+
+    #[test]
+    fn parses_and_formats_times() {
+        assert_eq!(parse_time("08:30"), Some(510));
+        assert_eq!(parse_time(" 00:00 "), Some(0));
+        assert_eq!(parse_time("23:59"), Some(1439));
+        assert_eq!(parse_time("24:00"), None);
+        assert_eq!(parse_time("8h"), None);
+        assert_eq!(format_time(510), "08:30");
+        assert_eq!(format_time(0), "00:00");
+    }
+
+    #[test]
+    fn counts_whole_slots_between_opening_and_closing() {
+        assert_eq!(hours().slots_per_day(), 4);
+        let ragged = GarageHours { closing: 12 * 60 + 45, ..hours() };
+        assert_eq!(ragged.slots_per_day(), 4);
+        let reversed = GarageHours { opening: 12 * 60, closing: 8 * 60, ..hours() };
+        assert_eq!(reversed.slots_per_day(), 0);
+        let no_grid = GarageHours { slot_minutes: 0, ..hours() };
+        assert_eq!(no_grid.slots_per_day(), 0);
+    }
+
+    #[test]
+    fn rounds_durations_up_to_whole_slots() {
+        let hours = hours();
+        assert_eq!(hours.units_for(60), 1);
+        assert_eq!(hours.units_for(61), 2);
+        assert_eq!(hours.units_for(120), 2);
+        assert_eq!(hours.units_for(0), 1);
+        assert_eq!(hours.units_for(-30), 1);
+    }
+
+    #[test]
+    fn measures_capacity_in_slots_only_with_bays() {
+        assert_eq!(hours().daily_capacity(), 8);
+        let without_bays = GarageHours { bays: Vec::new(), ..hours() };
+        assert!(!without_bays.uses_bays());
+        assert_eq!(without_bays.daily_capacity(), 3);
+    }
+
+    #[test]
+    fn accepts_windows_on_the_grid_within_opening_hours() {
+        let hours = hours();
+        assert_eq!(hours.window_error(8 * 60, 60), None);
+        assert_eq!(hours.window_error(10 * 60, 120), None);
+    }
+
+    #[test]
+    fn rejects_windows_outside_opening_hours() {
+        let hours = hours();
+        assert!(hours.window_error(7 * 60, 60).unwrap().contains("outside opening hours 08:00-12:00"));
+        assert!(hours.window_error(11 * 60, 120).unwrap().contains("outside opening hours"));
+    }
+
+    #[test]
+    fn rejects_windows_off_the_slot_grid() {
+        let error = hours().window_error(8 * 60 + 30, 60).unwrap();
+        assert!(error.contains("not aligned to 60-minute slots from 08:00"), "{}", error);
+    }
+
+    #[test]
+    fn treats_touching_bookings_as_free() {
+        let booked = [booked(1, "09:00", 60)];
+        assert!(bay_is_free(&booked, 1, 8 * 60, 9 * 60));
+        assert!(bay_is_free(&booked, 1, 10 * 60, 11 * 60));
+        assert!(!bay_is_free(&booked, 1, 8 * 60 + 30, 9 * 60 + 30));
+        assert!(bay_is_free(&booked, 2, 9 * 60, 10 * 60));
+    }
+
+    #[test]
+    fn lists_free_bays_for_every_start() {
+        let slots = open_slots(&hours(), &[booked(1, "09:00", 60)], 60);
+        assert_eq!(
+            slots,
+            vec![(480, vec![1, 2]), (540, vec![2]), (600, vec![1, 2]), (660, vec![1, 2])]
+        );
+    }
+
+    #[test]
+    fn needs_a_bay_free_for_the_whole_duration() {
+        let booked = [booked(1, "10:00", 60), booked(2, "08:00", 120)];
+        let slots = open_slots(&hours(), &booked, 120);
+        // From 09:00 bay 1 runs into its 10:00 booking and bay 2 is busy until 10:00.
+        assert_eq!(slots, vec![(480, vec![1]), (600, vec![2])]);
+    }
+
+    #[test]
+    fn leaves_out_starts_that_would_run_past_closing() {
+        let slots = open_slots(&hours(), &[], 180);
+        assert_eq!(slots.iter().map(|(start, _)| *start).collect::<Vec<_>>(), vec![480, 540]);
+    }
+
+    #[test]
+    fn has_no_slots_without_a_grid() {
+        let hours = GarageHours { slot_minutes: 0, ..hours() };
+        assert!(open_slots(&hours, &[], 60).is_empty());
+    }
+}
@@ -0,0 +1,340 @@
+use chrono::{Datelike, Local};
+use serde::Serialize;
+
+const VIN_LENGTH: usize = 17;
+/// Position of the check digit, counted from zero.
+const CHECK_DIGIT_INDEX: usize = 8;
+const MODEL_YEAR_INDEX: usize = 9;
+const WEIGHTS: [u32; VIN_LENGTH] = [8, 7, 6, 5, 4, 3, 2, 10, 0, 9, 8, 7, 6, 5, 4, 3, 2];
+/// Model year codes in order from 1980 (`A`); the cycle repeats every 30
+/// years, so `A` is also 2010.
+const MODEL_YEAR_CODES: &str = "ABCDEFGHJKLMNPRSTVWXY123456789";
+const MODEL_YEAR_BASE: i32 = 1980;
+const MODEL_YEAR_CYCLE: i32 = 30;
+const NORTH_AMERICA: &str = "North America";
+
+/// World manufacturer identifiers (the first three characters) the
+/// decoder knows: manufacturer and the make it sells the car as.
+const MANUFACTURERS: &[(&str, &str, &str)] = &[
+    ("1FA", "Ford Motor Company", "Ford"),
+    ("1FM", "Ford Motor Company", "Ford"),
+    ("1FT", "Ford Motor Company", "Ford"),
+    ("1G1", "General Motors", "Chevrolet"),
+    ("1GC", "General Motors", "Chevrolet"),
+    ("1HG", "Honda of America", "Honda"),
+    ("1N4", "Nissan North America", "Nissan"),
+    ("2HG", "Honda of Canada", "Honda"),
+    ("2T1", "Toyota Motor Manufacturing Canada", "Toyota"),
+    ("3VW", "Volkswagen de México", "Volkswagen"),
+    ("4T1", "Toyota Motor Manufacturing Kentucky", "Toyota"),
+    ("5YJ", "Tesla", "Tesla"),
+    ("JHM", "Honda", "Honda"),
+    ("JMZ", "Mazda", "Mazda"),
+    ("JN1", "Nissan", "Nissan"),
+    ("JT2", "Toyota", "Toyota"),
+    ("JTD", "Toyota", "Toyota"),
+    ("JTE", "Toyota", "Toyota"),
+    ("KMH", "Hyundai", "Hyundai"),
+    ("KNA", "Kia", "Kia"),
+    ("SAJ", "Jaguar Land Rover", "Jaguar"),
+    ("SAL", "Jaguar Land Rover", "Land Rover"),
+    ("SCC", "Lotus Cars", "Lotus"),
+    ("TMB", "Škoda Auto", "Škoda"),
+    ("TRU", "Audi Hungaria", "Audi"),
+    ("UU1", "Dacia", "Dacia"),
+    ("VF1", "Renault", "Renault"),
+    ("VF3", "Peugeot", "Peugeot"),
+    ("VF7", "Citroën", "Citroën"),
+    ("VR3", "Peugeot", "Peugeot"),
+    ("VSS", "SEAT", "SEAT"),
+    ("W0L", "Opel", "Opel"),
+    ("WAU", "Audi", "Audi"),
+    ("WBA", "BMW", "BMW"),
+    ("WBS", "BMW M", "BMW"),
+    ("WDB", "Mercedes-Benz", "Mercedes-Benz"),
+    ("WDD", "Mercedes-Benz", "Mercedes-Benz"),
+    ("W1K", "Mercedes-Benz", "Mercedes-Benz"),
+    ("WF0", "Ford Germany", "Ford"),
+    ("WME", "Smart", "Smart"),
+    ("WP0", "Porsche", "Porsche"),
+    ("WVW", "Volkswagen", "Volkswagen"),
+    ("WV1", "Volkswagen Commercial Vehicles", "Volkswagen"),
+    ("WV2", "Volkswagen Commercial Vehicles", "Volkswagen"),
+    ("YS3", "Saab", "Saab"),
+    ("YV1", "Volvo Cars", "Volvo"),
+    ("ZAR", "Alfa Romeo", "Alfa Romeo"),
+    ("ZFA", "Fiat", "Fiat"),
+    ("ZFF", "Ferrari", "Ferrari"),
+];
+
+/// Short names the decoded make is also known by, e.g. `VW`.
+const MAKE_ALIASES: &[(&str, &str)] = &[
+    ("vw", "volkswagen"),
+    ("mercedes", "mercedesbenz"),
+    ("benz", "mercedesbenz"),
+    ("alfa", "alfaromeo"),
+    ("chevy", "chevrolet"),
+];
+
+/// What an offline decode can tell from a VIN. The model year is a best
+/// guess outside North America, where the year code is not mandatory.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedVin {
+    pub vin: String,
+    pub wmi: String,
+    pub region: &'static str,
+    pub manufacturer: Option<&'static str>,
+    pub make: Option<&'static str>,
+    pub model_year: Option<i32>,
+}
+
+/// ISO 3780 transliteration of a VIN character for the check digit.
+fn value(c: char) -> Option<u32> {
+    match c {
+        '0'..='9' => c.to_digit(10),
+        'A' | 'J' => Some(1),
+        'B' | 'K' | 'S' => Some(2),
+        'C' | 'L' | 'T' => Some(3),
+        'D' | 'M' | 'U' => Some(4),
+        'E' | 'N' | 'V' => Some(5),
+        'F' | 'W' => Some(6),
+        'G' | 'P' | 'X' => Some(7),
+        'H' | 'Y' => Some(8),
+        'R' | 'Z' => Some(9),
+        // I, O and Q are never used, so they cannot be mistaken for 1 and 0.
+        _ => None,
+    }
+}
+
+/// The character the ninth position must hold: the weighted sum of all
+/// characters modulo 11, with 10 written as `X`.
+fn check_digit(vin: &str) -> Option<char> {
+    let mut sum = 0;
+    for (c, weight) in vin.chars().zip(WEIGHTS) {
+        sum += value(c)? * weight;
+    }
+    match sum % 11 {
+        10 => Some('X'),
+        digit => char::from_digit(digit, 10),
+    }
+}
+
+/// Upper-cases the VIN and drops spaces and hyphens, then checks its
+/// length and alphabet, and the check digit of North American VINs.
+pub fn normalize(vin: &str) -> Result<String, String> {
+    let normalized: String = vin
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    if normalized.chars().count() != VIN_LENGTH {
+        return Err(format!("vin '{}' must have {} characters", vin.trim(), VIN_LENGTH));
+    }
+    if let Some(c) = normalized.chars().find(|c| value(*c).is_none()) {
+        return Err(format!(
+            "vin '{}' contains '{}'; only digits and letters other than I, O and Q are allowed",
+            vin.trim(),
+            c
+        ));
+    }
+    // Only North America makes the check digit mandatory; elsewhere the
+    // ninth character is often just a filler such as `Z`.
+    let first = normalized.chars().next().expect("length was checked above");
+    if region(first) != NORTH_AMERICA {
+        return Ok(normalized);
+    }
+    let expected = check_digit(&normalized).expect("characters were checked above");
+    let actual = normalized.chars().nth(CHECK_DIGIT_INDEX).expect("length was checked above");
+    if actual != expected {
+        return Err(format!(
+            "vin '{}' has check digit '{}' but its characters give '{}'",
+            vin.trim(),
+            actual,
+            expected
+        ));
+    }
+
+    Ok(normalized)
+}
+
+fn region(first: char) -> &'static str {
+    match first {
+        'A'..='H' => "Africa",
+        'J'..='R' => "Asia",
+        'S'..='Z' => "Europe",
+        '1'..='5' => NORTH_AMERICA,
+        '6' | '7' => "Oceania",
+        _ => "South America",
+    }
+}
+
+/// North American cars use a digit in position 7 for model years up to
+/// 2009 and a letter from 2010. Elsewhere the latest year that is not in
+/// the future is taken.
+fn model_year(vin: &[char], north_american: bool) -> Option<i32> {
+    let offset = MODEL_YEAR_CODES.chars().position(|code| code == vin[MODEL_YEAR_INDEX])? as i32;
+    let first = MODEL_YEAR_BASE + offset;
+    let second = first + MODEL_YEAR_CYCLE;
+
+    if north_american {
+        return Some(if vin[6].is_ascii_digit() { first } else { second });
+    }
+    let latest = Local::now().year() + 1;
+    Some(if second <= latest { second } else { first })
+}
+
+/// Decodes a VIN already checked by `normalize`.
+pub fn decode(vin: &str) -> DecodedVin {
+    let chars: Vec<char> = vin.chars().collect();
+    let wmi: String = chars[..3].iter().collect();
+    let region = region(chars[0]);
+    let known = MANUFACTURERS.iter().find(|(code, _, _)| *code == wmi);
+
+    DecodedVin {
+        vin: vin.to_string(),
+        model_year: model_year(&chars, region == NORTH_AMERICA),
+        wmi,
+        region,
+        manufacturer: known.map(|(_, manufacturer, _)| *manufacturer),
+        make: known.map(|(_, _, make)| *make),
+    }
+}
+
+/// Letters and digits only, lower case and without accents, so `VW`,
+/// `Volkswagen` and `volkswagen` compare equal.
+fn make_key(make: &str) -> String {
+    let key: String = make
+        .chars()
+        .filter_map(|c| match c {
+            'š' | 'Š' => Some('s'),
+            'ë' | 'é' | 'è' => Some('e'),
+            c if c.is_ascii_alphanumeric() => Some(c.to_ascii_lowercase()),
+            _ => None,
+        })
+        .collect();
+    MAKE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == key)
+        .map_or(key, |(_, make)| make.to_string())
+}
+
+/// Explains why a car's make or production year contradicts its VIN. The
+/// year is only checked where the year code is mandatory, and the model
+/// year may be one year off the production year either way.
+pub fn mismatch(decoded: &DecodedVin, make: &str, production_year: i64) -> Option<String> {
+    if let Some(vin_make) = decoded.make {
+        if make_key(vin_make) != make_key(make) {
+            return Some(format!("vin {} belongs to a {}, not a {}", decoded.vin, vin_make, make.trim()));
+        }
+    }
+    let year_is_mandatory = decoded.region == NORTH_AMERICA;
+    if let Some(year) = decoded.model_year.filter(|_| year_is_mandatory) {
+        if (i64::from(year) - production_year).abs() > 1 {
+            return Some(format!(
+                "vin {} is for model year {}, which does not fit productionYear {}",
+                decoded.vin, year, production_year
+            ));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_a_valid_north_american_vin() {
+        assert_eq!(normalize("1HGCM82633A004352").unwrap(), "1HGCM82633A004352");
+    }
+
+    #[test]
+    fn accepts_x_as_check_digit() {
+        assert_eq!(check_digit("1M8GDM9AXKP042788"), Some('X'));
+        assert!(normalize("1M8GDM9AXKP042788").is_ok());
+    }
+
+    #[test]
+    fn normalizes_case_spaces_and_hyphens() {
+        assert_eq!(normalize(" 1hgcm826-33a 004352 ").unwrap(), "1HGCM82633A004352");
+    }
+
+    #[test]
+    fn rejects_a_wrong_check_digit_in_north_america() {
+        let err = normalize("1HGCM82643A004352").unwrap_err();
+        assert!(err.contains("check digit '4'"), "{}", err);
+    }
+
+    #[test]
+    fn ignores_the_check_digit_elsewhere() {
+        assert_eq!(normalize("WVWZZZ1JZ3W386752").unwrap(), "WVWZZZ1JZ3W386752");
+    }
+
+    #[test]
+    fn rejects_i_o_and_q() {
+        for vin in ["IVWZZZ1JZ3W386752", "WVWZZZ1JZ3W38675O", "WVWZZZ1JZ3W3Q6752"] {
+            assert!(normalize(vin).is_err(), "{} was accepted", vin);
+        }
+    }
+
+    #[test]
+    fn rejects_the_wrong_length() {
+        assert!(normalize("1HGCM82633A00435").is_err());
+        assert!(normalize("1HGCM82633A0043521").is_err());
+    }
+
+    #[test]
+    fn decodes_a_known_manufacturer() {
+        let decoded = decode("1HGCM82633A004352");
+        assert_eq!(decoded.wmi, "1HG");
+        assert_eq!(decoded.region, NORTH_AMERICA);
+        assert_eq!(decoded.make, Some("Honda"));
+        assert_eq!(decoded.model_year, Some(2003));
+    }
+
+    #[test]
+    fn decodes_a_european_vin() {
+        let decoded = decode("WVWZZZ1JZ3W386752");
+        assert_eq!(decoded.region, "Europe");
+        assert_eq!(decoded.make, Some("Volkswagen"));
+        assert_eq!(decoded.model_year, Some(2003));
+    }
+
+    #[test]
+    fn reads_the_year_cycle_from_position_seven() {
+        // Same year code `K`: a digit in position 7 means 1989, a letter 2019.
+        assert_eq!(decode("1M8GDM9AXKP042788").model_year, Some(1989));
+        assert_eq!(decode("1HGCM8A6XKA004352").model_year, Some(2019));
+    }
+
+    #[test]
+    fn leaves_unknown_manufacturers_empty() {
+        let decoded = decode("1M8GDM9AXKP042788");
+        assert_eq!(decoded.manufacturer, None);
+        assert_eq!(decoded.make, None);
+    }
+
+    #[test]
+    fn matches_make_aliases() {
+        let decoded = decode("WVWZZZ1JZ3W386752");
+        assert_eq!(mismatch(&decoded, "VW", 2003), None);
+        assert_eq!(mismatch(&decoded, "volkswagen", 2003), None);
+        assert!(mismatch(&decoded, "Toyota", 2003).is_some());
+    }
+
+    #[test]
+    fn allows_one_year_between_model_and_production_year() {
+        let decoded = decode("1HGCM82633A004352");
+        assert_eq!(mismatch(&decoded, "Honda", 2002), None);
+        assert_eq!(mismatch(&decoded, "Honda", 2004), None);
+        assert!(mismatch(&decoded, "Honda", 2006).is_some());
+    }
+
+    #[test]
+    fn skips_the_year_check_outside_north_america() {
+        let decoded = decode("WVWZZZ1JZ3W386752");
+        assert_eq!(mismatch(&decoded, "Volkswagen", 2010), None);
+    }
+}
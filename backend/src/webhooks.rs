@@ -0,0 +1,165 @@
+use crate::jobs;
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use log::warn;
+use sha2::Sha256;
+use sqlx::SqlitePool;
+
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+
+/// Deliveries still failing after this many attempts are given up on.
+pub const MAX_ATTEMPTS: i64 = 8;
+
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+/// Most deliveries attempted per `dispatch_due` call.
+pub const BATCH_SIZE: i64 = 50;
+/// Receivers can be down for a while, so deliveries back off longer than jobs.
+const MAX_RETRY_SECONDS: i64 = 6 * 60 * 60;
+const SQLITE_TIMESTAMP: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Hex HMAC-SHA256 of `"{timestamp}.{body}"`. Receivers recompute it with
+/// their secret and reject stale timestamps to stop replays.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// HTTP client for deliveries; slow endpoints time out instead of holding
+/// up a job worker.
+pub fn client() -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()
+}
+
+pub fn generate_secret() -> String {
+    format!("whsec_{}", uuid::Uuid::new_v4().simple())
+}
+
+/// Longest a batch can take to send, every request timing out.
+fn claim_duration() -> Duration {
+    Duration::from_std(REQUEST_TIMEOUT * BATCH_SIZE as u32).expect("claim fits in a chrono duration")
+}
+
+enum Outcome {
+    Delivered(i64),
+    Failed(Option<i64>, String),
+}
+
+async fn send(client: &reqwest::Client, delivery_id: i64, url: &str, secret: &str, event_type: &str, payload: &str) -> Outcome {
+    let timestamp = Utc::now().timestamp();
+    let signature = sign(secret, timestamp, payload);
+
+    let response = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(SIGNATURE_HEADER, format!("sha256={}", signature))
+        .header(TIMESTAMP_HEADER, timestamp.to_string())
+        .header(EVENT_HEADER, event_type)
+        .header(DELIVERY_HEADER, delivery_id.to_string())
+        .body(payload.to_string())
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status().is_success() => Outcome::Delivered(i64::from(response.status().as_u16())),
+        Ok(response) => Outcome::Failed(
+            Some(i64::from(response.status().as_u16())),
+            format!("Endpoint answered {}", response.status()),
+        ),
+        Err(err) => Outcome::Failed(None, err.to_string()),
+    }
+}
+
+/// Sends every pending delivery that is due and records the outcome.
+/// Returns how many were attempted.
+pub async fn dispatch_due(pool: &SqlitePool, client: &reqwest::Client) -> Result<usize, sqlx::Error> {
+    // Claim the batch first by moving it out of reach of other dispatchers
+    // for as long as sending it can take. Deliveries left behind by a crash
+    // become due again once the claim runs out.
+    let claim_until = (Utc::now() + claim_duration()).format(SQLITE_TIMESTAMP).to_string();
+    let claimed = sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries
+        SET next_attempt_at = ?1
+        WHERE id IN (
+            SELECT webhook_deliveries.id
+            FROM webhook_deliveries
+            JOIN webhooks ON webhooks.id = webhook_deliveries.webhook_id
+            WHERE webhook_deliveries.status = 'PENDING'
+              AND webhook_deliveries.next_attempt_at <= datetime('now')
+              AND webhooks.active = 1
+            ORDER BY webhook_deliveries.next_attempt_at, webhook_deliveries.id
+            LIMIT ?2
+        )
+        RETURNING id AS "id!", webhook_id, event_type, payload, attempts
+        "#,
+        claim_until,
+        BATCH_SIZE
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut due = Vec::with_capacity(claimed.len());
+    for delivery in claimed {
+        let webhook = sqlx::query!("SELECT url, secret FROM webhooks WHERE id = ?", delivery.webhook_id)
+            .fetch_optional(pool)
+            .await?;
+        // Deleted since the claim, and its deliveries with it.
+        if let Some(webhook) = webhook {
+            due.push((delivery, webhook));
+        }
+    }
+    due.sort_by_key(|(delivery, _)| delivery.id);
+
+    for (delivery, webhook) in &due {
+        let attempts = delivery.attempts + 1;
+        match send(client, delivery.id, &webhook.url, &webhook.secret, &delivery.event_type, &delivery.payload).await {
+            Outcome::Delivered(status) => {
+                sqlx::query!(
+                    r#"
+                    UPDATE webhook_deliveries
+                    SET status = 'DELIVERED', attempts = ?, response_status = ?, last_error = NULL,
+                        last_attempt_at = datetime('now'), delivered_at = datetime('now')
+                    WHERE id = ?
+                    "#,
+                    attempts,
+                    status,
+                    delivery.id
+                )
+                .execute(pool)
+                .await?;
+            }
+            Outcome::Failed(status, message) => {
+                let gave_up = attempts >= MAX_ATTEMPTS;
+                let next_status = if gave_up { "FAILED" } else { "PENDING" };
+                let next_attempt_at = (Utc::now() + jobs::retry_delay(attempts, MAX_RETRY_SECONDS)).format(SQLITE_TIMESTAMP).to_string();
+                if gave_up {
+                    warn!("Giving up on webhook delivery {} after {} attempts: {}", delivery.id, attempts, message);
+                }
+                sqlx::query!(
+                    r#"
+                    UPDATE webhook_deliveries
+                    SET status = ?, attempts = ?, response_status = ?, last_error = ?,
+                        last_attempt_at = datetime('now'), next_attempt_at = ?
+                    WHERE id = ?
+                    "#,
+                    next_status,
+                    attempts,
+                    status,
+                    message,
+                    next_attempt_at,
+                    delivery.id
+                )
+                .execute(pool)
+                .await?;
+            }
+        }
+    }
+
+    Ok(due.len())
+}